@@ -0,0 +1,224 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const USAGE: &str = "Usage: aoc new <day> | aoc run <day> | aoc gen <day> <size> [seed] | aoc list";
+
+
+// The repository root, which holds the workspace manifest and dayTEMPLATE
+fn get_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_path_buf()
+}
+
+fn parse_day(string: &str) -> Result<u32, String> {
+    match string.trim_start_matches("day").parse::<u32>() {
+        Ok(day) if (1..=25).contains(&day) => Ok(day),
+        _ => Err(format!("'{}' is not a day between 1 and 25", string)),
+    }
+}
+
+// Days are registered by being members of the workspace, which is also the
+// list the runner works from.
+fn get_registered_days(manifest: &str) -> Vec<u32> {
+    let mut days: Vec<u32> = manifest.lines()
+        .filter_map(|line| line.trim().trim_end_matches(',').trim_matches('"').strip_prefix("day"))
+        .filter_map(|day| day.parse::<u32>().ok())
+        .collect();
+    days.sort_unstable();
+    days
+}
+
+// The manifest with `day<day>` added to the members list, keeping the days in order
+fn register_day(manifest: &str, day: u32) -> Result<String, String> {
+    if get_registered_days(manifest).contains(&day) {
+        return Err(format!("day{} is already a workspace member", day));
+    }
+    let lines: Vec<&str> = manifest.lines().collect();
+    let start = lines.iter().position(|line| line.trim_start().starts_with("members = ["))
+        .ok_or("The workspace manifest has no members list")?;
+    let end = start + lines[start..].iter().position(|line| line.trim() == "]")
+        .ok_or("The workspace members list is not closed")?;
+    // Before the first later day, or at the end of the list
+    let position = (start + 1..end)
+        .find(|i| get_registered_days(lines[*i]).first().is_some_and(|other| *other > day))
+        .unwrap_or(end);
+    let mut updated: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    updated.insert(position, format!("    \"day{}\",", day));
+    Ok(updated.join("\n") + "\n")
+}
+
+fn get_day_manifest(day: u32) -> String {
+    format!(
+        "[package]\nname = \"day{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+        # See more keys and their definitions at https://doc.rust-lang.org/cargo/reference/manifest.html\n\n\
+        [dependencies]\naoc_coords = {{ path = \"../aoc_coords\" }}\naoc_graph = {{ path = \"../aoc_graph\" }}\n",
+        day,
+    )
+}
+
+// Creates `day<day>` from dayTEMPLATE with empty input files, and adds it to the workspace
+fn new_day(root: &Path, day: u32) -> Result<PathBuf, String> {
+    let directory = root.join(format!("day{}", day));
+    if directory.exists() {
+        return Err(format!("{} already exists", directory.display()));
+    }
+    let manifest_path = root.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path).map_err(|e| format!("Reading {}: {}", manifest_path.display(), e))?;
+    let manifest = register_day(&manifest, day)?;
+    let template = root.join("dayTEMPLATE");
+    let main = fs::read_to_string(template.join("main.rs")).map_err(|e| format!("Reading the template: {}", e))?;
+    let parser = fs::read_to_string(template.join("aoc_parser.rs")).map_err(|e| format!("Reading the template: {}", e))?;
+
+    let write = |path: PathBuf, contents: &str| fs::write(&path, contents).map_err(|e| format!("Writing {}: {}", path.display(), e));
+    fs::create_dir_all(directory.join("src")).map_err(|e| format!("Creating {}: {}", directory.display(), e))?;
+    write(directory.join("Cargo.toml"), &get_day_manifest(day))?;
+    write(directory.join("src").join("main.rs"), &main)?;
+    write(directory.join("src").join("aoc_parser.rs"), &parser)?;
+    write(directory.join("input.txt"), "")?;
+    write(directory.join("example.txt"), "")?;
+    write(manifest_path, &manifest)?;
+    Ok(directory)
+}
+
+fn run_day(root: &Path, day: u32) -> Result<(), String> {
+    let manifest = fs::read_to_string(root.join("Cargo.toml")).map_err(|e| e.to_string())?;
+    if !get_registered_days(&manifest).contains(&day) {
+        return Err(format!("day{} is not registered, create it with 'aoc new {}'", day, day));
+    }
+    // Days that read their input at runtime get the puzzle input on stdin
+    let directory = root.join(format!("day{}", day));
+    let input = fs::File::open(directory.join("input.txt")).map_err(|e| format!("Opening the input: {}", e))?;
+    let status = Command::new("cargo")
+        .args(["run", "--release", "-p", &format!("day{}", day)])
+        .current_dir(&directory)
+        .stdin(input)
+        .status()
+        .map_err(|e| format!("Running cargo: {}", e))?;
+    if status.success() { Ok(()) } else { Err(format!("day{} exited with {}", day, status)) }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let root = get_root();
+    match args {
+        [command, day] if command == "new" => {
+            let directory = new_day(&root, parse_day(day)?)?;
+            println!("Created {}", directory.display());
+            Ok(())
+        }
+        [command, day] if command == "run" => run_day(&root, parse_day(day)?),
+        [command, day, size, seed @ ..] if command == "gen" && seed.len() <= 1 => {
+            let size = size.parse::<usize>().map_err(|_| format!("'{}' is not a size", size))?;
+            let seed = match seed.first() {
+                Some(seed) => seed.parse::<u64>().map_err(|_| format!("'{}' is not a seed", seed))?,
+                None => 0,
+            };
+            print!("{}", aoc_gen::generate(parse_day(day)?, size, seed)?);
+            Ok(())
+        }
+        [command] if command == "list" => {
+            let manifest = fs::read_to_string(root.join("Cargo.toml")).map_err(|e| e.to_string())?;
+            for day in get_registered_days(&manifest) {
+                println!("day{}", day);
+            }
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+
+#[cfg(test)]
+const MANIFEST: &str = "[workspace]\nresolver = \"2\"\nmembers = [\n    \"aoc\",\n    \"day1\",\n    \"day2\",\n    \"day10\",\n]\n";
+
+#[test]
+fn test_register_day() {
+    assert_eq!(get_registered_days(MANIFEST), vec![1, 2, 10]);
+    let updated = register_day(MANIFEST, 3).unwrap();
+    assert!(updated.contains("    \"day2\",\n    \"day3\",\n    \"day10\",\n"));
+    let updated = register_day(&updated, 11).unwrap();
+    assert!(updated.ends_with("    \"day10\",\n    \"day11\",\n]\n"));
+    assert_eq!(get_registered_days(&updated), vec![1, 2, 3, 10, 11]);
+    assert!(register_day(MANIFEST, 2).is_err());
+    assert!(register_day("[workspace]\n", 2).is_err());
+    assert_eq!(parse_day("day7"), Ok(7));
+    assert!(parse_day("26").is_err());
+    assert!(parse_day("x").is_err());
+}
+
+#[test]
+fn test_new_day() {
+    let root = std::env::temp_dir().join(format!("aoc_new_day_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("dayTEMPLATE")).unwrap();
+    fs::write(root.join("Cargo.toml"), MANIFEST).unwrap();
+    fs::write(root.join("dayTEMPLATE").join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("dayTEMPLATE").join("aoc_parser.rs"), "// parser\n").unwrap();
+
+    let directory = new_day(&root, 5).unwrap();
+    assert_eq!(fs::read_to_string(directory.join("src").join("main.rs")).unwrap(), "fn main() {}\n");
+    assert_eq!(fs::read_to_string(directory.join("src").join("aoc_parser.rs")).unwrap(), "// parser\n");
+    assert!(fs::read_to_string(directory.join("Cargo.toml")).unwrap().contains("name = \"day5\""));
+    assert!(fs::read_to_string(directory.join("Cargo.toml")).unwrap().contains("aoc_coords = { path = \"../aoc_coords\" }"));
+    assert_eq!(fs::read_to_string(directory.join("input.txt")).unwrap(), "");
+    assert_eq!(fs::read_to_string(directory.join("example.txt")).unwrap(), "");
+    assert_eq!(get_registered_days(&fs::read_to_string(root.join("Cargo.toml")).unwrap()), vec![1, 2, 5, 10]);
+
+    // Nothing is touched the second time round
+    fs::write(directory.join("input.txt"), "puzzle").unwrap();
+    assert!(new_day(&root, 5).is_err());
+    assert_eq!(fs::read_to_string(directory.join("input.txt")).unwrap(), "puzzle");
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_template_is_registered_layout() {
+    // The real template has the files new_day copies
+    let template = get_root().join("dayTEMPLATE");
+    assert!(template.join("main.rs").exists());
+    assert!(template.join("aoc_parser.rs").exists());
+}
+
+#[cfg(test)]
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let path = entry.unwrap().path();
+        let target = to.join(path.file_name().unwrap());
+        if path.is_dir() {
+            copy_dir(&path, &target);
+        } else {
+            fs::copy(&path, &target).unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_new_day_builds() {
+    // A workspace with the real template and the crates new days depend on
+    let root = std::env::temp_dir().join(format!("aoc_new_day_build_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("Cargo.toml"), "[workspace]\nresolver = \"2\"\nmembers = [\n    \"aoc_coords\",\n    \"aoc_graph\",\n]\n").unwrap();
+    for directory in ["dayTEMPLATE", "aoc_coords", "aoc_graph"] {
+        copy_dir(&get_root().join(directory), &root.join(directory));
+    }
+
+    new_day(&root, 5).unwrap();
+    let cargo = std::env::var("CARGO").unwrap_or("cargo".to_string());
+    let output = Command::new(cargo)
+        .args(["build", "--offline", "-p", "day5"])
+        .current_dir(&root)
+        .env("CARGO_TARGET_DIR", root.join("target"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    fs::remove_dir_all(&root).unwrap();
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(error) = run(&args) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,320 @@
+// Generic 2D and 3D vectors for the grid and space puzzles. The days each grew
+// their own coordinate struct, and those convert to and from these, so a day can
+// move over to the arithmetic here without touching the rest of its code.
+//
+// On a grid x is the column and y is the row, counting down from the top. The
+// vectors have no Ord of their own, since a BTreeSet of them needs to know which
+// way round it sorts: wrap them in RowMajor or ByZ for that.
+use std::cmp::Ordering;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+// The number types a vector can hold, signed or not
+pub trait Scalar: Copy + Ord + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {
+    const ZERO: Self;
+    const ONE: Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty),*) => {
+        $(impl Scalar for $t {
+            const ZERO: $t = 0;
+            const ONE: $t = 1;
+            fn checked_add(self, other: $t) -> Option<$t> {
+                <$t>::checked_add(self, other)
+            }
+            fn checked_sub(self, other: $t) -> Option<$t> {
+                <$t>::checked_sub(self, other)
+            }
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        })*
+    };
+}
+
+impl_scalar!(i32, i64, isize, u32, u64, usize);
+
+// Works for unsigned types, where a - b would underflow
+fn difference<T: Scalar>(a: T, b: T) -> T {
+    if a > b { a - b } else { b - a }
+}
+
+// One step of -1, 0 or +1, or None off the end of the type (below zero for usize)
+fn offset<T: Scalar>(value: T, step: i8) -> Option<T> {
+    match step {
+        -1 => value.checked_sub(T::ONE),
+        1 => value.checked_add(T::ONE),
+        _ => Some(value),
+    }
+}
+
+// Clockwise from up
+const ORTHOGONAL: [(i8, i8); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const SURROUNDING: [(i8, i8); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+// Below, the four sides, then above
+const FACES: [(i8, i8, i8); 6] = [(0, 0, -1), (0, -1, 0), (1, 0, 0), (0, 1, 0), (-1, 0, 0), (0, 0, 1)];
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Vec2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T: Scalar> Vec2<T> {
+    pub fn new(x: T, y: T) -> Vec2<T> {
+        Vec2{x, y}
+    }
+    pub fn manhattan(&self, other: &Vec2<T>) -> T {
+        difference(self.x, other.x) + difference(self.y, other.y)
+    }
+    pub fn chebyshev(&self, other: &Vec2<T>) -> T {
+        difference(self.x, other.x).max(difference(self.y, other.y))
+    }
+    pub fn euclidean(&self, other: &Vec2<T>) -> f64 {
+        let (dx, dy) = (difference(self.x, other.x).to_f64(), difference(self.y, other.y).to_f64());
+        dx.hypot(dy)
+    }
+    // The four cells sharing a side, clockwise from up, leaving out any the type can't hold
+    pub fn neighbours4(self) -> impl Iterator<Item = Vec2<T>> {
+        ORTHOGONAL.into_iter().filter_map(move |(dx, dy)| Some(Vec2::new(offset(self.x, dx)?, offset(self.y, dy)?)))
+    }
+    // The eight cells sharing a side or a corner, clockwise from up
+    pub fn neighbours8(self) -> impl Iterator<Item = Vec2<T>> {
+        SURROUNDING.into_iter().filter_map(move |(dx, dy)| Some(Vec2::new(offset(self.x, dx)?, offset(self.y, dy)?)))
+    }
+    // Top to bottom, then left to right, the order a grid is read in
+    pub fn cmp_row_major(&self, other: &Vec2<T>) -> Ordering {
+        (self.y, self.x).cmp(&(other.y, other.x))
+    }
+    pub fn cmp_column_major(&self, other: &Vec2<T>) -> Ordering {
+        (self.x, self.y).cmp(&(other.x, other.y))
+    }
+}
+
+impl<T: Scalar> Add for Vec2<T> {
+    type Output = Vec2<T>;
+    fn add(self, other: Vec2<T>) -> Vec2<T> {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Scalar> Sub for Vec2<T> {
+    type Output = Vec2<T>;
+    fn sub(self, other: Vec2<T>) -> Vec2<T> {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Scalar> Mul<T> for Vec2<T> {
+    type Output = Vec2<T>;
+    fn mul(self, scalar: T) -> Vec2<T> {
+        Vec2::new(self.x * scalar, self.y * scalar)
+    }
+}
+
+impl<T: Scalar + Neg<Output = T>> Neg for Vec2<T> {
+    type Output = Vec2<T>;
+    fn neg(self) -> Vec2<T> {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Scalar> AddAssign for Vec2<T> {
+    fn add_assign(&mut self, other: Vec2<T>) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Scalar> SubAssign for Vec2<T> {
+    fn sub_assign(&mut self, other: Vec2<T>) {
+        *self = *self - other;
+    }
+}
+
+impl<T> From<(T, T)> for Vec2<T> {
+    fn from((x, y): (T, T)) -> Vec2<T> {
+        Vec2{x, y}
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Vec3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Scalar> Vec3<T> {
+    pub fn new(x: T, y: T, z: T) -> Vec3<T> {
+        Vec3{x, y, z}
+    }
+    pub fn manhattan(&self, other: &Vec3<T>) -> T {
+        difference(self.x, other.x) + difference(self.y, other.y) + difference(self.z, other.z)
+    }
+    pub fn chebyshev(&self, other: &Vec3<T>) -> T {
+        difference(self.x, other.x).max(difference(self.y, other.y)).max(difference(self.z, other.z))
+    }
+    pub fn euclidean(&self, other: &Vec3<T>) -> f64 {
+        let (dx, dy, dz) = (difference(self.x, other.x).to_f64(), difference(self.y, other.y).to_f64(), difference(self.z, other.z).to_f64());
+        (dx * dx + dy * dy + dz * dz).sqrt()
+    }
+    // The six cells sharing a face: below, the four sides, then above
+    pub fn neighbours6(self) -> impl Iterator<Item = Vec3<T>> {
+        FACES.into_iter().filter_map(move |(dx, dy, dz)| Some(Vec3::new(offset(self.x, dx)?, offset(self.y, dy)?, offset(self.z, dz)?)))
+    }
+    // Lowest first, then by x and y, the same as day 22's z-first Coord3D
+    pub fn cmp_by_z(&self, other: &Vec3<T>) -> Ordering {
+        (self.z, self.x, self.y).cmp(&(other.z, other.x, other.y))
+    }
+    // The x and y, looking down from above
+    pub fn flatten(&self) -> Vec2<T> {
+        Vec2::new(self.x, self.y)
+    }
+}
+
+impl<T: Scalar> Add for Vec3<T> {
+    type Output = Vec3<T>;
+    fn add(self, other: Vec3<T>) -> Vec3<T> {
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl<T: Scalar> Sub for Vec3<T> {
+    type Output = Vec3<T>;
+    fn sub(self, other: Vec3<T>) -> Vec3<T> {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl<T: Scalar> Mul<T> for Vec3<T> {
+    type Output = Vec3<T>;
+    fn mul(self, scalar: T) -> Vec3<T> {
+        Vec3::new(self.x * scalar, self.y * scalar, self.z * scalar)
+    }
+}
+
+impl<T: Scalar + Neg<Output = T>> Neg for Vec3<T> {
+    type Output = Vec3<T>;
+    fn neg(self) -> Vec3<T> {
+        Vec3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl<T: Scalar> AddAssign for Vec3<T> {
+    fn add_assign(&mut self, other: Vec3<T>) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Scalar> SubAssign for Vec3<T> {
+    fn sub_assign(&mut self, other: Vec3<T>) {
+        *self = *self - other;
+    }
+}
+
+impl<T> From<(T, T, T)> for Vec3<T> {
+    fn from((x, y, z): (T, T, T)) -> Vec3<T> {
+        Vec3{x, y, z}
+    }
+}
+
+
+// A Vec2 that sorts top to bottom, then left to right
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RowMajor<T>(pub Vec2<T>);
+
+impl<T: Scalar> Ord for RowMajor<T> {
+    fn cmp(&self, other: &RowMajor<T>) -> Ordering {
+        self.0.cmp_row_major(&other.0)
+    }
+}
+
+impl<T: Scalar> PartialOrd for RowMajor<T> {
+    fn partial_cmp(&self, other: &RowMajor<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// A Vec3 that sorts lowest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ByZ<T>(pub Vec3<T>);
+
+impl<T: Scalar> Ord for ByZ<T> {
+    fn cmp(&self, other: &ByZ<T>) -> Ordering {
+        self.0.cmp_by_z(&other.0)
+    }
+}
+
+impl<T: Scalar> PartialOrd for ByZ<T> {
+    fn partial_cmp(&self, other: &ByZ<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+
+#[test]
+fn test_arithmetic_and_distances() {
+    let a: Vec2<i32> = Vec2::new(1, -2);
+    let b: Vec2<i32> = Vec2::from((4, 2));
+    assert_eq!(a + b, Vec2::new(5, 0));
+    assert_eq!(b - a, Vec2::new(3, 4));
+    assert_eq!(a * 3, Vec2::new(3, -6));
+    assert_eq!(-a, Vec2::new(-1, 2));
+    let mut c = a;
+    c += b;
+    c -= Vec2::new(1, 1);
+    assert_eq!(c, Vec2::new(4, -1));
+    assert_eq!(a.manhattan(&b), 7);
+    assert_eq!(a.chebyshev(&b), 4);
+    assert_eq!(a.euclidean(&b), 5.0);
+
+    let p: Vec3<i64> = Vec3::new(1, 2, 3);
+    let q: Vec3<i64> = Vec3::new(3, 5, -3);
+    assert_eq!(p + q, Vec3::new(4, 7, 0));
+    assert_eq!(q - p, Vec3::new(2, 3, -6));
+    assert_eq!(p * 2, Vec3::new(2, 4, 6));
+    assert_eq!(p.manhattan(&q), 11);
+    assert_eq!(p.chebyshev(&q), 6);
+    assert_eq!(p.euclidean(&q), 7.0);
+    assert_eq!(q.flatten(), Vec2::new(3, 5));
+
+    // Unsigned distances don't underflow whichever way round they are taken
+    let u: Vec2<usize> = Vec2::new(2, 9);
+    assert_eq!(u.manhattan(&Vec2::new(5, 1)), 11);
+    assert_eq!(Vec2::new(5, 1).manhattan(&u), 11);
+}
+
+#[test]
+fn test_neighbours() {
+    let centre: Vec2<i32> = Vec2::new(0, 0);
+    let sides: Vec<Vec2<i32>> = centre.neighbours4().collect();
+    assert_eq!(sides, vec![Vec2::new(0, -1), Vec2::new(1, 0), Vec2::new(0, 1), Vec2::new(-1, 0)]);
+    assert_eq!(centre.neighbours8().count(), 8);
+    assert!(centre.neighbours8().all(|n| n.chebyshev(&centre) == 1));
+    // The corner of a usize grid has nothing above or to the left
+    let corner: Vec2<usize> = Vec2::new(0, 0);
+    assert_eq!(corner.neighbours4().collect::<Vec<_>>(), vec![Vec2::new(1, 0), Vec2::new(0, 1)]);
+    assert_eq!(corner.neighbours8().count(), 3);
+    let faces: Vec<Vec3<i64>> = Vec3::new(0, 0, 1).neighbours6().collect();
+    assert_eq!(faces.len(), 6);
+    assert_eq!((faces[0], faces[5]), (Vec3::new(0, 0, 0), Vec3::new(0, 0, 2)));
+    assert_eq!(Vec3::<u32>::new(0, 0, 0).neighbours6().count(), 3);
+}
+
+#[test]
+fn test_orderings() {
+    use std::collections::BTreeSet;
+    let cells: BTreeSet<RowMajor<i32>> = [(2, 0), (0, 1), (1, 0), (0, 0)].into_iter().map(|c| RowMajor(Vec2::from(c))).collect();
+    let read: Vec<(i32, i32)> = cells.iter().map(|RowMajor(v)| (v.x, v.y)).collect();
+    assert_eq!(read, vec![(0, 0), (1, 0), (2, 0), (0, 1)]);
+    assert_eq!(Vec2::new(2, 0).cmp_column_major(&Vec2::new(0, 1)), Ordering::Greater);
+
+    let bricks: BTreeSet<ByZ<i64>> = [(0, 0, 5), (9, 9, 1), (0, 1, 1)].into_iter().map(|c| ByZ(Vec3::from(c))).collect();
+    let heights: Vec<(i64, i64)> = bricks.iter().map(|ByZ(v)| (v.z, v.x)).collect();
+    assert_eq!(heights, vec![(1, 0), (1, 9), (5, 0)]);
+}
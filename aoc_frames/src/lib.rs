@@ -0,0 +1,276 @@
+// Frame recording for the grid simulations. A simulation offers the recorder a
+// frame at every step, and the recorder keeps the ones it wants, then plays them
+// back in the terminal or writes them out as PPM images or an animated GIF.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub type Colour = [u8; 3];
+
+// A grid of characters, each with an optional colour
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<char>,
+    colours: Vec<Option<Colour>>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize, fill: char) -> Frame {
+        Frame{width, height, cells: vec![fill; width * height], colours: vec![None; width * height]}
+    }
+    // Short rows are padded out with spaces
+    pub fn from_rows(rows: &[Vec<char>]) -> Frame {
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut frame = Frame::new(width, rows.len(), ' ');
+        for (i, row) in rows.iter().enumerate() {
+            for (j, c) in row.iter().enumerate() {
+                frame.set(i, j, *c);
+            }
+        }
+        frame
+    }
+    pub fn get(&self, row: usize, column: usize) -> char {
+        self.cells[row * self.width + column]
+    }
+    pub fn colour(&self, row: usize, column: usize) -> Option<Colour> {
+        self.colours[row * self.width + column]
+    }
+    pub fn set(&mut self, row: usize, column: usize, c: char) {
+        self.cells[row * self.width + column] = c;
+    }
+    pub fn set_coloured(&mut self, row: usize, column: usize, c: char, colour: Colour) {
+        self.cells[row * self.width + column] = c;
+        self.colours[row * self.width + column] = Some(colour);
+    }
+    pub fn to_text(&self) -> String {
+        let rows: Vec<String> = self.cells.chunks(self.width.max(1)).map(|row| row.iter().collect()).collect();
+        rows.join("\n")
+    }
+    // The grid with 24-bit colour escapes around every coloured cell
+    pub fn to_ansi(&self) -> String {
+        let mut ansi = String::new();
+        for row in 0..self.height {
+            for column in 0..self.width {
+                match self.colour(row, column) {
+                    Some([r, g, b]) => ansi.push_str(&format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, self.get(row, column))),
+                    None => ansi.push(self.get(row, column)),
+                }
+            }
+            ansi.push('\n');
+        }
+        ansi
+    }
+    // RGB bytes with every cell drawn as a `cell_size` square block
+    pub fn to_pixels(&self, cell_size: usize) -> Vec<u8> {
+        let mut pixels: Vec<u8> = Vec::with_capacity(self.width * self.height * cell_size * cell_size * 3);
+        for row in 0..self.height {
+            let line: Vec<u8> = (0..self.width)
+                .flat_map(|column| {
+                    let colour = self.colour(row, column).unwrap_or_else(|| default_colour(self.get(row, column)));
+                    std::iter::repeat_n(colour, cell_size).flatten()
+                })
+                .collect();
+            for _ in 0..cell_size {
+                pixels.extend(&line);
+            }
+        }
+        pixels
+    }
+    pub fn to_ppm(&self, cell_size: usize) -> Vec<u8> {
+        let mut bytes: Vec<u8> = format!("P6\n{} {}\n255\n", self.width * cell_size, self.height * cell_size).into_bytes();
+        bytes.extend(self.to_pixels(cell_size));
+        bytes
+    }
+}
+
+// Cells without a colour of their own: dark for empty space, grey for walls and
+// light for everything else
+fn default_colour(c: char) -> Colour {
+    match c {
+        '.' | ' ' => [16, 16, 16],
+        '#' => [110, 110, 110],
+        _ => [220, 220, 220],
+    }
+}
+
+
+// What to do with a recording, as picked on the command line
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Output {
+    Play,
+    Gif(PathBuf),
+    // A directory to write one PPM image per frame into
+    Ppm(PathBuf),
+}
+
+
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    frames: Vec<Frame>,
+    frame_skip: usize,
+    max_frames: usize,
+    offered: usize,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder::new()
+    }
+}
+
+impl Recorder {
+    // Keeps every frame
+    pub fn new() -> Recorder {
+        Recorder{frames: Vec::new(), frame_skip: 0, max_frames: usize::MAX, offered: 0}
+    }
+    // Keeps nothing, for running a simulation without recording it
+    pub fn off() -> Recorder {
+        Recorder::new().with_max_frames(0)
+    }
+    // Keeps one frame, then drops the next `frame_skip`
+    pub fn with_frame_skip(mut self, frame_skip: usize) -> Recorder {
+        self.frame_skip = frame_skip;
+        self
+    }
+    pub fn with_max_frames(mut self, max_frames: usize) -> Recorder {
+        self.max_frames = max_frames;
+        self
+    }
+    // Whether the next frame offered will be kept
+    pub fn wants_frame(&self) -> bool {
+        self.frames.len() < self.max_frames && self.offered.is_multiple_of(self.frame_skip + 1)
+    }
+    // The frame is only built if it's going to be kept
+    pub fn offer(&mut self, build: impl FnOnce() -> Frame) {
+        if self.wants_frame() {
+            self.frames.push(build());
+        }
+        self.offered += 1;
+    }
+    pub fn push(&mut self, frame: Frame) {
+        self.offer(|| frame);
+    }
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    // Every frame drawn over the last, from the top left of a cleared screen
+    pub fn to_ansi_playback(&self) -> String {
+        self.frames.iter().map(|frame| format!("\x1b[H\x1b[2J{}", frame.to_ansi())).collect()
+    }
+    pub fn play(&self, delay: Duration) {
+        for frame in &self.frames {
+            print!("\x1b[H\x1b[2J{}", frame.to_ansi());
+            std::thread::sleep(delay);
+        }
+    }
+    // `<prefix>_0000.ppm`, `<prefix>_0001.ppm` and so on in `directory`
+    pub fn write_ppm_frames(&self, directory: &Path, prefix: &str, cell_size: usize) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(directory)?;
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for (n, frame) in self.frames.iter().enumerate() {
+            let path = directory.join(format!("{}_{:04}.ppm", prefix, n));
+            fs::write(&path, frame.to_ppm(cell_size))?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+    // Looping animation, `delay` being hundredths of a second per frame
+    pub fn to_gif(&self, cell_size: usize, delay: u16) -> Result<Vec<u8>, String> {
+        let width = self.frames.iter().map(|frame| frame.width * cell_size).max().unwrap_or(0);
+        let height = self.frames.iter().map(|frame| frame.height * cell_size).max().unwrap_or(0);
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(format!("{}x{} is too large for a GIF", width, height));
+        }
+        let mut bytes: Vec<u8> = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut bytes, width as u16, height as u16, &[]).map_err(|e| e.to_string())?;
+            encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+            for frame in &self.frames {
+                let pixels = frame.to_pixels(cell_size);
+                let mut gif_frame = gif::Frame::from_rgb_speed((frame.width * cell_size) as u16, (frame.height * cell_size) as u16, &pixels, 10);
+                gif_frame.delay = delay;
+                encoder.write_frame(&gif_frame).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(bytes)
+    }
+    // Plays the frames in the terminal at ten a second, or writes them out with
+    // every cell `cell_size` pixels across. PPM files are named after `prefix`.
+    pub fn output(&self, output: &Output, prefix: &str, cell_size: usize) -> Result<(), String> {
+        match output {
+            Output::Play => self.play(Duration::from_millis(100)),
+            Output::Gif(path) => fs::write(path, self.to_gif(cell_size, 10)?)
+                .map_err(|error| format!("Could not write '{}': {}", path.display(), error))?,
+            Output::Ppm(directory) => {
+                self.write_ppm_frames(directory, prefix, cell_size)
+                    .map_err(|error| format!("Could not write frames to '{}': {}", directory.display(), error))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+fn counter_frame(n: usize) -> Frame {
+    let mut frame = Frame::from_rows(&[vec!['.', '#'], vec!['.']]);
+    frame.set_coloured(1, 1, char::from_digit(n as u32 % 10, 10).unwrap(), [255, 0, 0]);
+    frame
+}
+
+#[test]
+fn test_frame() {
+    let frame = counter_frame(7);
+    assert_eq!((frame.width, frame.height), (2, 2));
+    assert_eq!(frame.to_text(), ".#\n.7");
+    assert_eq!(frame.to_ansi(), ".#\n.\x1b[38;2;255;0;0m7\x1b[0m\n");
+    let ppm = frame.to_ppm(2);
+    assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+    assert_eq!(ppm.len(), 11 + 4 * 4 * 3);
+    // The bottom right block is red
+    assert_eq!(&ppm[ppm.len() - 3..], &[255, 0, 0]);
+    assert_eq!(&ppm[11..14], &[16, 16, 16]);
+}
+
+#[test]
+fn test_frame_skip_and_limit() {
+    let mut recorder = Recorder::new().with_frame_skip(2).with_max_frames(3);
+    let mut built: usize = 0;
+    for n in 0..20 {
+        recorder.offer(|| { built += 1; counter_frame(n) });
+    }
+    // Frames 0, 3 and 6, and nothing built after that
+    assert_eq!(built, 3);
+    let kept: Vec<char> = recorder.frames().iter().map(|frame| frame.get(1, 1)).collect();
+    assert_eq!(kept, vec!['0', '3', '6']);
+    let mut off = Recorder::off();
+    off.push(counter_frame(1));
+    assert!(off.frames().is_empty());
+}
+
+#[test]
+fn test_outputs() {
+    let mut recorder = Recorder::new();
+    for n in 0..3 {
+        recorder.push(counter_frame(n));
+    }
+    assert_eq!(recorder.to_ansi_playback().matches("\x1b[2J").count(), 3);
+    let gif = recorder.to_gif(3, 10).unwrap();
+    assert!(gif.starts_with(b"GIF89a"));
+    let directory = std::env::temp_dir().join(format!("aoc_frames_{}", std::process::id()));
+    let paths = recorder.write_ppm_frames(&directory, "counter", 1).unwrap();
+    assert_eq!(paths.len(), 3);
+    assert!(paths[2].ends_with("counter_0002.ppm"));
+    assert_eq!(fs::read(&paths[1]).unwrap(), counter_frame(1).to_ppm(1));
+    let gif_path = directory.join("counter.gif");
+    recorder.output(&Output::Gif(gif_path.clone()), "counter", 3).unwrap();
+    assert_eq!(fs::read(&gif_path).unwrap(), gif);
+    recorder.output(&Output::Ppm(directory.join("ppm")), "counter", 2).unwrap();
+    assert_eq!(fs::read(directory.join("ppm").join("counter_0000.ppm")).unwrap(), counter_frame(0).to_ppm(2));
+    assert!(recorder.output(&Output::Gif(directory.join("missing").join("counter.gif")), "counter", 3).is_err());
+    fs::remove_dir_all(&directory).unwrap();
+}
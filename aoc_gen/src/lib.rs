@@ -0,0 +1,978 @@
+// Seedable generators for puzzle inputs. Each one builds text in the same
+// layout as the real input for its day, of whatever size is asked for, and
+// structurally valid in the way the puzzle promises (a single pipe loop, a
+// closed dig plan, bricks that don't overlap, ...). The same seed always gives
+// the same input.
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+// SplitMix64, which is plenty for making test inputs
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng{state: seed}
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+    // Uniform in 0..n, for n > 0
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+    // Uniform in low..=high
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + (self.next_u64() % (high - low + 1) as u64) as i64
+    }
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+    pub fn choose<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+// `size` is the main dimension for the day: lines, cards, hands, races and so on
+// for the line based days, and the side length for the grid days.
+pub fn generate(day: u32, size: usize, seed: u64) -> Result<String, String> {
+    if size == 0 {
+        return Err("The size must be at least 1".to_string());
+    }
+    let mut rng = Rng::new(seed);
+    let rng = &mut rng;
+    let input = match day {
+        1 => calibration_document(rng, size),
+        2 => cube_games(rng, size),
+        3 => engine_schematic(rng, size.max(3), size.max(3)),
+        4 => scratchcards(rng, size),
+        5 => almanac(rng, size, size.max(2)),
+        6 => boat_races(rng, size),
+        7 => camel_hands(rng, size),
+        8 => desert_network(rng, size.max(2), size.max(2)),
+        9 => oasis_report(rng, size, 21),
+        10 => pipe_loop(rng, size.max(2), size.max(2), 0.3),
+        11 => galaxy_image(rng, size, size, 0.05),
+        12 => spring_records(rng, size, 20, 0.5),
+        13 => mirror_patterns(rng, size, 15),
+        14 => rock_platform(rng, size, size, 0.2, 0.1),
+        15 => initialization_sequence(rng, size, (size / 4).max(1)),
+        16 => mirror_grid(rng, size, size, 0.1),
+        17 => heat_loss_map(rng, size, size),
+        18 => dig_plan(rng, size.max(2)),
+        19 => workflow_system(rng, size, size),
+        20 => module_configuration(rng, size.clamp(1, 8), 12),
+        21 => garden_map(rng, size.max(3), 0.15),
+        22 => brick_snapshot(rng, size, 10),
+        23 => hiking_trails(rng, size.max(3), 0.2),
+        24 => hailstones(rng, size),
+        25 => component_wiring(rng, size.max(5)),
+        _ => return Err(format!("There is no generator for day {}", day)),
+    };
+    Ok(input)
+}
+
+// The inputs saved under a fuzz target's regressions directory, in file name
+// order. Each one used to panic its parser, and they're kept as the target's
+// regression corpus so the day's tests can check they're now rejected.
+pub fn fuzz_regressions(target: &str) -> Vec<String> {
+    let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/../fuzz/regressions/").to_string() + target;
+    let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(&directory)
+        .unwrap_or_else(|e| panic!("Cannot read {}: {}", directory, e))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    paths.iter().map(|path| std::fs::read_to_string(path).unwrap()).collect()
+}
+
+const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+fn random_word(rng: &mut Rng, alphabet: &[u8], length: usize) -> String {
+    (0..length).map(|_| rng.choose(alphabet) as char).collect()
+}
+
+// `count` distinct words, none of them in `taken`
+fn distinct_words(rng: &mut Rng, count: usize, alphabet: &[u8], length: usize, taken: &[&str]) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut seen: HashSet<String> = taken.iter().map(|word| word.to_string()).collect();
+    let mut length = length;
+    let mut attempts: usize = 0;
+    while words.len() < count {
+        let word = random_word(rng, alphabet, length);
+        if seen.insert(word.clone()) {
+            words.push(word);
+        }
+        // Move on to longer words once the short ones are running out
+        attempts += 1;
+        if attempts > 20 * count {
+            length += 1;
+            attempts = 0;
+        }
+    }
+    words
+}
+
+fn grid_to_string(grid: &[Vec<char>]) -> String {
+    grid.iter().map(|row| row.iter().collect::<String>() + "\n").collect()
+}
+
+
+// Day 1: lines of letters with digits and spelled out digits mixed in, each with
+// at least one real digit
+pub fn calibration_document(rng: &mut Rng, lines: usize) -> String {
+    const WORDS: [&str; 9] = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+    let mut document = String::new();
+    for _ in 0..lines {
+        let mut line = String::new();
+        let digit_at = rng.range(0, 5);
+        for k in 0..rng.range(6, 12) {
+            if k == digit_at || rng.chance(0.1) {
+                line.push(char::from_digit(rng.range(1, 9) as u32, 10).unwrap());
+            }
+            else if rng.chance(0.15) {
+                line.push_str(rng.choose(&WORDS));
+            }
+            else {
+                line.push(rng.choose(LETTERS) as char);
+            }
+        }
+        document.push_str(&line);
+        document.push('\n');
+    }
+    document
+}
+
+// Day 2
+pub fn cube_games(rng: &mut Rng, games: usize) -> String {
+    let mut record = String::new();
+    for id in 1..=games {
+        let hands: Vec<String> = (0..rng.range(1, 6))
+            .map(|_| {
+                let mut colours = vec!["red", "green", "blue"];
+                rng.shuffle(&mut colours);
+                colours.truncate(rng.range(1, 3) as usize);
+                colours.iter().map(|colour| format!("{} {}", rng.range(1, 20), colour)).collect::<Vec<String>>().join(", ")
+            })
+            .collect();
+        record.push_str(&format!("Game {}: {}\n", id, hands.join("; ")));
+    }
+    record
+}
+
+// Day 3: numbers of up to three digits, never touching each other along a row,
+// with symbols scattered between them
+pub fn engine_schematic(rng: &mut Rng, width: usize, height: usize) -> String {
+    const SYMBOLS: &[u8] = b"*#+$/@=%&";
+    let mut grid: Vec<Vec<char>> = vec![vec!['.'; width]; height];
+    for row in grid.iter_mut() {
+        let mut j: usize = 0;
+        while j < width {
+            let length = rng.range(1, 3) as usize;
+            if rng.chance(0.15) && j + length <= width {
+                row[j] = char::from_digit(rng.range(1, 9) as u32, 10).unwrap();
+                for cell in row.iter_mut().skip(j + 1).take(length - 1) {
+                    *cell = char::from_digit(rng.range(0, 9) as u32, 10).unwrap();
+                }
+                j += length;
+                if j < width && rng.chance(0.2) {
+                    row[j] = rng.choose(SYMBOLS) as char;
+                }
+            }
+            else if rng.chance(0.05) {
+                row[j] = rng.choose(SYMBOLS) as char;
+            }
+            j += 1;
+        }
+    }
+    grid_to_string(&grid)
+}
+
+// Day 4: no card wins copies of cards past the end of the table
+pub fn scratchcards(rng: &mut Rng, cards: usize) -> String {
+    let mut table = String::new();
+    for id in 1..=cards {
+        let mut numbers: Vec<i64> = (1..=99).collect();
+        rng.shuffle(&mut numbers);
+        let wins = rng.range(0, 5.min(cards - id) as i64) as usize;
+        let winning: Vec<i64> = numbers[..5].to_vec();
+        let mut player: Vec<i64> = numbers[5 - wins..13 - wins].to_vec();
+        rng.shuffle(&mut player);
+        let format = |numbers: &[i64]| numbers.iter().map(|n| format!("{:>2}", n)).collect::<Vec<String>>().join(" ");
+        table.push_str(&format!("Card {:>3}: {} | {}\n", id, format(&winning), format(&player)));
+    }
+    table
+}
+
+// Day 5: every map shuffles a run of consecutive ranges, so it's a bijection on
+// the numbers it covers
+pub fn almanac(rng: &mut Rng, seed_ranges: usize, ranges_per_map: usize) -> String {
+    const NAMES: [&str; 8] = ["seed", "soil", "fertilizer", "water", "light", "temperature", "humidity", "location"];
+    let seeds: Vec<String> = (0..seed_ranges)
+        .map(|_| format!("{} {}", rng.range(0, 1_000_000_000), rng.range(1, 100_000_000)))
+        .collect();
+    let mut almanac = format!("seeds: {}\n", seeds.join(" "));
+    for pair in NAMES.windows(2) {
+        let mut lengths: Vec<i64> = (0..ranges_per_map).map(|_| rng.range(1, 200_000_000)).collect();
+        let source_start = rng.range(0, 500_000_000);
+        let mut order: Vec<usize> = (0..lengths.len()).collect();
+        rng.shuffle(&mut order);
+        let mut destinations: Vec<i64> = vec![0; lengths.len()];
+        let mut next = source_start;
+        for k in &order {
+            destinations[*k] = next;
+            next += lengths[*k];
+        }
+        almanac.push_str(&format!("\n{}-to-{} map:\n", pair[0], pair[1]));
+        let mut source = source_start;
+        for (k, length) in lengths.iter_mut().enumerate() {
+            almanac.push_str(&format!("{} {} {}\n", destinations[k], source, length));
+            source += *length;
+        }
+    }
+    almanac
+}
+
+// Day 6: every race can be won
+pub fn boat_races(rng: &mut Rng, races: usize) -> String {
+    let times: Vec<i64> = (0..races).map(|_| rng.range(7, 99)).collect();
+    let distances: Vec<i64> = times.iter().map(|time| rng.range(0, (time / 2) * (time - time / 2) - 1)).collect();
+    let format = |values: &[i64]| values.iter().map(|value| format!("{:>5}", value)).collect::<String>();
+    format!("Time:    {}\nDistance:{}\n", format(&times), format(&distances))
+}
+
+// Day 7: no two hands the same, so the ranking never ties
+pub fn camel_hands(rng: &mut Rng, hands: usize) -> String {
+    const CARDS: &[u8] = b"23456789TJQKA";
+    let hands = hands.min(13usize.pow(5) / 2);
+    let mut lines = String::new();
+    for hand in distinct_words(rng, hands, CARDS, 5, &[]) {
+        lines.push_str(&format!("{} {}\n", hand, rng.range(1, 1000)));
+    }
+    lines
+}
+
+// Day 8: every node only points further along a chain that ends at ZZZ, so any
+// walk from AAA gets there within `nodes` steps
+pub fn desert_network(rng: &mut Rng, nodes: usize, instructions: usize) -> String {
+    const NAME_LETTERS: &[u8] = b"BCDEFGHIJKLMNOPQRSTUVWXY";
+    let mut names: Vec<String> = vec!["AAA".to_string()];
+    names.extend(distinct_words(rng, nodes - 2, NAME_LETTERS, 3, &[]));
+    names.push("ZZZ".to_string());
+    let mut network: String = (0..instructions).map(|_| rng.choose(b"LR") as char).collect();
+    network.push_str("\n\n");
+    for (k, name) in names.iter().enumerate() {
+        let (left, right) = if k + 1 == names.len() {
+            (k, k)
+        }
+        else {
+            let later = (names.len() - k - 1) as i64;
+            (k + rng.range(1, later) as usize, k + rng.range(1, later) as usize)
+        };
+        network.push_str(&format!("{} = ({}, {})\n", name, names[left], names[right]));
+    }
+    network
+}
+
+// Day 9: polynomials of degree at most four, so the differences always run out
+pub fn oasis_report(rng: &mut Rng, histories: usize, length: usize) -> String {
+    let mut report = String::new();
+    for _ in 0..histories {
+        let coefficients: Vec<i64> = (0..rng.range(1, 5)).map(|_| rng.range(-5, 5)).collect();
+        let values: Vec<String> = (0..length as i64)
+            .map(|x| coefficients.iter().rev().fold(0, |total, c| total * x + c).to_string())
+            .collect();
+        report.push_str(&values.join(" "));
+        report.push('\n');
+    }
+    report
+}
+
+type Cell = (usize, usize);
+type Link = (Cell, Cell);
+
+// A random simple loop through the cells of a (2 * blocks_high) by
+// (2 * blocks_wide) grid, in walking order. A random tree is grown over the
+// 2x2 blocks, every block starts out as a little loop of its four cells, and
+// each tree edge cuts open the two sides it joins and splices the loops
+// together, leaving one loop round the whole tree.
+fn random_loop(rng: &mut Rng, blocks_wide: usize, blocks_high: usize, fill: f64) -> Vec<Cell> {
+    let target = ((blocks_wide * blocks_high) as f64 * fill).ceil().max(1.0) as usize;
+    let first = (rng.below(blocks_high), rng.below(blocks_wide));
+    let mut in_tree: HashSet<Cell> = HashSet::from([first]);
+    let mut tree: Vec<Cell> = vec![first];
+    let mut frontier: Vec<Link> = Vec::new();
+    let mut tree_edges: Vec<Link> = Vec::new();
+    let add_frontier = |frontier: &mut Vec<Link>, (a, b): Cell| {
+        if a > 0 { frontier.push(((a - 1, b), (a, b))); }
+        if b > 0 { frontier.push(((a, b - 1), (a, b))); }
+        if a + 1 < blocks_high { frontier.push(((a, b), (a + 1, b))); }
+        if b + 1 < blocks_wide { frontier.push(((a, b), (a, b + 1))); }
+    };
+    add_frontier(&mut frontier, first);
+    while tree.len() < target && !frontier.is_empty() {
+        let edge = frontier.swap_remove(rng.below(frontier.len()));
+        let new_block = if in_tree.contains(&edge.0) { edge.1 } else { edge.0 };
+        if in_tree.insert(new_block) {
+            tree.push(new_block);
+            tree_edges.push(edge);
+            add_frontier(&mut frontier, new_block);
+        }
+    }
+
+    let mut links: BTreeSet<Link> = BTreeSet::new();
+    for (a, b) in &tree {
+        let (top, left) = (2 * a, 2 * b);
+        links.insert(((top, left), (top, left + 1)));
+        links.insert(((top + 1, left), (top + 1, left + 1)));
+        links.insert(((top, left), (top + 1, left)));
+        links.insert(((top, left + 1), (top + 1, left + 1)));
+    }
+    for ((a, b), (c, d)) in tree_edges {
+        if a == c {
+            // Side by side: open the right of (a, b) and the left of (c, d)
+            let (top, right, next) = (2 * a, 2 * b + 1, 2 * d);
+            links.remove(&((top, right), (top + 1, right)));
+            links.remove(&((top, next), (top + 1, next)));
+            links.insert(((top, right), (top, next)));
+            links.insert(((top + 1, right), (top + 1, next)));
+        }
+        else {
+            // One above the other: open the bottom of (a, b) and the top of (c, d)
+            let (bottom, left, next) = (2 * a + 1, 2 * b, 2 * c);
+            links.remove(&((bottom, left), (bottom, left + 1)));
+            links.remove(&((next, left), (next, left + 1)));
+            links.insert(((bottom, left), (next, left)));
+            links.insert(((bottom, left + 1), (next, left + 1)));
+        }
+    }
+
+    let mut neighbours: BTreeMap<Cell, Vec<Cell>> = BTreeMap::new();
+    for (p, q) in &links {
+        neighbours.entry(*p).or_default().push(*q);
+        neighbours.entry(*q).or_default().push(*p);
+    }
+    let start = *links.iter().map(|(p, _)| p).min().unwrap();
+    let mut cells: Vec<Cell> = vec![start];
+    let mut previous = start;
+    let mut current = neighbours[&start][0];
+    while current != start {
+        cells.push(current);
+        let next = *neighbours[&current].iter().find(|cell| **cell != previous).unwrap();
+        previous = current;
+        current = next;
+    }
+    cells
+}
+
+fn pipe_between(cell: Cell, a: Cell, b: Cell) -> char {
+    let side = |other: Cell| {
+        if other.0 < cell.0 { 'N' } else if other.0 > cell.0 { 'S' } else if other.1 > cell.1 { 'E' } else { 'W' }
+    };
+    match (side(a), side(b)) {
+        ('N', 'S') | ('S', 'N') => '|',
+        ('E', 'W') | ('W', 'E') => '-',
+        ('N', 'E') | ('E', 'N') => 'L',
+        ('N', 'W') | ('W', 'N') => 'J',
+        ('S', 'W') | ('W', 'S') => '7',
+        _ => 'F',
+    }
+}
+
+// Day 10: a single loop through S, with `junk` of the other tiles holding pipes
+// that aren't part of it. None of the junk connects to S.
+pub fn pipe_loop(rng: &mut Rng, width: usize, height: usize, junk: f64) -> String {
+    let cells = random_loop(rng, width / 2, height / 2, 0.6);
+    let offset = (rng.below(height % 2 + 1), rng.below(width % 2 + 1));
+    let mut grid: Vec<Vec<char>> = vec![vec!['.'; width]; height];
+    for (k, cell) in cells.iter().enumerate() {
+        let before = cells[(k + cells.len() - 1) % cells.len()];
+        let after = cells[(k + 1) % cells.len()];
+        grid[cell.0 + offset.0][cell.1 + offset.1] = pipe_between(*cell, before, after);
+    }
+    let start = rng.choose(&cells);
+    let start = (start.0 + offset.0, start.1 + offset.1);
+    let on_loop: HashSet<Cell> = cells.iter().map(|(i, j)| (i + offset.0, j + offset.1)).collect();
+    for (i, row) in grid.iter_mut().enumerate() {
+        for (j, tile) in row.iter_mut().enumerate() {
+            let next_to_start = i.abs_diff(start.0) + j.abs_diff(start.1) == 1;
+            if !on_loop.contains(&(i, j)) && !next_to_start && rng.chance(junk) {
+                *tile = rng.choose(b"|-LJ7F") as char;
+            }
+        }
+    }
+    grid[start.0][start.1] = 'S';
+    grid_to_string(&grid)
+}
+
+// Day 11
+pub fn galaxy_image(rng: &mut Rng, width: usize, height: usize, density: f64) -> String {
+    let grid: Vec<Vec<char>> = (0..height)
+        .map(|_| (0..width).map(|_| if rng.chance(density) { '#' } else { '.' }).collect())
+        .collect();
+    grid_to_string(&grid)
+}
+
+// Day 12: the groups are read off a real row of springs before `unknown` of it is
+// hidden, so every record has at least one arrangement
+pub fn spring_records(rng: &mut Rng, records: usize, length: usize, unknown: f64) -> String {
+    let mut lines = String::new();
+    for _ in 0..records {
+        let mut springs: Vec<bool> = (0..length).map(|_| rng.chance(0.4)).collect();
+        if !springs.contains(&true) {
+            springs[rng.below(length)] = true;
+        }
+        let groups: Vec<String> = springs.split(|damaged| !damaged)
+            .filter(|group| !group.is_empty())
+            .map(|group| group.len().to_string())
+            .collect();
+        let row: String = springs.iter()
+            .map(|damaged| if rng.chance(unknown) { '?' } else if *damaged { '#' } else { '.' })
+            .collect();
+        lines.push_str(&format!("{} {}\n", row, groups.join(",")));
+    }
+    lines
+}
+
+// The number of rows (or columns, read down the transpose) that a pattern
+// reflects after
+fn count_reflections(grid: &[Vec<char>]) -> usize {
+    (1..grid.len())
+        .filter(|line| (0..*line).rev().zip(*line..grid.len()).all(|(a, b)| grid[a] == grid[b]))
+        .count()
+}
+
+fn transpose(grid: &[Vec<char>]) -> Vec<Vec<char>> {
+    (0..grid[0].len()).map(|j| grid.iter().map(|row| row[j]).collect()).collect()
+}
+
+// Day 13: patterns with exactly one line of reflection
+pub fn mirror_patterns(rng: &mut Rng, patterns: usize, max_size: usize) -> String {
+    let max_size = max_size.max(5) as i64;
+    let mut notes: Vec<String> = Vec::new();
+    while notes.len() < patterns {
+        let (width, height) = (rng.range(5, max_size) as usize, rng.range(5, max_size) as usize);
+        let mut grid: Vec<Vec<char>> = (0..height)
+            .map(|_| (0..width).map(|_| if rng.chance(0.5) { '#' } else { '.' }).collect())
+            .collect();
+        let vertical = rng.chance(0.5);
+        if vertical {
+            grid = transpose(&grid);
+        }
+        let line = rng.range(1, grid.len() as i64 - 1) as usize;
+        for (a, b) in (0..line).rev().zip(line..grid.len()) {
+            grid[b] = grid[a].clone();
+        }
+        if vertical {
+            grid = transpose(&grid);
+        }
+        if count_reflections(&grid) + count_reflections(&transpose(&grid)) == 1 {
+            notes.push(grid_to_string(&grid));
+        }
+    }
+    notes.join("\n")
+}
+
+// Day 14: `rounded` and `cubes` are the share of tiles holding each kind of rock
+pub fn rock_platform(rng: &mut Rng, width: usize, height: usize, rounded: f64, cubes: f64) -> String {
+    let grid: Vec<Vec<char>> = (0..height)
+        .map(|_| (0..width).map(|_| {
+            if rng.chance(rounded) { 'O' } else if rng.chance(cubes / (1.0 - rounded)) { '#' } else { '.' }
+        }).collect())
+        .collect();
+    grid_to_string(&grid)
+}
+
+// Day 15: one line of steps over `labels` different lens labels
+pub fn initialization_sequence(rng: &mut Rng, steps: usize, labels: usize) -> String {
+    let labels: Vec<String> = (0..labels)
+        .map(|_| {
+            let length = rng.range(2, 6) as usize;
+            random_word(rng, LETTERS, length)
+        })
+        .collect();
+    let steps: Vec<String> = (0..steps)
+        .map(|_| {
+            let label = &labels[rng.below(labels.len())];
+            if rng.chance(0.3) { format!("{}-", label) } else { format!("{}={}", label, rng.range(1, 9)) }
+        })
+        .collect();
+    steps.join(",") + "\n"
+}
+
+// Day 16: `density` of the tiles hold a mirror or a splitter
+pub fn mirror_grid(rng: &mut Rng, width: usize, height: usize, density: f64) -> String {
+    let grid: Vec<Vec<char>> = (0..height)
+        .map(|_| (0..width).map(|_| if rng.chance(density) { rng.choose(b"/\\|-") as char } else { '.' }).collect())
+        .collect();
+    grid_to_string(&grid)
+}
+
+// Day 17
+pub fn heat_loss_map(rng: &mut Rng, width: usize, height: usize) -> String {
+    let grid: Vec<Vec<char>> = (0..height)
+        .map(|_| (0..width).map(|_| char::from_digit(rng.range(1, 9) as u32, 10).unwrap()).collect())
+        .collect();
+    grid_to_string(&grid)
+}
+
+// Day 18: a closed trench that never crosses or touches itself, walking round
+// a random loop on a `size` by `size` site. The colour codes describe the same
+// loop scaled up, so both parts get a valid plan.
+pub fn dig_plan(rng: &mut Rng, size: usize) -> String {
+    let mut cells = random_loop(rng, size / 2, size / 2, 0.6);
+    // Start on a corner so the first and last instructions turn
+    let corner = (0..cells.len())
+        .find(|k| {
+            let (before, after) = (cells[(k + cells.len() - 1) % cells.len()], cells[(k + 1) % cells.len()]);
+            before.0 != after.0 && before.1 != after.1
+        })
+        .unwrap();
+    cells.rotate_left(corner);
+    let direction = |from: Cell, to: Cell| {
+        if to.1 > from.1 { ('R', 0) } else if to.0 > from.0 { ('D', 1) } else if to.1 < from.1 { ('L', 2) } else { ('U', 3) }
+    };
+    let mut runs: Vec<((char, u32), usize)> = Vec::new();
+    for k in 0..cells.len() {
+        let step = direction(cells[k], cells[(k + 1) % cells.len()]);
+        match runs.last_mut() {
+            Some((last, length)) if *last == step => *length += 1,
+            _ => runs.push((step, 1)),
+        }
+    }
+    let longest = runs.iter().map(|(_, length)| *length).max().unwrap();
+    let most = (0xfffff / longest) as i64;
+    let scale = rng.range(most / 2, most) as usize;
+    runs.iter()
+        .map(|((letter, digit), length)| format!("{} {} (#{:05x}{})\n", letter, length, length * scale, digit))
+        .collect()
+}
+
+// Day 19: workflows only ever send parts to later workflows, so nothing loops
+pub fn workflow_system(rng: &mut Rng, workflows: usize, parts: usize) -> String {
+    let mut names: Vec<String> = vec!["in".to_string()];
+    names.extend(distinct_words(rng, workflows.saturating_sub(1), LETTERS, 2, &["in"]));
+    let target = |rng: &mut Rng, k: usize| {
+        if k + 1 < names.len() && rng.chance(0.6) {
+            names[rng.range(k as i64 + 1, names.len() as i64 - 1) as usize].clone()
+        }
+        else {
+            rng.choose(&["A", "R"]).to_string()
+        }
+    };
+    let mut system = String::new();
+    for (k, name) in names.iter().enumerate() {
+        let mut rules: Vec<String> = (0..rng.range(1, 3))
+            .map(|_| {
+                let category = rng.choose(b"xmas") as char;
+                let comparison = rng.choose(b"<>") as char;
+                format!("{}{}{}:{}", category, comparison, rng.range(1, 4000), target(rng, k))
+            })
+            .collect();
+        rules.push(target(rng, k));
+        system.push_str(&format!("{}{{{}}}\n", name, rules.join(",")));
+    }
+    system.push('\n');
+    for _ in 0..parts {
+        system.push_str(&format!(
+            "{{x={},m={},a={},s={}}}\n",
+            rng.range(1, 4000), rng.range(1, 4000), rng.range(1, 4000), rng.range(1, 4000),
+        ));
+    }
+    system
+}
+
+// Day 20: the layout of the real inputs. The broadcaster starts `counters`
+// chains of flip-flops, each a binary counter of `bits` bits. A conjunction
+// reads some of the bits and resets the counter, and once every counter has
+// fired, the final conjunction sends a low pulse to rx.
+pub fn module_configuration(rng: &mut Rng, counters: usize, bits: usize) -> String {
+    let names = distinct_words(rng, counters * (bits + 2) + 1, LETTERS, 2, &["rx"]);
+    let (last, names) = names.split_last().unwrap();
+    let mut lines: Vec<String> = Vec::new();
+    let mut starts: Vec<String> = Vec::new();
+    let mut inverters: Vec<String> = Vec::new();
+    for counter in names.chunks(bits + 2) {
+        let (flip_flops, hub, inverter) = (&counter[..bits], &counter[bits], &counter[bits + 1]);
+        let mut hub_outputs: Vec<String> = vec![flip_flops[0].clone()];
+        for (k, flip_flop) in flip_flops.iter().enumerate() {
+            let mut outputs: Vec<String> = Vec::new();
+            if k + 1 < bits {
+                outputs.push(flip_flops[k + 1].clone());
+            }
+            if k == 0 || k + 1 == bits || rng.chance(0.5) {
+                outputs.push(hub.clone());
+            }
+            else {
+                hub_outputs.push(flip_flop.clone());
+            }
+            lines.push(format!("%{} -> {}", flip_flop, outputs.join(", ")));
+        }
+        hub_outputs.push(inverter.clone());
+        rng.shuffle(&mut hub_outputs);
+        lines.push(format!("&{} -> {}", hub, hub_outputs.join(", ")));
+        lines.push(format!("&{} -> {}", inverter, last));
+        starts.push(flip_flops[0].clone());
+        inverters.push(inverter.clone());
+    }
+    lines.push(format!("&{} -> rx", last));
+    lines.push(format!("broadcaster -> {}", starts.join(", ")));
+    rng.shuffle(&mut lines);
+    lines.join("\n") + "\n"
+}
+
+// Day 21: like the real input, S sits in the middle of an odd sized garden
+// whose middle row, middle column and edges are clear of rocks
+pub fn garden_map(rng: &mut Rng, size: usize, density: f64) -> String {
+    let size = size | 1;
+    let middle = size / 2;
+    let grid: Vec<Vec<char>> = (0..size)
+        .map(|i| (0..size).map(|j| {
+            if i == middle && j == middle { 'S' }
+            else if i == middle || j == middle || i == 0 || j == 0 || i + 1 == size || j + 1 == size { '.' }
+            else if rng.chance(density) { '#' }
+            else { '.' }
+        }).collect())
+        .collect();
+    grid_to_string(&grid)
+}
+
+// Day 22: `bricks` bricks of up to four cubes in a `width` by `width` column,
+// none of them overlapping, listed in no particular order
+pub fn brick_snapshot(rng: &mut Rng, bricks: usize, width: usize) -> String {
+    let width = width.max(1) as i64;
+    let mut occupied: HashSet<(i64, i64, i64)> = HashSet::new();
+    let mut lines: Vec<String> = Vec::new();
+    for _ in 0..bricks {
+        let axis = rng.below(3);
+        let length = rng.range(1, if axis == 2 { 4 } else { 4.min(width) });
+        let extent = |along: bool| if along { length - 1 } else { 0 };
+        let (dx, dy, dz) = (extent(axis == 0), extent(axis == 1), extent(axis == 2));
+        let (x, y) = (rng.range(0, width - 1 - dx), rng.range(0, width - 1 - dy));
+        let mut z = rng.range(1, 2 * bricks as i64);
+        let cubes = |z: i64| -> Vec<(i64, i64, i64)> { (0..length).map(|k| (x + k * dx.signum(), y + k * dy.signum(), z + k * dz.signum())).collect() };
+        while cubes(z).iter().any(|cube| occupied.contains(cube)) {
+            z += 1;
+        }
+        occupied.extend(cubes(z));
+        lines.push(format!("{},{},{}~{},{},{}\n", x, y, z, x + dx, y + dy, z + dz));
+    }
+    rng.shuffle(&mut lines);
+    lines.concat()
+}
+
+// Day 23: a lattice of one tile wide trails. Junctions sit where trails cross,
+// slopes on either side of each one only let you go right or down, and some
+// trails are left out, but never the top row or right column of junctions, so
+// the end can always be reached.
+pub fn hiking_trails(rng: &mut Rng, junctions: usize, missing: f64) -> String {
+    let spacing = rng.range(4, 8) as usize;
+    let position = |k: usize| 1 + k * spacing;
+    let end = position(junctions - 1);
+    let mut grid: Vec<Vec<char>> = vec![vec!['#'; end + 2]; end + 2];
+    let dig = |grid: &mut Vec<Vec<char>>, from: Cell, to: Cell| {
+        for row in grid.iter_mut().take(to.0 + 1).skip(from.0) {
+            for tile in row.iter_mut().take(to.1 + 1).skip(from.1) {
+                *tile = '.';
+            }
+        }
+    };
+    for a in 0..junctions {
+        for b in 0..junctions {
+            let (i, j) = (position(a), position(b));
+            if b + 1 < junctions && (a == 0 || !rng.chance(missing)) {
+                dig(&mut grid, (i, j), (i, position(b + 1)));
+                grid[i][j + 1] = '>';
+                grid[i][position(b + 1) - 1] = '>';
+            }
+            if a + 1 < junctions && (b + 1 == junctions || !rng.chance(missing)) {
+                dig(&mut grid, (i, j), (position(a + 1), j));
+                grid[i + 1][j] = 'v';
+                grid[position(a + 1) - 1][j] = 'v';
+            }
+        }
+    }
+    dig(&mut grid, (0, 1), (1, 1));
+    dig(&mut grid, (end, end), (end + 1, end));
+    grid_to_string(&grid)
+}
+
+type Xyz = [i64; 3];
+
+// A rock thrown from `position` at `velocity`, and hailstones that it hits, each
+// at a different whole number of nanoseconds
+fn hailstorm(rng: &mut Rng, hailstones: usize) -> (Xyz, Xyz, Vec<(Xyz, Xyz)>) {
+    let position: Xyz = [0; 3].map(|_| rng.range(100_000_000_000_000, 400_000_000_000_000));
+    let velocity: Xyz = [0; 3].map(|_| rng.range(-300, 300));
+    let mut times: BTreeSet<i64> = BTreeSet::new();
+    while times.len() < hailstones {
+        times.insert(rng.range(1, 1_000_000_000_000));
+    }
+    let mut times: Vec<i64> = times.into_iter().collect();
+    rng.shuffle(&mut times);
+    let stones = times.iter()
+        .map(|time| {
+            // Never moving alongside the rock, or it would hit all of its path or none of it
+            let mut hail_velocity = velocity;
+            while hail_velocity == velocity {
+                hail_velocity = [0; 3].map(|_| rng.range(-300, 300));
+            }
+            let hail_position: Xyz = std::array::from_fn(|k| position[k] + time * (velocity[k] - hail_velocity[k]));
+            (hail_position, hail_velocity)
+        })
+        .collect();
+    (position, velocity, stones)
+}
+
+// Day 24: there is a rock throw that hits every hailstone, as the puzzle promises
+pub fn hailstones(rng: &mut Rng, hailstones: usize) -> String {
+    let (_, _, stones) = hailstorm(rng, hailstones);
+    stones.iter()
+        .map(|(p, v)| format!("{}, {}, {} @ {}, {}, {}\n", p[0], p[1], p[2], v[0], v[1], v[2]))
+        .collect()
+}
+
+type Wire = (String, String);
+
+// Two groups of `size` components, each wired so that it takes four cuts to
+// split it, and three wires between the groups. Every component is wired to the
+// next two in its group (wrapping round) and to a few others at random.
+fn wiring_groups(rng: &mut Rng, size: usize) -> (Vec<String>, BTreeSet<Wire>, Vec<Wire>) {
+    let names = distinct_words(rng, 2 * size, LETTERS, 3, &[]);
+    let mut wires: BTreeSet<Wire> = BTreeSet::new();
+    let mut connect = |a: &str, b: &str| {
+        let wire = if a < b { (a.to_string(), b.to_string()) } else { (b.to_string(), a.to_string()) };
+        wires.insert(wire);
+    };
+    for group in names.chunks(size) {
+        for k in 0..size {
+            connect(&group[k], &group[(k + 1) % size]);
+            connect(&group[k], &group[(k + 2) % size]);
+            let other = rng.below(size);
+            if other != k {
+                connect(&group[k], &group[other]);
+            }
+        }
+    }
+    let mut cut: Vec<Wire> = Vec::new();
+    let mut ends: Vec<usize> = (0..size).collect();
+    rng.shuffle(&mut ends);
+    for k in 0..3 {
+        let (a, b) = (&names[ends[k]], &names[size + rng.below(size)]);
+        connect(a, b);
+        cut.push((a.clone(), b.clone()));
+    }
+    (names, wires, cut)
+}
+
+// Day 25: each line lists a component and some of the components wired to it,
+// with every wire listed once
+pub fn component_wiring(rng: &mut Rng, size: usize) -> String {
+    let (_, wires, _) = wiring_groups(rng, size.max(5));
+    let mut lines: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (a, b) in wires {
+        let (from, to) = if rng.chance(0.5) { (a, b) } else { (b, a) };
+        lines.entry(from).or_default().push(to);
+    }
+    let mut lines: Vec<String> = lines.iter().map(|(from, to)| format!("{}: {}\n", from, to.join(" "))).collect();
+    rng.shuffle(&mut lines);
+    lines.concat()
+}
+
+
+#[cfg(test)]
+fn parse_grid(input: &str) -> Vec<Vec<char>> {
+    input.lines().map(|line| line.chars().collect()).collect()
+}
+
+#[test]
+fn test_generate() {
+    for day in 1..=25 {
+        let input = generate(day, 10, 3).unwrap();
+        assert!(!input.is_empty());
+        assert_eq!(input, generate(day, 10, 3).unwrap());
+        assert_ne!(input, generate(day, 10, 4).unwrap());
+    }
+    assert!(generate(26, 10, 3).is_err());
+    assert!(generate(1, 0, 3).is_err());
+}
+
+#[test]
+fn test_pipe_loop() {
+    let offsets = |pipe: char| -> Vec<(i64, i64)> {
+        match pipe {
+            '|' => vec![(-1, 0), (1, 0)],
+            '-' => vec![(0, -1), (0, 1)],
+            'L' => vec![(-1, 0), (0, 1)],
+            'J' => vec![(-1, 0), (0, -1)],
+            '7' => vec![(1, 0), (0, -1)],
+            'F' => vec![(1, 0), (0, 1)],
+            _ => vec![],
+        }
+    };
+    for seed in 0..20 {
+        let grid = parse_grid(&pipe_loop(&mut Rng::new(seed), 21, 16, 0.0));
+        assert_eq!((grid.len(), grid[0].len()), (16, 21));
+        let tile = |(i, j): (i64, i64)| grid.get(i as usize).and_then(|row| row.get(j as usize)).copied().unwrap_or('.');
+        let start = (0..16).flat_map(|i| (0..21).map(move |j| (i, j))).find(|cell| tile(*cell) == 'S').unwrap();
+        // Exactly two pipes point at S, and following one leads back round to it
+        let into_start: Vec<(i64, i64)> = [(-1, 0), (1, 0), (0, -1), (0, 1)].iter()
+            .map(|(di, dj)| (start.0 + di, start.1 + dj))
+            .filter(|cell| offsets(tile(*cell)).iter().any(|(di, dj)| (cell.0 + di, cell.1 + dj) == start))
+            .collect();
+        assert_eq!(into_start.len(), 2);
+        let (mut previous, mut current, mut length) = (start, into_start[0], 1);
+        while current != start {
+            let next: Vec<(i64, i64)> = offsets(tile(current)).iter().map(|(di, dj)| (current.0 + di, current.1 + dj)).collect();
+            assert!(next.contains(&previous));
+            let following = *next.iter().find(|cell| **cell != previous).unwrap();
+            (previous, current) = (current, following);
+            length += 1;
+        }
+        assert_eq!(length, grid.iter().flatten().filter(|c| **c != '.').count());
+        assert_eq!(length % 4, 0);
+    }
+}
+
+#[test]
+fn test_spring_records() {
+    for line in spring_records(&mut Rng::new(5), 30, 12, 0.6).lines() {
+        let (row, groups) = line.split_once(' ').unwrap();
+        let row: Vec<char> = row.chars().collect();
+        let unknowns: Vec<usize> = (0..row.len()).filter(|k| row[*k] == '?').collect();
+        let arrangements = (0..1u32 << unknowns.len())
+            .filter(|mask| {
+                let mut filled = row.clone();
+                for (bit, k) in unknowns.iter().enumerate() {
+                    filled[*k] = if mask & (1 << bit) != 0 { '#' } else { '.' };
+                }
+                let counted: Vec<String> = filled.split(|c| *c == '.').filter(|group| !group.is_empty()).map(|group| group.len().to_string()).collect();
+                counted.join(",") == groups
+            })
+            .count();
+        assert!(arrangements > 0, "{}", line);
+    }
+}
+
+#[test]
+fn test_hailstorm() {
+    let (position, velocity, stones) = hailstorm(&mut Rng::new(24), 300);
+    let mut times: HashSet<i64> = HashSet::new();
+    for (p, v) in &stones {
+        // The time the rock meets this stone along whichever axis they differ on
+        let axis = (0..3).find(|k| v[*k] != velocity[*k]).unwrap();
+        let time = (p[axis] - position[axis]) / (velocity[axis] - v[axis]);
+        assert!(time > 0 && times.insert(time));
+        for k in 0..3 {
+            assert_eq!(p[k] + time * v[k], position[k] + time * velocity[k]);
+        }
+    }
+    let text = hailstones(&mut Rng::new(24), 300);
+    assert_eq!(text.lines().count(), 300);
+    let (p, v) = stones[0];
+    assert!(text.starts_with(&format!("{}, {}, {} @ {}, {}, {}\n", p[0], p[1], p[2], v[0], v[1], v[2])));
+}
+
+#[test]
+fn test_component_wiring() {
+    let (names, wires, cut) = wiring_groups(&mut Rng::new(25), 40);
+    // Taking out the three cut wires leaves exactly the two groups
+    let mut groups: Vec<usize> = (0..names.len()).collect();
+    fn find(groups: &mut [usize], k: usize) -> usize {
+        if groups[k] != k {
+            groups[k] = find(groups, groups[k]);
+        }
+        groups[k]
+    }
+    let index: std::collections::HashMap<&String, usize> = names.iter().enumerate().map(|(k, name)| (name, k)).collect();
+    for (a, b) in &wires {
+        if !cut.iter().any(|(c, d)| (c == a && d == b) || (c == b && d == a)) {
+            let (a, b) = (find(&mut groups, index[a]), find(&mut groups, index[b]));
+            groups[a] = b;
+        }
+    }
+    let roots: BTreeSet<usize> = (0..names.len()).map(|k| find(&mut groups, k)).collect();
+    assert_eq!(roots.len(), 2);
+    assert_eq!(find(&mut groups, 0), find(&mut groups, 39));
+    assert_ne!(find(&mut groups, 0), find(&mut groups, 40));
+    // Every wire shows up once in the text
+    let text = component_wiring(&mut Rng::new(25), 40);
+    let listed: usize = text.lines().map(|line| line.split_once(": ").unwrap().1.split(' ').count()).sum();
+    assert_eq!(listed, wires.len());
+}
+
+#[test]
+fn test_dig_plan() {
+    for seed in 0..20 {
+        let plan = dig_plan(&mut Rng::new(seed), 14);
+        let mut position: (i64, i64) = (0, 0);
+        let mut dug: HashSet<(i64, i64)> = HashSet::new();
+        let mut scaled: (i64, i64) = (0, 0);
+        for line in plan.lines() {
+            let parts: Vec<&str> = line.split(' ').collect();
+            let step = match parts[0] { "R" => (0, 1), "D" => (1, 0), "L" => (0, -1), _ => (-1, 0) };
+            for _ in 0..parts[1].parse::<i64>().unwrap() {
+                position = (position.0 + step.0, position.1 + step.1);
+                assert!(dug.insert(position));
+            }
+            let colour = parts[2].trim_matches(&['(', ')', '#'][..]);
+            let length = i64::from_str_radix(&colour[..5], 16).unwrap();
+            let step = match &colour[5..] { "0" => (0, 1), "1" => (1, 0), "2" => (0, -1), _ => (-1, 0) };
+            scaled = (scaled.0 + step.0 * length, scaled.1 + step.1 * length);
+        }
+        assert_eq!((position, scaled), ((0, 0), (0, 0)));
+    }
+}
+
+#[test]
+fn test_puzzle_promises() {
+    let mut rng = Rng::new(11);
+    for pattern in mirror_patterns(&mut rng, 20, 12).split("\n\n") {
+        let grid = parse_grid(pattern);
+        assert_eq!(count_reflections(&grid) + count_reflections(&transpose(&grid)), 1);
+    }
+
+    for (k, line) in scratchcards(&mut rng, 20).lines().enumerate() {
+        let (winning, player) = line.split_once(':').unwrap().1.split_once('|').unwrap();
+        let winning: HashSet<&str> = winning.split_whitespace().collect();
+        let wins = player.split_whitespace().filter(|n| winning.contains(n)).count();
+        assert!(k + 1 + wins <= 20);
+    }
+
+    let mut cubes: HashSet<(i64, i64, i64)> = HashSet::new();
+    for line in brick_snapshot(&mut rng, 200, 5).lines() {
+        let ends: Vec<i64> = line.split(&[',', '~'][..]).map(|n| n.parse().unwrap()).collect();
+        assert!(ends[2] >= 1);
+        for x in ends[0]..=ends[3] {
+            for y in ends[1]..=ends[4] {
+                for z in ends[2]..=ends[5] {
+                    assert!(cubes.insert((x, y, z)));
+                }
+            }
+        }
+    }
+
+    let network = desert_network(&mut rng, 30, 7);
+    let (instructions, nodes) = network.split_once("\n\n").unwrap();
+    let nodes: std::collections::HashMap<&str, (&str, &str)> = nodes.lines()
+        .map(|line| (&line[0..3], (&line[7..10], &line[12..15])))
+        .collect();
+    let mut node = "AAA";
+    for instruction in instructions.chars().cycle().take(30) {
+        node = if instruction == 'L' { nodes[node].0 } else { nodes[node].1 };
+    }
+    assert_eq!(node, "ZZZ");
+
+    let garden = parse_grid(&garden_map(&mut rng, 10, 0.3));
+    assert_eq!((garden.len(), garden[5][5]), (11, 'S'));
+    assert!(garden[5].iter().chain(garden.iter().map(|row| &row[5])).all(|c| *c != '#'));
+}
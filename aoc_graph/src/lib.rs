@@ -0,0 +1,193 @@
+// Export for the days whose puzzles are really graphs. A model lists its nodes
+// and edges once, and gets DOT (for Graphviz) and node-link JSON (the format
+// networkx, d3 and friends read) for free.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GraphNode {
+    pub id: String,
+    pub label: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub label: Option<String>,
+    pub weight: Option<i64>,
+}
+
+impl GraphNode {
+    pub fn new(id: &str, label: &str) -> GraphNode {
+        GraphNode{id: id.to_string(), label: label.to_string()}
+    }
+}
+
+impl GraphEdge {
+    pub fn new(source: &str, target: &str) -> GraphEdge {
+        GraphEdge{source: source.to_string(), target: target.to_string(), label: None, weight: None}
+    }
+    pub fn with_label(mut self, label: &str) -> GraphEdge {
+        self.label = Some(label.to_string());
+        self
+    }
+    pub fn with_weight(mut self, weight: i64) -> GraphEdge {
+        self.weight = Some(weight);
+        self
+    }
+}
+
+pub trait GraphExport {
+    fn graph_nodes(&self) -> Vec<GraphNode>;
+    fn graph_edges(&self) -> Vec<GraphEdge>;
+    fn graph_name(&self) -> String {
+        "graph".to_string()
+    }
+    fn is_directed(&self) -> bool {
+        true
+    }
+
+    fn to_dot(&self) -> String {
+        let (keyword, arrow) = if self.is_directed() { ("digraph", "->") } else { ("graph", "--") };
+        let mut dot = format!("{} {} {{\n", keyword, quote(&self.graph_name()));
+        for node in self.graph_nodes() {
+            dot.push_str(&format!("    {} [label={}];\n", quote(&node.id), quote(&node.label)));
+        }
+        for edge in self.graph_edges() {
+            let mut attributes: Vec<String> = Vec::new();
+            if let Some(label) = &edge.label {
+                attributes.push(format!("label={}", quote(label)));
+            }
+            if let Some(weight) = edge.weight {
+                attributes.push(format!("weight={}", weight));
+                if edge.label.is_none() {
+                    attributes.push(format!("label={}", quote(&weight.to_string())));
+                }
+            }
+            dot.push_str(&format!("    {} {} {}", quote(&edge.source), arrow, quote(&edge.target)));
+            if !attributes.is_empty() {
+                dot.push_str(&format!(" [{}]", attributes.join(", ")));
+            }
+            dot.push_str(";\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn to_node_link_json(&self) -> String {
+        let edges = self.graph_edges();
+        let mut pairs: Vec<(&str, &str)> = edges.iter().map(|edge| (edge.source.as_str(), edge.target.as_str())).collect();
+        if !self.is_directed() {
+            pairs = pairs.iter().map(|(a, b)| if a <= b { (*a, *b) } else { (*b, *a) }).collect();
+        }
+        pairs.sort_unstable();
+        let multigraph = pairs.windows(2).any(|pair| pair[0] == pair[1]);
+
+        let nodes: Vec<String> = self.graph_nodes().iter()
+            .map(|node| format!("{{\"id\": {}, \"label\": {}}}", quote(&node.id), quote(&node.label)))
+            .collect();
+        let links: Vec<String> = edges.iter()
+            .map(|edge| {
+                let mut link = format!("{{\"source\": {}, \"target\": {}", quote(&edge.source), quote(&edge.target));
+                if let Some(label) = &edge.label {
+                    link.push_str(&format!(", \"label\": {}", quote(label)));
+                }
+                if let Some(weight) = edge.weight {
+                    link.push_str(&format!(", \"weight\": {}", weight));
+                }
+                link.push('}');
+                link
+            })
+            .collect();
+        format!(
+            "{{\"directed\": {}, \"multigraph\": {}, \"graph\": {{\"name\": {}}}, \"nodes\": [{}], \"links\": [{}]}}",
+            self.is_directed(), multigraph, quote(&self.graph_name()), nodes.join(", "), links.join(", "),
+        )
+    }
+
+    // The graph in a format named on the command line, "dot" or "json"
+    fn export(&self, format: &str) -> Result<String, String> {
+        match format {
+            "dot" => Ok(self.to_dot()),
+            "json" => Ok(self.to_node_link_json()),
+            _ => Err(format!("Unknown graph format '{}', expected 'dot' or 'json'", format)),
+        }
+    }
+}
+
+// A double quoted string, escaped the same way for both DOT and JSON
+fn quote(string: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+
+#[cfg(test)]
+struct Triangle {
+    directed: bool,
+}
+
+#[cfg(test)]
+impl GraphExport for Triangle {
+    fn graph_nodes(&self) -> Vec<GraphNode> {
+        vec![GraphNode::new("a", "A"), GraphNode::new("b", "say \"b\""), GraphNode::new("c", "C")]
+    }
+    fn graph_edges(&self) -> Vec<GraphEdge> {
+        vec![
+            GraphEdge::new("a", "b").with_label("L"),
+            GraphEdge::new("b", "c").with_weight(3),
+            GraphEdge::new("c", "a").with_label("R").with_weight(-1),
+            GraphEdge::new("a", "c"),
+        ]
+    }
+    fn graph_name(&self) -> String {
+        "triangle".to_string()
+    }
+    fn is_directed(&self) -> bool {
+        self.directed
+    }
+}
+
+#[test]
+fn test_dot() {
+    let dot = Triangle{directed: true}.to_dot();
+    assert!(dot.starts_with("digraph \"triangle\" {\n"));
+    assert!(dot.contains("    \"b\" [label=\"say \\\"b\\\"\"];\n"));
+    assert!(dot.contains("    \"a\" -> \"b\" [label=\"L\"];\n"));
+    assert!(dot.contains("    \"b\" -> \"c\" [weight=3, label=\"3\"];\n"));
+    assert!(dot.contains("    \"c\" -> \"a\" [label=\"R\", weight=-1];\n"));
+    assert!(dot.contains("    \"a\" -> \"c\";\n"));
+    assert!(dot.ends_with("}\n"));
+    let dot = Triangle{directed: false}.to_dot();
+    assert!(dot.starts_with("graph \"triangle\" {\n"));
+    assert!(dot.contains("\"a\" -- \"b\""));
+}
+
+#[test]
+fn test_node_link_json() {
+    let json = Triangle{directed: true}.to_node_link_json();
+    assert!(json.starts_with("{\"directed\": true, \"multigraph\": false, \"graph\": {\"name\": \"triangle\"}, \"nodes\": [{\"id\": \"a\", \"label\": \"A\"}"));
+    assert!(json.contains("{\"source\": \"c\", \"target\": \"a\", \"label\": \"R\", \"weight\": -1}"));
+    assert!(json.contains("{\"source\": \"a\", \"target\": \"c\"}"));
+    // a -> c and c -> a are the same edge twice once direction is dropped
+    let json = Triangle{directed: false}.to_node_link_json();
+    assert!(json.starts_with("{\"directed\": false, \"multigraph\": true,"));
+    assert_eq!(quote("tab\there"), "\"tab\\u0009here\"");
+}
+
+#[test]
+fn test_export() {
+    let triangle = Triangle{directed: true};
+    assert_eq!(triangle.export("dot"), Ok(triangle.to_dot()));
+    assert_eq!(triangle.export("json"), Ok(triangle.to_node_link_json()));
+    assert!(triangle.export("svg").is_err());
+}
@@ -0,0 +1,238 @@
+// Grids with no fixed edges. A SparseGrid holds whatever cells have been set,
+// anywhere on the plane, and a Torus repeats one dense grid forever in every
+// direction. Either can be drawn as text over any window, the way the puzzles
+// draw them, with rows counting down from the top.
+use std::collections::HashMap;
+use aoc_coords::Vec2;
+
+pub type Point = Vec2<i64>;
+
+
+// Every point from `min` to `max` inclusive, which is empty if `min` is past `max`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Window {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Window {
+    pub fn new(min: Point, max: Point) -> Window {
+        Window{min, max}
+    }
+    pub fn around(point: Point) -> Window {
+        Window{min: point, max: point}
+    }
+    pub fn width(&self) -> i64 {
+        (self.max.x - self.min.x + 1).max(0)
+    }
+    pub fn height(&self) -> i64 {
+        (self.max.y - self.min.y + 1).max(0)
+    }
+    pub fn contains(&self, point: &Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+    // Grows the window just enough to hold `point`
+    pub fn include(&mut self, point: Point) {
+        self.min = Vec2::new(self.min.x.min(point.x), self.min.y.min(point.y));
+        self.max = Vec2::new(self.max.x.max(point.x), self.max.y.max(point.y));
+    }
+    // Top to bottom, then left to right
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let (min, max) = (self.min, self.max);
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Vec2::new(x, y)))
+    }
+    // A line of text per row, without a trailing newline
+    pub fn render(&self, mut draw: impl FnMut(Point) -> char) -> String {
+        let rows: Vec<String> = (self.min.y..=self.max.y)
+            .map(|y| (self.min.x..=self.max.x).map(|x| draw(Vec2::new(x, y))).collect())
+            .collect();
+        rows.join("\n")
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<V> {
+    cells: HashMap<Point, V>,
+}
+
+impl<V> Default for SparseGrid<V> {
+    fn default() -> Self {
+        SparseGrid::new()
+    }
+}
+
+impl<V> SparseGrid<V> {
+    pub fn new() -> SparseGrid<V> {
+        SparseGrid{cells: HashMap::new()}
+    }
+    // A cell for every character `parse` gives a value, with the first
+    // character of the first line at the origin
+    pub fn from_text(text: &str, mut parse: impl FnMut(char) -> Option<V>) -> SparseGrid<V> {
+        let mut grid = SparseGrid::new();
+        for (y, line) in text.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if let Some(value) = parse(c) {
+                    grid.insert(Vec2::new(x as i64, y as i64), value);
+                }
+            }
+        }
+        grid
+    }
+    pub fn insert(&mut self, point: Point, value: V) -> Option<V> {
+        self.cells.insert(point, value)
+    }
+    pub fn get(&self, point: &Point) -> Option<&V> {
+        self.cells.get(point)
+    }
+    pub fn get_mut(&mut self, point: &Point) -> Option<&mut V> {
+        self.cells.get_mut(point)
+    }
+    pub fn remove(&mut self, point: &Point) -> Option<V> {
+        self.cells.remove(point)
+    }
+    pub fn contains(&self, point: &Point) -> bool {
+        self.cells.contains_key(point)
+    }
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+    // In no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&Point, &V)> {
+        self.cells.iter()
+    }
+    // The smallest window holding every cell, or None if there are none
+    pub fn bounds(&self) -> Option<Window> {
+        let mut points = self.cells.keys();
+        let mut bounds = Window::around(*points.next()?);
+        for point in points {
+            bounds.include(*point);
+        }
+        Some(bounds)
+    }
+    pub fn render(&self, window: Window, mut draw: impl FnMut(Point, Option<&V>) -> char) -> String {
+        window.render(|point| draw(point, self.cells.get(&point)))
+    }
+    // Everything within the bounds
+    pub fn to_text(&self, draw: impl FnMut(Point, Option<&V>) -> char) -> String {
+        match self.bounds() {
+            Some(bounds) => self.render(bounds, draw),
+            None => String::new(),
+        }
+    }
+}
+
+impl<V> FromIterator<(Point, V)> for SparseGrid<V> {
+    fn from_iter<I: IntoIterator<Item = (Point, V)>>(iter: I) -> SparseGrid<V> {
+        SparseGrid{cells: iter.into_iter().collect()}
+    }
+}
+
+
+// A dense grid tiled over the whole plane. The original copy is tile (0, 0),
+// with its top left cell at the origin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Torus<V> {
+    rows: Vec<Vec<V>>,
+    width: usize,
+    height: usize,
+}
+
+impl<V> Torus<V> {
+    pub fn new(rows: Vec<Vec<V>>) -> Result<Torus<V>, String> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+        if width == 0 {
+            return Err("The grid is empty".to_string());
+        }
+        if let Some(i) = rows.iter().position(|row| row.len() != width) {
+            return Err(format!("Row {} has {} cells, where the first has {}", i, rows[i].len(), width));
+        }
+        Ok(Torus{rows, width, height})
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    // The tile a point is in, and the cell it lands on within that tile
+    pub fn locate(&self, point: Point) -> (Point, Vec2<usize>) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let tile = Vec2::new(point.x.div_euclid(width), point.y.div_euclid(height));
+        let cell = Vec2::new(point.x.rem_euclid(width) as usize, point.y.rem_euclid(height) as usize);
+        (tile, cell)
+    }
+    // The inverse of locate
+    pub fn point(&self, tile: Point, cell: Vec2<usize>) -> Point {
+        Vec2::new(tile.x * self.width as i64 + cell.x as i64, tile.y * self.height as i64 + cell.y as i64)
+    }
+    pub fn get(&self, point: Point) -> &V {
+        let (_, cell) = self.locate(point);
+        &self.rows[cell.y][cell.x]
+    }
+    pub fn render(&self, window: Window, mut draw: impl FnMut(Point, &V) -> char) -> String {
+        window.render(|point| draw(point, self.get(point)))
+    }
+}
+
+
+#[test]
+fn test_window() {
+    let mut window = Window::around(Vec2::new(2, -1));
+    window.include(Vec2::new(-1, 1));
+    assert_eq!(window, Window::new(Vec2::new(-1, -1), Vec2::new(2, 1)));
+    assert_eq!((window.width(), window.height()), (4, 3));
+    assert!(window.contains(&Vec2::new(0, 0)));
+    assert!(!window.contains(&Vec2::new(3, 0)));
+    let points: Vec<Point> = window.points().take(5).collect();
+    assert_eq!(points[3], Vec2::new(2, -1));
+    assert_eq!(points[4], Vec2::new(-1, 0));
+    assert_eq!(window.points().count(), 12);
+    let empty = Window::new(Vec2::new(1, 1), Vec2::new(0, 0));
+    assert_eq!((empty.width(), empty.points().count(), empty.render(|_| '#')), (0, 0, String::new()));
+}
+
+#[test]
+fn test_sparse_grid() {
+    let mut grid: SparseGrid<char> = SparseGrid::from_text("#..\n.#.", |c| (c == '#').then_some(c));
+    assert_eq!(grid.len(), 2);
+    assert_eq!(grid.bounds(), Some(Window::new(Vec2::new(0, 0), Vec2::new(1, 1))));
+    grid.insert(Vec2::new(-2, 3), 'x');
+    grid.insert(Vec2::new(-2, -1), 'y');
+    assert_eq!(grid.bounds(), Some(Window::new(Vec2::new(-2, -1), Vec2::new(1, 3))));
+    let draw = |_: Point, cell: Option<&char>| cell.copied().unwrap_or('.');
+    assert_eq!(grid.to_text(draw), "y...\n..#.\n...#\n....\nx...");
+    // Any window, whether it holds cells or not
+    assert_eq!(grid.render(Window::new(Vec2::new(0, 0), Vec2::new(2, 1)), draw), "#..\n.#.");
+    assert_eq!(grid.render(Window::new(Vec2::new(10, 10), Vec2::new(11, 10)), draw), "..");
+    assert_eq!(grid.remove(&Vec2::new(-2, 3)), Some('x'));
+    assert_eq!(grid.bounds(), Some(Window::new(Vec2::new(-2, -1), Vec2::new(1, 1))));
+    *grid.get_mut(&Vec2::new(0, 0)).unwrap() = 'o';
+    assert_eq!(grid.get(&Vec2::new(0, 0)), Some(&'o'));
+    let empty: SparseGrid<char> = SparseGrid::new();
+    assert_eq!((empty.bounds(), empty.to_text(draw)), (None, String::new()));
+    let collected: SparseGrid<u8> = [(Vec2::new(5, 5), 1)].into_iter().collect();
+    assert!(collected.contains(&Vec2::new(5, 5)));
+}
+
+#[test]
+fn test_torus() {
+    let torus = Torus::new(vec![vec!['a', 'b', 'c'], vec!['d', 'e', 'f']]).unwrap();
+    assert_eq!((torus.width(), torus.height()), (3, 2));
+    assert_eq!(torus.locate(Vec2::new(4, 1)), (Vec2::new(1, 0), Vec2::new(1, 1)));
+    assert_eq!(torus.locate(Vec2::new(-1, -1)), (Vec2::new(-1, -1), Vec2::new(2, 1)));
+    assert_eq!(torus.locate(Vec2::new(-3, -2)), (Vec2::new(-1, -1), Vec2::new(0, 0)));
+    for point in Window::new(Vec2::new(-7, -5), Vec2::new(7, 5)).points() {
+        let (tile, cell) = torus.locate(point);
+        assert_eq!(torus.point(tile, cell), point);
+    }
+    assert_eq!(*torus.get(Vec2::new(-4, 3)), 'f');
+    let text = torus.render(Window::new(Vec2::new(-2, -1), Vec2::new(3, 1)), |point, c| if point == Vec2::new(0, 0) { 'S' } else { *c });
+    assert_eq!(text, "efdefd\nbcSbca\nefdefd");
+    assert!(Torus::<char>::new(Vec::new()).is_err());
+    assert!(Torus::new(vec![vec!['a', 'b'], vec!['c']]).is_err());
+}
@@ -1,126 +1,293 @@
+use std::collections::{HashMap, VecDeque};
 
-use permutation::Permutation;
 
-fn parse_input() -> Vec<Vec<char>>{
-    let input: &str = include_str!("../input.txt");
-    let mut char_vec: Vec<char>;
-    let mut data: Vec<Vec<char>> = Vec::new();
-    for line in input.lines() {
-        char_vec = line.chars().collect();
-        data.push(char_vec)
+// A set of words and the digit each one spells
+#[derive(Clone, Debug)]
+struct Vocabulary {
+    words: Vec<(String, u32)>,
+}
+
+impl Vocabulary {
+    fn new(words: &[(&str, u32)]) -> Vocabulary {
+        Vocabulary{words: words.iter().map(|(word, value)| (word.to_string(), *value)).collect()}
+    }
+    fn digits() -> Vocabulary {
+        Vocabulary{words: (0..10).map(|value| (value.to_string(), value)).collect()}
+    }
+    fn english() -> Vocabulary {
+        Vocabulary::new(&[("one", 1), ("two", 2), ("three", 3), ("four", 4), ("five", 5), ("six", 6), ("seven", 7), ("eight", 8), ("nine", 9)])
+    }
+    fn french() -> Vocabulary {
+        Vocabulary::new(&[("un", 1), ("deux", 2), ("trois", 3), ("quatre", 4), ("cinq", 5), ("six", 6), ("sept", 7), ("huit", 8), ("neuf", 9)])
+    }
+    fn german() -> Vocabulary {
+        Vocabulary::new(&[("eins", 1), ("zwei", 2), ("drei", 3), ("vier", 4), ("fünf", 5), ("sechs", 6), ("sieben", 7), ("acht", 8), ("neun", 9)])
+    }
+    // Upper case so that they don't turn up inside lower case words. Numerals
+    // overlap each other, so these read best with `find_longest`.
+    fn roman() -> Vocabulary {
+        Vocabulary::new(&[("I", 1), ("II", 2), ("III", 3), ("IV", 4), ("V", 5), ("VI", 6), ("VII", 7), ("VIII", 8), ("IX", 9)])
+    }
+    fn by_name(name: &str) -> Result<Vocabulary, String> {
+        match name {
+            "english" => Ok(Vocabulary::english()),
+            "french" => Ok(Vocabulary::french()),
+            "german" => Ok(Vocabulary::german()),
+            "roman" => Ok(Vocabulary::roman()),
+            _ => Err(format!("Unknown language '{}'", name)),
+        }
     }
-    return data;
 }
 
 
-fn part1(){
-    let data: Vec<Vec<char>> = parse_input();
-    let mut total: u32 = 0;
-    let mut string_num: String;
-    let mut num_row: Vec<char>;
-    for row in data {
-        num_row = Vec::new();
-        for character in row.iter() {
-            if character.is_ascii_digit() {
-                num_row.push(*character)
+// Positions are in chars, `end` is exclusive
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Match {
+    start: usize,
+    end: usize,
+    value: u32,
+}
+
+
+// Aho-Corasick automaton over every registered word, so a line is scanned
+// once however many words there are, and overlapping words are all found.
+#[derive(Clone, Debug)]
+struct Matcher {
+    transitions: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    // (word length, value) for every word ending at each state
+    outputs: Vec<Vec<(usize, u32)>>,
+    // Read lines as leftmost longest matches instead of every match
+    longest: bool,
+}
+
+impl Matcher {
+    fn new(vocabularies: &[Vocabulary]) -> Matcher {
+        let mut matcher = Matcher{transitions: vec![HashMap::new()], fail: vec![0], outputs: vec![Vec::new()], longest: false};
+        for (word, value) in vocabularies.iter().flat_map(|vocabulary| vocabulary.words.iter()) {
+            let mut state: usize = 0;
+            for c in word.chars() {
+                state = match matcher.transitions[state].get(&c) {
+                    Some(next) => *next,
+                    None => {
+                        matcher.transitions.push(HashMap::new());
+                        matcher.fail.push(0);
+                        matcher.outputs.push(Vec::new());
+                        let next = matcher.transitions.len() - 1;
+                        matcher.transitions[state].insert(c, next);
+                        next
+                    }
+                };
+            }
+            // The same word in two vocabularies is still only one match
+            let output = (word.chars().count(), *value);
+            if !matcher.outputs[state].contains(&output) {
+                matcher.outputs[state].push(output);
             }
         }
-        string_num = String::new();
-        string_num.push(num_row[0]);
-        string_num.push(num_row[num_row.len() - 1]);
-        total += string_num.parse::<u32>().unwrap();
-    }
-    println!("{}", total);
-}
-
-fn map_word(word: &str) -> &str{
-    match word {
-        "one" | "1" => "1",
-        "two" | "2" => "2",
-        "three" | "3" => "3",
-        "four" | "4" => "4",
-        "five" | "5" => "5",
-        "six" | "6" => "6",
-        "seven" | "7" => "7",
-        "eight" | "8" => "8",
-        "nine" | "9" => "9",
-        &_ => todo!(),
-    }
-}
-
-fn part2(){
-    let data: Vec<Vec<char>> = parse_input();
-    let mut total: u32 = 0;
-    let mut string_num: String;
-    let mut num_row: Vec<&str>;
-    let mut indices: Vec<usize>;
-    let mut rnum_row: Vec<&str>;
-    let mut rindices: Vec<usize>;
-    let mut permutation: Permutation;
-    let mut rpermutation: Permutation;
-    let mut string_row: String;
-    let mut numbers_strings = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
-    for row in data {
-        indices = Vec::new();
-        num_row = Vec::new();
-        rindices = Vec::new();
-        rnum_row = Vec::new();
-        string_row = row.iter().collect();
-        for substring in numbers_strings.iter_mut() {
-            if string_row.contains(*substring) {
-                num_row.push(map_word(substring));
-                indices.push(string_row.find(*substring).unwrap());
-                rnum_row.push(map_word(substring));
-                rindices.push(string_row.rfind(*substring).unwrap());
+        // Breadth first, so every fail link points at an already finished state
+        let mut queue: VecDeque<usize> = matcher.transitions[0].values().copied().collect();
+        while let Some(state) = queue.pop_front() {
+            let children: Vec<(char, usize)> = matcher.transitions[state].iter().map(|(c, next)| (*c, *next)).collect();
+            for (c, child) in children {
+                let mut fallback = matcher.fail[state];
+                while fallback != 0 && !matcher.transitions[fallback].contains_key(&c) {
+                    fallback = matcher.fail[fallback];
+                }
+                let fail = matcher.transitions[fallback].get(&c).copied().filter(|next| *next != child).unwrap_or(0);
+                matcher.fail[child] = fail;
+                let inherited = matcher.outputs[fail].clone();
+                matcher.outputs[child].extend(inherited);
+                queue.push_back(child);
             }
         }
-        permutation = permutation::sort(indices.clone());
-        rpermutation = permutation::sort(rindices.clone());
-        num_row = permutation.apply_slice(&num_row);
-        rnum_row = rpermutation.apply_slice(&rnum_row);
-        string_num = String::new();
-        string_num.push_str(num_row[0]);
-        string_num.push_str(rnum_row[rnum_row.len() - 1]);
-        total += string_num.parse::<u32>().unwrap();
-    }
-    println!("{}", total);
-}
-
-
-fn part2_improved(){
-    let data: Vec<Vec<char>> = parse_input();
-    let mut total: u32 = 0;
-    let mut string_num: String;
-    let mut num_row: Vec<&str>;
-    let mut indices: Vec<usize>;
-    let mut permutation: Permutation;
-    let mut string_row: String;
-    let mut numbers_strings = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
-    for row in data {
-        indices = Vec::new();
-        num_row = Vec::new();
-        string_row = row.iter().collect();
-        for substring in numbers_strings.iter_mut() {
-            let matching_indices: Vec<_> = string_row.match_indices(*substring).collect();
-            for pair in matching_indices {
-                num_row.push(map_word(substring));
-                indices.push(pair.0);
+        matcher
+    }
+    fn with_longest(mut self, longest: bool) -> Matcher {
+        self.longest = longest;
+        self
+    }
+    // Every match, overlaps included, ordered by start and then longest first
+    fn find_all(&self, line: &str) -> Vec<Match> {
+        let mut matches: Vec<Match> = Vec::new();
+        let mut state: usize = 0;
+        for (i, c) in line.chars().enumerate() {
+            while state != 0 && !self.transitions[state].contains_key(&c) {
+                state = self.fail[state];
+            }
+            state = self.transitions[state].get(&c).copied().unwrap_or(0);
+            for (length, value) in &self.outputs[state] {
+                matches.push(Match{start: i + 1 - length, end: i + 1, value: *value});
+            }
+        }
+        matches.sort_by_key(|m| (m.start, usize::MAX - m.end));
+        matches
+    }
+    // Leftmost longest matches that don't overlap each other
+    fn find_longest(&self, line: &str) -> Vec<Match> {
+        let mut matches: Vec<Match> = Vec::new();
+        for m in self.find_all(line) {
+            if matches.last().is_none_or(|last| m.start >= last.end) {
+                matches.push(m);
             }
-            
         }
-        permutation = permutation::sort(indices.clone());
-        num_row = permutation.apply_slice(&num_row);
-        string_num = String::new();
-        string_num.push_str(num_row[0]);
-        string_num.push_str(num_row[num_row.len() - 1]);
-        total += string_num.parse::<u32>().unwrap();
+        matches
+    }
+    fn all_digits(&self, line: &str) -> Vec<u32> {
+        self.find_all(line).iter().map(|m| m.value).collect()
+    }
+    // The digits a line reads as, in order
+    fn digits(&self, line: &str) -> Vec<u32> {
+        if self.longest {
+            self.find_longest(line).iter().map(|m| m.value).collect()
+        } else {
+            self.all_digits(line)
+        }
+    }
+    fn first_and_last(&self, line: &str) -> Result<(u32, u32), String> {
+        let matches = if self.longest { self.find_longest(line) } else { self.find_all(line) };
+        match (matches.first(), matches.iter().max_by_key(|m| m.start)) {
+            (Some(first), Some(last)) => Ok((first.value, last.value)),
+            _ => Err(format!("No digits found in line '{}'", line)),
+        }
+    }
+    fn calibration_value(&self, line: &str) -> Result<u32, String> {
+        let (first, last) = self.first_and_last(line)?;
+        Ok(first * 10 + last)
+    }
+    fn calibration_total(&self, lines: &[&str]) -> Result<u32, String> {
+        lines.iter().map(|line| self.calibration_value(line)).sum()
+    }
+}
+
+
+fn parse_input(mut string: &str) -> Vec<&str>{
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    string.lines().collect()
+}
+
+#[test]
+fn test_examples() {
+    let digits = Matcher::new(&[Vocabulary::digits()]);
+    assert_eq!(digits.calibration_total(&parse_input(include_str!("../example.txt"))), Ok(142));
+    let words = Matcher::new(&[Vocabulary::english(), Vocabulary::digits()]);
+    // The puzzle's 281, plus 11 for the extra "one456one" line at the top of the file
+    assert_eq!(words.calibration_total(&parse_input(include_str!("../example2.txt"))), Ok(292));
+}
+
+#[test]
+fn test_overlaps() {
+    let words = Matcher::new(&[Vocabulary::english(), Vocabulary::digits()]);
+    assert_eq!(words.calibration_value("eightwo"), Ok(82));
+    assert_eq!(words.all_digits("xtwone3four"), vec![2, 1, 3, 4]);
+    assert_eq!(words.all_digits("oneighthreeightwo"), vec![1, 8, 3, 8, 2]);
+    assert_eq!(words.calibration_value("7"), Ok(77));
+    assert!(words.calibration_value("abc").is_err());
+    assert!(words.calibration_total(&["1two", "zero"]).is_err());
+}
+
+#[test]
+fn test_other_vocabularies() {
+    let roman = Matcher::new(&[Vocabulary::roman()]);
+    assert_eq!(roman.all_digits("VIII"), vec![8, 7, 6, 5, 3, 2, 1, 2, 1, 1]);
+    let values: Vec<u32> = roman.find_longest("xVIIIxIVxIX").iter().map(|m| m.value).collect();
+    assert_eq!(values, vec![8, 4, 9]);
+    // Vocabularies can be mixed, and share prefixes with each other
+    let mixed = Matcher::new(&[Vocabulary::french(), Vocabulary::german(), Vocabulary::english()]);
+    assert_eq!(mixed.all_digits("seinsechsixfünf"), vec![1, 6, 6, 5]);
+    assert_eq!(mixed.calibration_value("huitroisieben"), Ok(87));
+    // Every match ends on the I inside IX, the longest matches on the IX
+    assert_eq!(roman.calibration_value("VIIxIX"), Ok(71));
+    let roman = roman.with_longest(true);
+    assert_eq!(roman.calibration_value("VIIxIX"), Ok(79));
+    assert_eq!(roman.digits("xVIIIxIVxIX"), vec![8, 4, 9]);
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |args: &[&str]| parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    let options = args(&[]).unwrap();
+    assert_eq!((options.languages.len(), options.longest, options.show_digits), (1, false, false));
+    let options = args(&["--language", "french", "--language", "roman", "--longest", "--digits"]).unwrap();
+    assert_eq!((options.languages.len(), options.longest, options.show_digits), (2, true, true));
+    assert!(Matcher::new(&options.languages).with_longest(true).calibration_value("unVIII").is_ok());
+    assert_eq!(args(&["--language", "klingon"]).unwrap_err(), "Unknown language 'klingon'");
+    assert!(args(&["--language"]).is_err());
+    assert!(args(&["--shortest"]).is_err());
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    let matcher = Matcher::new(&[Vocabulary::english(), Vocabulary::digits(), Vocabulary::roman()]);
+    for input in aoc_gen::fuzz_regressions("day1_calibration_total") {
+        assert!(matcher.calibration_total(&parse_input(&input)).is_err(), "{:?}", input);
+    }
+}
+
+
+fn part1(){
+    let data = parse_input("");
+    let matcher = Matcher::new(&[Vocabulary::digits()]);
+    println!("{}", matcher.calibration_total(&data).unwrap());
+}
+
+
+fn part2(options: &Options){
+    let data = parse_input("");
+    let mut vocabularies = options.languages.clone();
+    vocabularies.push(Vocabulary::digits());
+    let matcher = Matcher::new(&vocabularies).with_longest(options.longest);
+    if options.show_digits {
+        for line in &data {
+            println!("{}: {:?}", line, matcher.digits(line));
+        }
+    }
+    match matcher.calibration_total(&data) {
+        Ok(total) => println!("{}", total),
+        Err(error) => eprintln!("{}", error),
+    }
+}
+
+
+const USAGE: &str = "Usage: day1 [--language <english|french|german|roman>]... [--longest] [--digits]";
+
+// Options for part 2, which reads spelled out digits
+#[derive(Clone, Debug)]
+struct Options {
+    languages: Vec<Vocabulary>,
+    longest: bool,
+    show_digits: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options{languages: Vec::new(), longest: false, show_digits: false};
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--language" => options.languages.push(Vocabulary::by_name(args.next().ok_or(USAGE)?)?),
+            "--longest" => options.longest = true,
+            "--digits" => options.show_digits = true,
+            _ => return Err(USAGE.to_string()),
+        }
     }
-    println!("{}", total);
+    if options.languages.is_empty() {
+        options.languages.push(Vocabulary::english());
+    }
+    Ok(options)
 }
 
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
     part1();
-    part2();
-    part2_improved();
+    part2(&options);
 }
@@ -1,7 +1,8 @@
 mod aoc_parser;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap};
 
 use aoc_parser::get_input_as_chars;
+use aoc_frames::{Frame, Output, Recorder};
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 enum NodeType {
@@ -19,58 +20,103 @@ struct Node {
     on_path: bool,
 }
 
-fn get_node_from_char(c: &char, coord: (usize, usize)) -> Node{
+// Every tile by its (row, column), counted from 1
+type Pipes = HashMap<(usize, usize), Node>;
+// The number of steps to the far side of the loop, and the tiles on it
+type Loop = (usize, BTreeSet<(usize, usize)>);
+
+fn get_node_from_char(c: &char, coord: (usize, usize)) -> Result<Node, String>{
     match c {
-        '.' => Node { coord: coord, connections: BTreeSet::new(), type_: NodeType::Ground, char_: '.', on_path: false },
-        'S' => Node { coord: coord, connections: BTreeSet::new(), type_: NodeType::Start, char_: 'S', on_path: false },
-        '|' => Node { coord: coord, connections: BTreeSet::from([(coord.0+1, coord.1), (coord.0-1, coord.1)]), type_: NodeType::Pipe, char_: '|', on_path: false },
-        '-' => Node { coord: coord, connections: BTreeSet::from([(coord.0, coord.1+1), (coord.0, coord.1-1)]), type_: NodeType::Pipe, char_: '-', on_path: false },
-        'L' => Node { coord: coord, connections: BTreeSet::from([(coord.0-1, coord.1), (coord.0, coord.1+1)]), type_: NodeType::Pipe, char_: 'L', on_path: false },
-        'J' => Node { coord: coord, connections: BTreeSet::from([(coord.0-1, coord.1), (coord.0, coord.1-1)]), type_: NodeType::Pipe, char_: 'J', on_path: false },
-        '7' => Node { coord: coord, connections: BTreeSet::from([(coord.0+1, coord.1), (coord.0, coord.1-1)]), type_: NodeType::Pipe, char_: '7', on_path: false },
-        'F' => Node { coord: coord, connections: BTreeSet::from([(coord.0+1, coord.1), (coord.0, coord.1+1)]), type_: NodeType::Pipe, char_: 'F', on_path: false },
-        _ => unreachable!(),
+        '.' => Ok(Node { coord, connections: BTreeSet::new(), type_: NodeType::Ground, char_: '.', on_path: false }),
+        'S' => Ok(Node { coord, connections: BTreeSet::new(), type_: NodeType::Start, char_: 'S', on_path: false }),
+        '|' => Ok(Node { coord, connections: BTreeSet::from([(coord.0+1, coord.1), (coord.0-1, coord.1)]), type_: NodeType::Pipe, char_: '|', on_path: false }),
+        '-' => Ok(Node { coord, connections: BTreeSet::from([(coord.0, coord.1+1), (coord.0, coord.1-1)]), type_: NodeType::Pipe, char_: '-', on_path: false }),
+        'L' => Ok(Node { coord, connections: BTreeSet::from([(coord.0-1, coord.1), (coord.0, coord.1+1)]), type_: NodeType::Pipe, char_: 'L', on_path: false }),
+        'J' => Ok(Node { coord, connections: BTreeSet::from([(coord.0-1, coord.1), (coord.0, coord.1-1)]), type_: NodeType::Pipe, char_: 'J', on_path: false }),
+        '7' => Ok(Node { coord, connections: BTreeSet::from([(coord.0+1, coord.1), (coord.0, coord.1-1)]), type_: NodeType::Pipe, char_: '7', on_path: false }),
+        'F' => Ok(Node { coord, connections: BTreeSet::from([(coord.0+1, coord.1), (coord.0, coord.1+1)]), type_: NodeType::Pipe, char_: 'F', on_path: false }),
+        _ => Err(format!("Unknown tile '{}' on row {}, column {}", c, coord.0, coord.1)),
     }
 }
 
-fn parse_input() -> (HashMap<(usize, usize), Node>, Node) {
-    let data: Vec<Vec<char>> = get_input_as_chars(include_str!("../input.txt"));
+fn parse_input(mut string: &str) -> Result<(Pipes, Node), String> {
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    let data: Vec<Vec<char>> = get_input_as_chars(string);
     let mut nodes: HashMap<(usize, usize), Node> = HashMap::new();
-    let mut start: Node = Node {coord: (99999, 99999), connections: BTreeSet::new(), type_: NodeType::Start, char_: 'S', on_path: true};
+    let mut start: Option<Node> = None;
     for (i, row) in data.iter().enumerate(){
         for (j, c) in row.iter().enumerate() {
-            let n = get_node_from_char(c, (i+1, j+1));
+            let n = get_node_from_char(c, (i+1, j+1))?;
             if n.type_ == NodeType::Start{
-                start = n.clone();
+                if start.is_some() {
+                    return Err(format!("A second start on row {}, column {}", i + 1, j + 1));
+                }
+                start = Some(n.clone());
             }
             nodes.insert((i+1, j+1), n);
         }
     }
-    (nodes, start)
+    let start = start.ok_or("There is no start tile")?;
+    Ok((nodes, start))
 }
 
 fn find_connecting_nodes(node: Node, nodes: HashMap<(usize, usize), Node>) -> Vec<(usize, usize)>{
     let mut connecting_nodes: Vec<(usize, usize)> = Vec::new();
     for (_k, n) in nodes {
         if n.connections.contains(&node.coord) {
-            connecting_nodes.push(n.coord.clone());
+            connecting_nodes.push(n.coord);
         }
     }
     connecting_nodes
 }
 
-fn find_loop(start: Node, nodes: HashMap<(usize, usize), Node>) -> (usize, BTreeSet<(usize, usize)>) {
+// The visited pipes in blue and the two ends walking round the loop in red
+fn to_frame(nodes: &HashMap<(usize, usize), Node>, visited: &BTreeSet<(usize, usize)>, current_nodes: &[(usize, usize)]) -> Frame {
+    let height = nodes.keys().map(|coord| coord.0).max().unwrap_or(0);
+    let width = nodes.keys().map(|coord| coord.1).max().unwrap_or(0);
+    let mut frame = Frame::new(width, height, '.');
+    for (coord, node) in nodes {
+        if current_nodes.contains(coord) {
+            frame.set_coloured(coord.0 - 1, coord.1 - 1, node.char_, [255, 60, 60]);
+        }
+        else if visited.contains(coord) {
+            frame.set_coloured(coord.0 - 1, coord.1 - 1, node.char_, [80, 160, 255]);
+        }
+        else {
+            frame.set(coord.0 - 1, coord.1 - 1, node.char_);
+        }
+    }
+    frame
+}
+
+fn find_loop(start: Node, nodes: Pipes) -> Result<Loop, String> {
+    find_loop_recorded(start, nodes, &mut Recorder::off())
+}
+
+// The recorder is offered a frame before each step of the walk, and one at the
+// end once both ends have met. Each step has to leave exactly two ends, or the
+// pipes don't make a single loop through the start.
+fn find_loop_recorded(start: Node, nodes: Pipes, recorder: &mut Recorder) -> Result<Loop, String> {
     let mut current_nodes: Vec<(usize, usize)> = find_connecting_nodes(start.clone(), nodes.clone());
     let mut counter: usize = 1;
     let mut visited: BTreeSet<(usize, usize)> = BTreeSet::from([start.coord]);
-    while current_nodes[0] != current_nodes[1] {
+    loop {
+        if current_nodes.len() != 2 {
+            return Err(format!("The loop splits into {} ends after {} steps", current_nodes.len(), counter));
+        }
+        if current_nodes[0] == current_nodes[1] {
+            break;
+        }
+        recorder.offer(|| to_frame(&nodes, &visited, &current_nodes));
         let mut next_steps: Vec<(usize, usize)> = Vec::new();
         for coord in current_nodes {
             visited.insert(coord);
-            let n: Node = nodes.get(&coord).unwrap().clone();
+            let n: Node = nodes.get(&coord).ok_or(format!("The loop leaves the grid at {:?}", coord))?.clone();
             for connection in n.connections{
                 if !visited.contains(&connection) {
-                    next_steps.push(connection.clone());
+                    next_steps.push(connection);
                 }
             }
         }
@@ -78,15 +124,67 @@ fn find_loop(start: Node, nodes: HashMap<(usize, usize), Node>) -> (usize, BTree
         current_nodes = next_steps;
     }
     visited.insert(current_nodes[0]);
-    (counter, visited)
+    recorder.offer(|| to_frame(&nodes, &visited, &current_nodes));
+    Ok((counter, visited))
+}
+
+#[test]
+fn test_find_loop(){
+    let (nodes, start) = parse_input(include_str!("../example1.txt")).unwrap();
+    let mut recorder = Recorder::new();
+    let (steps, visited) = find_loop_recorded(start, nodes, &mut recorder).unwrap();
+    assert_eq!((steps, visited.len()), (4, 8));
+    let frames = recorder.frames();
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[0].to_text(), include_str!("../example1.txt").trim_end());
+    assert_eq!(frames[0].colour(1, 1), Some([80, 160, 255]));
+    assert_eq!(frames[0].colour(1, 2), Some([255, 60, 60]));
+    assert_eq!(frames[3].colour(3, 3), Some([255, 60, 60]));
+    assert_eq!(frames[3].colour(2, 2), None);
+    let (nodes, start) = parse_input(include_str!("../example3.txt")).unwrap();
+    assert_eq!(find_loop(start, nodes).unwrap().0, 8);
+}
+
+
+#[test]
+fn test_parse_args(){
+    let args = |args: &[&str]| parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    assert_eq!(args(&[]), Ok(None));
+    assert_eq!(args(&["--play"]), Ok(Some(Output::Play)));
+    assert_eq!(args(&["--gif", "loop.gif"]), Ok(Some(Output::Gif("loop.gif".into()))));
+    assert_eq!(args(&["--ppm", "frames"]), Ok(Some(Output::Ppm("frames".into()))));
+    assert!(args(&["--gif"]).is_err());
+    assert!(args(&["--play", "--gif", "loop.gif"]).is_err());
 }
 
+#[test]
+fn test_generated_loops(){
+    for seed in 0..10 {
+        let mut rng = aoc_gen::Rng::new(seed);
+        let input = aoc_gen::pipe_loop(&mut rng, 41, 30, 0.0);
+        let tiles = input.chars().filter(|c| !".\n".contains(*c)).count();
+        let (nodes, start) = parse_input(&input).unwrap();
+        let (steps, visited) = find_loop(start, nodes).unwrap();
+        assert_eq!((steps * 2, visited.len()), (tiles, tiles));
+        // Junk pipes around the loop make no difference
+        let mut rng = aoc_gen::Rng::new(seed);
+        let (nodes, start) = parse_input(&aoc_gen::pipe_loop(&mut rng, 41, 30, 0.5)).unwrap();
+        assert_eq!(find_loop(start, nodes).unwrap().0 * 2, tiles);
+    }
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day10_find_loop") {
+        assert!(parse_input(&input).and_then(|(nodes, start)| find_loop(start, nodes)).is_err(), "{:?}", input);
+    }
+}
 
 fn part1(){
     let nodes: HashMap<(usize, usize), Node>;
     let start: Node;
-    (nodes, start) = parse_input();
-    println!("Part 1 Answer: {}", find_loop(start, nodes).0);
+    (nodes, start) = parse_input("").unwrap();
+    println!("Part 1 Answer: {}", find_loop(start, nodes).unwrap().0);
 }
 
 fn part2(){
@@ -95,8 +193,8 @@ fn part2(){
     let loop_nodes: BTreeSet<(usize, usize)>;
     let mut counter: usize = 0;
     let mut last_char: char = '.';
-    (nodes, start) = parse_input();
-    (_, loop_nodes) = find_loop(start, nodes.clone());
+    (nodes, start) = parse_input("").unwrap();
+    (_, loop_nodes) = find_loop(start, nodes.clone()).unwrap();
     let data: Vec<Vec<char>> = get_input_as_chars(include_str!("../input.txt"));
     for (i, row) in data.iter().enumerate(){
         let mut num_times_crossed_loop: i32 = 0;
@@ -130,8 +228,49 @@ fn part2(){
     println!("Part 2 Answer: {}", counter);
 }
 
+// Every 50th step of the walk round the puzzle's loop
+fn record(output: &Output) -> Result<(), String> {
+    let (nodes, start) = parse_input("")?;
+    let mut recorder = Recorder::new().with_frame_skip(49);
+    find_loop_recorded(start, nodes, &mut recorder)?;
+    recorder.output(output, "day10", 4)
+}
+
+const USAGE: &str = "Usage: day10 [--play | --gif <path> | --ppm <directory>]";
+
+fn parse_args(args: &[String]) -> Result<Option<Output>, String> {
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let next = match arg.as_str() {
+            "--play" => Output::Play,
+            "--gif" => Output::Gif(args.next().ok_or(USAGE)?.into()),
+            "--ppm" => Output::Ppm(args.next().ok_or(USAGE)?.into()),
+            _ => return Err(USAGE.to_string()),
+        };
+        if output.replace(next).is_some() {
+            return Err(USAGE.to_string());
+        }
+    }
+    Ok(output)
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let output = match parse_args(&args) {
+        Ok(output) => output,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
     part1();
     part2();
+    if let Some(output) = output {
+        if let Err(error) = record(&output) {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
 }
 
@@ -1,5 +1,5 @@
 mod aoc_parser;
-use std::collections::{HashSet, HashMap, BTreeSet};
+use std::collections::HashSet;
 use aoc_parser::get_input_as_chars;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -15,22 +15,24 @@ impl Galaxy {
     }
 }
 
-fn expand_the_universe2(offset: i64) -> HashMap<usize, Galaxy>{
-    let data: Vec<Vec<char>> = get_input_as_chars(include_str!("../input.txt"));
-    let mut empty_columns: HashSet<usize> = HashSet::from_iter(0..data[0].len());
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct GalaxyPair {
+    first: usize,
+    second: usize,
+    distance: i64,
+}
+
+// Every empty row and column becomes `factor` rows or columns wide
+fn expand_the_universe2(data: &[Vec<char>], factor: i64) -> Vec<Galaxy>{
+    let mut empty_columns: HashSet<usize> = HashSet::from_iter(0..data.first().map_or(0, |row| row.len()));
     let mut empty_rows: HashSet<usize> = HashSet::from_iter(0..data.len());
-    let mut galaxy_number: usize = 1;
-    let mut galaxies: HashMap<usize, Galaxy> = HashMap::new();
+    let mut galaxies: Vec<Galaxy> = Vec::new();
 
     for (i, row) in data.iter().enumerate() {
         for (j, c) in row.iter().enumerate(){
             if c == &'#' {
-                if empty_columns.contains(&j){
-                    empty_columns.remove(&j);
-                }
-                if empty_rows.contains(&i){
-                    empty_rows.remove(&i);
-                }
+                empty_columns.remove(&j);
+                empty_rows.remove(&i);
             }
         }
     }
@@ -38,96 +40,193 @@ fn expand_the_universe2(offset: i64) -> HashMap<usize, Galaxy>{
     for (i, row) in data.iter().enumerate(){
         let mut joffset: i64 = 0;
         if empty_rows.contains(&i){
-            ioffset += offset - 1;
+            ioffset += factor - 1;
         }
         for (j, c) in row.iter().enumerate(){
             if empty_columns.contains(&j){
-                joffset += offset - 1;
+                joffset += factor - 1;
             }
             if c == &'#' {
-                galaxies.insert(galaxy_number, Galaxy { number: galaxy_number, x: j as i64 + joffset, y: i as i64 + ioffset });
-                galaxy_number += 1;
+                galaxies.push(Galaxy { number: galaxies.len() + 1, x: j as i64 + joffset, y: i as i64 + ioffset });
             }
         }
     }
     galaxies
-    
 }
 
-fn expand_the_universe() -> Vec<Vec<char>>{
-    let data: Vec<Vec<char>> = get_input_as_chars(include_str!("../input.txt"));
-    let mut row_expanded: Vec<Vec<char>> = Vec::new();
-    let mut fully_expanded: Vec<Vec<char>> = Vec::new();
-    let mut empty_columns: HashSet<usize> = HashSet::from_iter(0..data.len());
+// Sum of |a - b| over every pair. Once sorted, each value is larger than the k
+// values before it, so it contributes value * k minus their running total.
+fn sum_of_axis_distances(mut values: Vec<i64>) -> i64 {
+    values.sort_unstable();
+    let mut prefix: i64 = 0;
+    let mut total: i64 = 0;
+    for (k, value) in values.iter().enumerate() {
+        total += value * k as i64 - prefix;
+        prefix += value;
+    }
+    total
+}
 
-    for row in data {
-        row_expanded.push(row.clone());
-        if row.clone().iter().all(|&item| item == '.') {
-            row_expanded.push(row.clone());
-        }
-        for (j, c) in row.iter().enumerate(){
-            if c == &'#' && empty_columns.contains(&j) {
-                empty_columns.remove(&j);
-            }
-        }
+// Manhattan distance splits into the two axes, so the pairs never need visiting
+fn sum_of_distances(galaxies: &[Galaxy]) -> i64 {
+    sum_of_axis_distances(galaxies.iter().map(|galaxy| galaxy.x).collect())
+        + sum_of_axis_distances(galaxies.iter().map(|galaxy| galaxy.y).collect())
+}
+
+fn distance_matrix(galaxies: &[Galaxy]) -> Vec<Vec<i64>> {
+    galaxies.iter().map(|galaxy| galaxies.iter().map(|other| galaxy.distance_to(*other)).collect()).collect()
+}
+
+// One row per galaxy, with the columns lined up
+fn show_matrix(matrix: &[Vec<i64>]) -> String {
+    let width = matrix.iter().flatten().map(|distance| distance.to_string().len()).max().unwrap_or(0);
+    let mut text = String::new();
+    for row in matrix {
+        let cells: Vec<String> = row.iter().map(|distance| format!("{:>width$}", distance, width = width)).collect();
+        text += &cells.join(" ");
+        text.push('\n');
     }
-    for row in row_expanded {
-        let mut new_row: Vec<char> = Vec::new();
-        for (j, c) in row.iter().enumerate(){
-            new_row.push(*c);
-            if empty_columns.contains(&j) {
-                new_row.push(*c);
+    text
+}
+
+fn make_pair(galaxy: Galaxy, other: Galaxy) -> GalaxyPair {
+    GalaxyPair{first: galaxy.number.min(other.number), second: galaxy.number.max(other.number), distance: galaxy.distance_to(other)}
+}
+
+// Sweeps across the galaxies by x, only looking ahead while the x gap alone
+// is still shorter than the best pair so far. Ties go to the lowest numbers.
+fn closest_pair(galaxies: &[Galaxy]) -> Option<GalaxyPair> {
+    let mut sorted: Vec<Galaxy> = galaxies.to_vec();
+    sorted.sort_by_key(|galaxy| (galaxy.x, galaxy.y));
+    let mut best: Option<GalaxyPair> = None;
+    for (k, galaxy) in sorted.iter().enumerate() {
+        for other in &sorted[k + 1..] {
+            if best.is_some_and(|pair| other.x - galaxy.x > pair.distance) {
+                break;
+            }
+            let pair = make_pair(*galaxy, *other);
+            if best.is_none_or(|best| (pair.distance, pair.first, pair.second) < (best.distance, best.first, best.second)) {
+                best = Some(pair);
             }
         }
-        fully_expanded.push(new_row);
     }
-    fully_expanded
+    best
 }
 
-fn find_galaxies(universe: Vec<Vec<char>>) -> HashMap<usize, Galaxy>{
-    let mut galaxy_number: usize = 1;
-    let mut galaxies: HashMap<usize, Galaxy> = HashMap::new();
-    for (i, row) in universe.iter().enumerate(){
-        for (j, c) in row.iter().enumerate(){
-            if c == &'#' {
-                galaxies.insert(galaxy_number, Galaxy { number: galaxy_number, x: j as i64, y: i as i64 });
-                galaxy_number += 1;
-            }
+// The farthest pair always sits at the extremes of x + y or x - y
+fn farthest_pair(galaxies: &[Galaxy]) -> Option<GalaxyPair> {
+    let rotations: [fn(&Galaxy) -> i64; 2] = [|galaxy| galaxy.x + galaxy.y, |galaxy| galaxy.x - galaxy.y];
+    rotations.iter()
+        .filter_map(|rotation| {
+            let low = galaxies.iter().min_by_key(|galaxy| rotation(galaxy))?;
+            let high = galaxies.iter().max_by_key(|galaxy| rotation(galaxy))?;
+            Some(make_pair(*low, *high))
+        })
+        .max_by_key(|pair| pair.distance)
+        .filter(|pair| pair.first != pair.second)
+}
+
+// The image has to be a rectangle of galaxies and empty space, since the empty
+// columns are found from the width of the first row
+fn parse_input(mut string: &str) -> Result<Vec<Vec<char>>, String>{
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    let data = get_input_as_chars(string);
+    let width = data.first().map_or(0, |row| row.len());
+    for (i, row) in data.iter().enumerate() {
+        if row.len() != width {
+            return Err(format!("Row {} is {} wide, but the first row is {}", i + 1, row.len(), width));
+        }
+        if let Some(c) = row.iter().find(|c| !".#".contains(**c)) {
+            return Err(format!("Unknown character '{}' on row {}", c, i + 1));
         }
     }
-    galaxies
+    Ok(data)
+}
+
+#[test]
+fn test_example() {
+    let data = parse_input(include_str!("../example.txt")).unwrap();
+    assert_eq!(sum_of_distances(&expand_the_universe2(&data, 2)), 374);
+    assert_eq!(sum_of_distances(&expand_the_universe2(&data, 10)), 1030);
+    assert_eq!(sum_of_distances(&expand_the_universe2(&data, 100)), 8410);
+
+    let galaxies = expand_the_universe2(&data, 2);
+    let matrix = distance_matrix(&galaxies);
+    assert_eq!((matrix[4][8], matrix[0][6], matrix[2][5], matrix[7][8]), (9, 15, 17, 5));
+    assert_eq!(matrix.iter().flatten().sum::<i64>(), 2 * 374);
+    let text = show_matrix(&matrix);
+    assert_eq!(text.lines().count(), 9);
+    assert!(text.starts_with(" 0  6  6  9"));
 }
 
-fn distances_between_galaxies(galaxies: HashMap<usize, Galaxy>) -> Vec<i64>{
-    let mut exhausted_pairs: BTreeSet<BTreeSet<usize>> = BTreeSet::new();
-    let mut distances: Vec<i64> = Vec::new();
-    for (k, galaxy) in galaxies.clone(){
-        for (m, galaxy2) in galaxies.clone() {
-            let pair: BTreeSet<usize> = BTreeSet::from([k, m]);
-            if !exhausted_pairs.contains(&pair){
-                let d = galaxy.distance_to(galaxy2);
-                distances.push(d);
-                exhausted_pairs.insert(pair);
+#[test]
+fn test_closest_and_farthest() {
+    let data = parse_input(include_str!("../example.txt")).unwrap();
+    for factor in [1, 2, 10, 1000000] {
+        let galaxies = expand_the_universe2(&data, factor);
+        let mut pairs: Vec<GalaxyPair> = Vec::new();
+        for (k, galaxy) in galaxies.iter().enumerate() {
+            for other in &galaxies[k + 1..] {
+                pairs.push(make_pair(*galaxy, *other));
             }
         }
+        let closest = closest_pair(&galaxies).unwrap();
+        assert_eq!(Some(closest), pairs.iter().copied().min_by_key(|pair| (pair.distance, pair.first, pair.second)));
+        let farthest = farthest_pair(&galaxies).unwrap();
+        assert_eq!(farthest.distance, pairs.iter().map(|pair| pair.distance).max().unwrap());
+    }
+    let lonely = expand_the_universe2(&parse_input("..\n.#").unwrap(), 2);
+    assert_eq!(closest_pair(&lonely), None);
+    assert_eq!(farthest_pair(&lonely), None);
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day11_parse_input") {
+        assert!(parse_input(&input).is_err(), "{:?}", input);
     }
-    distances
 }
 
 fn part1(){
-    let data = expand_the_universe();
-    let galaxies = find_galaxies(data);
-    let distances = distances_between_galaxies(galaxies);
-    println!("Part 1 Answer: {}", distances.iter().sum::<i64>());
+    let galaxies = expand_the_universe2(&parse_input("").unwrap(), 2);
+    println!("Part 1 Answer: {}", sum_of_distances(&galaxies));
 }
 
 fn part2(){
-    let galaxies = expand_the_universe2(1000000);
-    let distances = distances_between_galaxies(galaxies);
-    println!("Part 2 Answer: {}", distances.iter().sum::<i64>());
+    let galaxies = expand_the_universe2(&parse_input("").unwrap(), 1000000);
+    println!("Part 2 Answer: {}", sum_of_distances(&galaxies));
+    if let (Some(closest), Some(farthest)) = (closest_pair(&galaxies), farthest_pair(&galaxies)) {
+        println!("Closest pair: {} and {} ({}), farthest pair: {} and {} ({})",
+            closest.first, closest.second, closest.distance, farthest.first, farthest.second, farthest.distance);
+    }
+}
+
+const USAGE: &str = "Usage: day11 [--matrix]";
+
+fn parse_args(args: &[String]) -> Result<bool, String> {
+    let mut matrix = false;
+    for arg in args {
+        match arg.as_str() {
+            "--matrix" => matrix = true,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(matrix)
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match parse_args(&args) {
+        // The distances between the galaxies as part 1 expands them
+        Ok(true) => print!("{}", show_matrix(&distance_matrix(&expand_the_universe2(&parse_input("").unwrap(), 2)))),
+        Ok(false) => (),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
     part1();
     part2();
 }
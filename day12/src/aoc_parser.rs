@@ -1,5 +1,5 @@
 #[allow(dead_code)]
-pub fn get_input_as_lines(input: &'static str) -> Vec<&'static str>{
+pub fn get_input_as_lines(input: &str) -> Vec<&str>{
     let mut data: Vec<&str> = Vec::new();
     for line in input.lines() {
         data.push(line)
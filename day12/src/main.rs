@@ -1,9 +1,8 @@
 mod aoc_parser;
 use aoc_parser::get_input_as_lines;
-use itertools::Itertools;
 use std::mem::replace;
 use std::time::Instant;
-use std::collections::{HashSet, BTreeSet, VecDeque};
+use std::collections::VecDeque;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Springs(Vec<i8>);
@@ -66,8 +65,7 @@ impl PuzzleLine {
         //     println!("Filling line {:?} took {:2?}", set, timer3.elapsed()); 
         // }
         let mut stack: VecDeque<Vec<i8>> = VecDeque::from([self.springs.0.clone()]);
-        while stack.len() > 0 {
-            let spring_set = stack.pop_back().unwrap();
+        while let Some(spring_set) = stack.pop_back() {
             let mut blanks_present: bool = false;
             for (i, element) in spring_set.clone().iter().enumerate() {
                 if element == &9{
@@ -78,6 +76,8 @@ impl PuzzleLine {
                     stack.push_front(new0);
                     stack.push_front(new1);
                     blanks_present = true;
+                    // The later blanks are filled in from the two new sets
+                    break;
                 }                
             }
             if !blanks_present {
@@ -86,21 +86,6 @@ impl PuzzleLine {
         }
         arrangements
     }
-    fn fill_line(&self, set: Vec<&i8>) -> Springs {
-        let mut new_spring = Springs(Vec::new());
-        let mut counter: usize = 0;
-        for i in self.springs.0.iter() {
-            if i == &9 {
-                new_spring.0.push(*set[counter]);
-                counter += 1;
-            }
-            else {
-                new_spring.0.push(*i);
-            }
-        }
-        // dbg!(new_spring.clone());
-        new_spring
-    }
     fn are_springs_valid(&self, springs: Springs) -> bool{
         self.counts == springs.get_counts()
     }
@@ -133,30 +118,49 @@ impl PuzzleLine {
 //     possibilities
 // }
 
-fn parse_input() -> Vec<PuzzleLine> {
-    let data = get_input_as_lines(include_str!("../example2.txt"));
-    let mut lines: Vec<PuzzleLine> = Vec::new();
-    for row in data{
-        let binding: Vec<&str> = row.split_ascii_whitespace().collect();
-        let spring_str: Vec<char> = binding[0].chars().collect();
-        let counts: Vec<usize> = binding[1].split(',').map(|component| component.parse::<usize>().unwrap()).collect();
-        let mut springs: Vec<i8> = Vec::new();
-        for c in spring_str{
-            match c {
-                '.' => springs.push(0),
-                '#' => springs.push(1),
-                '?' => springs.push(9),
-                _ => (),
-            }
+// A line is the springs, then the sizes of the damaged groups
+fn parse_line(row: &str) -> Result<PuzzleLine, String> {
+    let binding: Vec<&str> = row.split_ascii_whitespace().collect();
+    if binding.len() != 2 {
+        return Err(format!("Expected springs and counts, found '{}'", row));
+    }
+    let mut springs: Vec<i8> = Vec::new();
+    for c in binding[0].chars(){
+        match c {
+            '.' => springs.push(0),
+            '#' => springs.push(1),
+            '?' => springs.push(9),
+            _ => return Err(format!("Unknown spring '{}'", c)),
         }
-        let p = PuzzleLine{springs: Springs(springs), counts};
-        lines.push(p);
     }
-    lines
+    let mut counts: Vec<usize> = Vec::new();
+    for component in binding[1].split(',') {
+        counts.push(component.parse::<usize>().map_err(|_| format!("Invalid count '{}'", component))?);
+    }
+    let p = PuzzleLine{springs: Springs(springs), counts};
+    // Every damaged spring has to be in a group, and the blanks have to be
+    // able to make up the rest
+    let damaged = p.springs.0.iter().filter(|&n| *n == 1).count();
+    match p.counts.iter().try_fold(0usize, |total, count| total.checked_add(*count)) {
+        Some(total) if total >= damaged && total - damaged <= p.get_num_blanks() => Ok(p),
+        _ => Err(format!("The counts in '{}' can't fit its springs", row)),
+    }
+}
+
+fn parse_input(mut string: &str) -> Result<Vec<PuzzleLine>, String> {
+    if string.is_empty(){
+        string = include_str!("../example2.txt");
+    }
+    let data = get_input_as_lines(string);
+    let mut lines: Vec<PuzzleLine> = Vec::new();
+    for (i, row) in data.iter().enumerate(){
+        lines.push(parse_line(row).map_err(|e| format!("Line {}: {}", i + 1, e))?);
+    }
+    Ok(lines)
 }
 
 fn part1() {
-    let lines = parse_input();
+    let lines = parse_input("").unwrap();
     let mut total: usize = 0;
     for line in lines{
         let num = line.get_num_valid_arrangements();
@@ -177,11 +181,44 @@ fn test_springs_counts(){
 }
 
 #[test]
-fn test_combinations(){
-    let springs = BTreeSet::from([0,1,1,1,0,0,1,1,0,0,1,0]);
-    let sets = springs.0.iter().combinations_w(springs.0.len());
-    dbg!(sets);
+fn test_arrangements(){
+    let line = PuzzleLine{springs: Springs(vec![9, 9, 9, 0, 1, 1, 1]), counts: vec![1, 1, 3]};
+    assert_eq!(line.get_possible_spring_arrangements().len(), 8);
+    assert_eq!(line.get_num_valid_arrangements(), 1);
+}
+
+#[test]
+fn test_arrangements_are_distinct(){
+    // Each set only has its first blank filled, so every arrangement turns up
+    // once rather than once for each order the blanks could be filled in
+    let line = PuzzleLine{springs: Springs(vec![9, 0, 9, 9, 1, 9]), counts: vec![1, 1]};
+    let arrangements = line.get_possible_spring_arrangements();
+    let distinct: std::collections::HashSet<&Vec<i8>> = arrangements.iter().map(|springs| &springs.0).collect();
+    assert_eq!((arrangements.len(), distinct.len()), (16, 16));
+}
 
+#[test]
+fn test_example(){
+    let lines = parse_input(include_str!("../example.txt")).unwrap();
+    let counts: Vec<usize> = lines.iter().map(|line| line.get_num_valid_arrangements()).collect();
+    assert_eq!(counts, vec![1, 4, 1, 1, 4, 10]);
+}
+
+#[test]
+fn test_generated_records(){
+    // Records the size of the real ones, with half of each row unknown
+    let mut rng = aoc_gen::Rng::new(12);
+    for line in parse_input(&aoc_gen::spring_records(&mut rng, 200, 20, 0.5)).unwrap() {
+        let arrangements = line.get_num_valid_arrangements();
+        assert!(arrangements >= 1 && arrangements <= 1 << line.get_num_blanks(), "{:?}", line);
+    }
+}
+
+#[test]
+fn test_fuzz_regressions(){
+    for input in aoc_gen::fuzz_regressions("day12_parse_input") {
+        assert!(parse_input(&input).is_err(), "{:?}", input);
+    }
 }
 
 fn main() {
@@ -1,3 +1,64 @@
+// Rows of ash '.' and rocks '#'
+type Pattern = Vec<Vec<char>>;
+
+
+// Patterns are separated by blank lines, and each has to be rectangular
+fn parse_input(mut string: &str) -> Result<Vec<Pattern>, String>{
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    let mut patterns: Vec<Pattern> = vec![Vec::new()];
+    for (i, line) in string.lines().enumerate() {
+        let pattern = patterns.last_mut().unwrap();
+        if line.is_empty() {
+            if pattern.is_empty() {
+                return Err(format!("Line {} starts an empty pattern", i + 1));
+            }
+            patterns.push(Vec::new());
+            continue;
+        }
+        if let Some(c) = line.chars().find(|c| !".#".contains(*c)) {
+            return Err(format!("Unknown character '{}' on line {}", c, i + 1));
+        }
+        let row: Vec<char> = line.chars().collect();
+        if let Some(first) = pattern.first() {
+            if row.len() != first.len() {
+                return Err(format!("Line {} is {} wide, but its pattern is {}", i + 1, row.len(), first.len()));
+            }
+        }
+        pattern.push(row);
+    }
+    if patterns.last().unwrap().is_empty() {
+        return Err("The last pattern is empty".to_string());
+    }
+    Ok(patterns)
+}
+
+#[test]
+fn test_example() {
+    let patterns = parse_input(include_str!("../example.txt")).unwrap();
+    assert_eq!(patterns.len(), 2);
+    assert!(patterns.iter().all(|pattern| pattern.len() == 7 && pattern[0].len() == 9));
+    assert_eq!(patterns[1][3], "#####.##.".chars().collect::<Vec<char>>());
+}
+
+#[test]
+fn test_generated_patterns() {
+    let notes = aoc_gen::mirror_patterns(&mut aoc_gen::Rng::new(13), 50, 15);
+    assert_eq!(parse_input(&notes).unwrap().len(), 50);
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day13_parse_input") {
+        assert!(parse_input(&input).is_err(), "{:?}", input);
+    }
+}
+
+
 fn main() {
-    println!("Hello, world!");
+    match parse_input("") {
+        Ok(patterns) => println!("Read {} patterns", patterns.len()),
+        Err(error) => eprintln!("{}", error),
+    }
 }
@@ -1,7 +1,9 @@
 mod aoc_parser;
-use std::collections::{HashMap, BTreeSet, BTreeMap};
+use std::collections::HashMap;
+use std::time::Duration;
 
 use aoc_parser::get_input_as_chars;
+use aoc_frames::{Frame, Recorder};
 
 #[derive(Clone, Debug, Copy)]
 enum Direction{
@@ -11,140 +13,309 @@ enum Direction{
     West,
 }
 
+const SPIN_CYCLE: &str = "NWSE";
+const USAGE: &str = "Usage: day14 [program] [load edge] [--export | --display] [--play <repetitions>]";
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-struct Platform {
-    height: i32,
-    width: i32,
-    grid: HashMap<(i32, i32), char>,
-    rocks: BTreeSet<(i32, i32)>,
-    fixed: BTreeSet<(i32, i32)>,
+// Reads a tilt program such as "NWSE" or "N,E,N,W" into the directions it tilts in.
+fn parse_program(program: &str) -> Result<Vec<Direction>, String> {
+    let mut directions: Vec<Direction> = Vec::new();
+    for c in program.chars() {
+        match c.to_ascii_uppercase() {
+            'N' => directions.push(Direction::North),
+            'E' => directions.push(Direction::East),
+            'S' => directions.push(Direction::South),
+            'W' => directions.push(Direction::West),
+            ',' => (),
+            c if c.is_whitespace() => (),
+            c => return Err(format!("Unknown tilt direction '{}' in program \"{}\"", c, program)),
+        }
+    }
+    if directions.is_empty() {
+        return Err(format!("Tilt program \"{}\" contains no directions", program));
+    }
+    Ok(directions)
 }
 
-impl Platform {
-    fn tilt(&mut self, direction: Direction){
-        match direction {
-            Direction::North => self.tilt_north(), // toward i = 0
-            Direction::East => self.tilt_east(),  // toward j = width
-            Direction::South => self.tilt_south(),  // toward i = height
-            Direction::West => self.tilt_west(),  // toward j = 0
-        }
+fn parse_edge(edge: &str) -> Result<Direction, String> {
+    match parse_program(edge)?[..] {
+        [direction] => Ok(direction),
+        _ => Err(format!("The load edge must be a single direction, found \"{}\"", edge)),
     }
-    fn move_rock(&mut self, coord: (i32, i32), direction: Direction) {
-        assert!(self.rocks.contains(&coord));
-        let mut can_move: bool = true;
-        let mut new_coord: (i32, i32) = coord;
-        let mut trial_coord: (i32, i32);
+}
 
-        while can_move {
-            match direction{
-                Direction::North => trial_coord = (new_coord.0 - 1, new_coord.1),
-                Direction::East => trial_coord = (new_coord.0, new_coord.1 + 1),
-                Direction::South => trial_coord = (new_coord.0 + 1, new_coord.1),
-                Direction::West => trial_coord = (new_coord.0, new_coord.1 - 1),
+
+// How part 2 is run, from the command line. With no arguments it spins the
+// puzzle's cycle and weighs the load on the north edge.
+struct Options {
+    program: Vec<Direction>,
+    edge: Direction,
+    export: bool,
+    display: bool,
+    play: Option<u64>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options{program: parse_program(SPIN_CYCLE)?, edge: Direction::North, export: false, display: false, play: None};
+    let mut positional: Vec<&str> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--export" => options.export = true,
+            "--display" => options.display = true,
+            "--play" => {
+                let repetitions = args.next().ok_or(USAGE)?;
+                options.play = Some(repetitions.parse::<u64>().map_err(|_| format!("'{}' is not a number of repetitions", repetitions))?);
             }
-            if self.grid.contains_key(&trial_coord) 
-                && !self.fixed.contains(&trial_coord) 
-                && !self.rocks.contains(&trial_coord) {
-                    self.rocks.remove(&new_coord);
-                    self.rocks.insert(trial_coord);
-                    new_coord = trial_coord;
+            arg if arg.starts_with("--") => return Err(USAGE.to_string()),
+            arg => positional.push(arg),
+        }
+    }
+    match positional[..] {
+        [] => (),
+        [program] => options.program = parse_program(program)?,
+        [program, edge] => {
+            options.program = parse_program(program)?;
+            options.edge = parse_edge(edge)?;
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(options)
+}
+
+
+// A packed set of bits stored as `lines` runs of `line_length` bits each.
+// Each line starts on a fresh u64 so that ranges never straddle two lines.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct BitGrid {
+    lines: usize,
+    line_length: usize,
+    words_per_line: usize,
+    words: Vec<u64>,
+}
 
+impl BitGrid {
+    fn new(lines: usize, line_length: usize) -> BitGrid {
+        let words_per_line = line_length.div_ceil(64);
+        BitGrid{lines, line_length, words_per_line, words: vec![0; lines * words_per_line]}
+    }
+    fn get(&self, line: usize, pos: usize) -> bool {
+        self.words[line * self.words_per_line + pos / 64] & (1 << (pos % 64)) != 0
+    }
+    fn set(&mut self, line: usize, pos: usize) {
+        self.words[line * self.words_per_line + pos / 64] |= 1 << (pos % 64);
+    }
+    // Calls `f` with the word index and mask covering bits start..end of the line
+    fn for_each_word(&self, line: usize, start: usize, end: usize, mut f: impl FnMut(usize, u64)) {
+        let mut pos = start;
+        while pos < end {
+            let word_end = (pos / 64 + 1) * 64;
+            let stop = word_end.min(end);
+            let width = stop - pos;
+            let mask = if width == 64 { u64::MAX } else { ((1u64 << width) - 1) << (pos % 64) };
+            f(line * self.words_per_line + pos / 64, mask);
+            pos = stop;
+        }
+    }
+    fn count_range(&self, line: usize, start: usize, end: usize) -> usize {
+        let mut total: usize = 0;
+        self.for_each_word(line, start, end, |index, mask| total += (self.words[index] & mask).count_ones() as usize);
+        total
+    }
+    fn set_range(&mut self, line: usize, start: usize, end: usize, value: bool) {
+        let mut updates: Vec<(usize, u64)> = Vec::new();
+        self.for_each_word(line, start, end, |index, mask| updates.push((index, mask)));
+        for (index, mask) in updates {
+            if value {
+                self.words[index] |= mask;
             }
             else {
-                can_move = false;
+                self.words[index] &= !mask;
             }
         }
     }
-    fn tilt_north(&mut self){
-        for i in 0..self.height {
-            for j in 0..self.width {
-                if self.rocks.contains(&(i, j)) {
-                    self.move_rock((i, j), Direction::North)
+    fn ones(&self) -> Vec<(usize, usize)> {
+        let mut positions: Vec<(usize, usize)> = Vec::new();
+        for line in 0..self.lines {
+            for w in 0..self.words_per_line {
+                let mut word = self.words[line * self.words_per_line + w];
+                while word != 0 {
+                    positions.push((line, w * 64 + word.trailing_zeros() as usize));
+                    word &= word - 1;
                 }
             }
         }
+        positions
     }
-    fn tilt_west(&mut self){
-        for j in 0..self.width {
-            for i in 0..self.height {
-                if self.rocks.contains(&(i, j)) {
-                    self.move_rock((i, j), Direction::West)
-                }
-            }
+    fn transpose(&self) -> BitGrid {
+        let mut transposed = BitGrid::new(self.line_length, self.lines);
+        for (line, pos) in self.ones() {
+            transposed.set(pos, line);
         }
+        transposed
     }
-    fn tilt_east(&mut self){
-        for j in 0..self.width {
-            for i in 0..self.height {
-                if self.rocks.contains(&(i, self.width-1-j)) {
-                    self.move_rock((i, self.width-1-j), Direction::East)
-                }
-            }
+}
+
+
+// Which axis the rounded rocks are currently packed along.
+#[derive(Clone, Debug, Copy, Eq, PartialEq, Hash)]
+enum Layout {
+    Rows,
+    Columns,
+}
+
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Platform {
+    height: usize,
+    width: usize,
+    cubes: BitGrid,
+    rocks: BitGrid,
+    layout: Layout,
+    // Runs of open cells between cube rocks, as (start, end) with end exclusive
+    row_segments: Vec<Vec<(usize, usize)>>,
+    column_segments: Vec<Vec<(usize, usize)>>,
+}
+
+impl Platform {
+    fn tilt(&mut self, direction: Direction){
+        match direction {
+            Direction::North => self.slide(Layout::Columns, true), // toward i = 0
+            Direction::East => self.slide(Layout::Rows, false),  // toward j = width
+            Direction::South => self.slide(Layout::Columns, false),  // toward i = height
+            Direction::West => self.slide(Layout::Rows, true),  // toward j = 0
         }
     }
-    fn tilt_south(&mut self){
-        for i in 0..self.height {
-            for j in 0..self.width {
-                if self.rocks.contains(&(self.height - 1 - i, j)) {
-                    self.move_rock((self.height - 1 - i, j), Direction::South)
+    fn set_layout(&mut self, layout: Layout) {
+        if self.layout != layout {
+            self.rocks = self.rocks.transpose();
+            self.layout = layout;
+        }
+    }
+    // Every rock in a segment ends up stacked against one end of it, so a tilt
+    // only needs the number of rocks per segment.
+    fn slide(&mut self, layout: Layout, toward_start: bool) {
+        self.set_layout(layout);
+        let segments = match layout {
+            Layout::Rows => &self.row_segments,
+            Layout::Columns => &self.column_segments,
+        };
+        for (line, line_segments) in segments.iter().enumerate() {
+            for &(start, end) in line_segments {
+                let count = self.rocks.count_range(line, start, end);
+                if count == 0 || count == end - start {
+                    continue;
+                }
+                self.rocks.set_range(line, start, end, false);
+                if toward_start {
+                    self.rocks.set_range(line, start, start + count, true);
+                }
+                else {
+                    self.rocks.set_range(line, end - count, end, true);
                 }
             }
         }
     }
-    fn get_load(&self, direction: Direction) -> i32 {
-        let mut total: i32 = 0;
-        let mut amount: i32;
-        for coord in self.rocks.clone() {
+    fn contains_rock(&self, i: usize, j: usize) -> bool {
+        match self.layout {
+            Layout::Rows => self.rocks.get(i, j),
+            Layout::Columns => self.rocks.get(j, i),
+        }
+    }
+    fn rock_coords(&self) -> Vec<(usize, usize)> {
+        match self.layout {
+            Layout::Rows => self.rocks.ones(),
+            Layout::Columns => self.rocks.ones().into_iter().map(|(j, i)| (i, j)).collect(),
+        }
+    }
+    fn get_load(&self, direction: Direction) -> i64 {
+        let mut total: i64 = 0;
+        let mut amount: usize;
+        for coord in self.rock_coords() {
             match direction{
-                Direction::North => amount = (coord.0 - self.height).abs(),
+                Direction::North => amount = self.height - coord.0,
                 Direction::East => amount = coord.1 + 1,
                 Direction::South => amount = coord.0 + 1,
-                Direction::West => amount = (coord.1 - self.width).abs(),
+                Direction::West => amount = self.width - coord.1,
             }
-            total += amount
+            total += amount as i64
         }
         total
     }
-    fn get_load_after(&mut self, direction: Direction, cycles: i32) -> i32{
-        let mut cache: BTreeMap<BTreeSet<(i32, i32)>, i32> = BTreeMap::new();
-        let mut n: i32 = 0;
-        while n < cycles {
-            if cache.contains_key(&self.rocks){
-                let offset = cache.get(&self.rocks).unwrap();
+    fn get_load_after(&mut self, program: &[Direction], direction: Direction, repetitions: u64) -> i64{
+        self.run_program(program, repetitions);
+        self.get_load(direction)
+    }
+    // Runs the tilt sequence `repetitions` times, skipping ahead once a
+    // repeated arrangement shows the platform has entered a loop.
+    fn run_program(&mut self, program: &[Direction], repetitions: u64){
+        let mut cache: HashMap<BitGrid, u64> = HashMap::new();
+        let mut n: u64 = 0;
+        while n < repetitions {
+            self.set_layout(Layout::Rows);
+            if let Some(offset) = cache.get(&self.rocks) {
                 let state_length = n - offset;
-                let remaining = (cycles - offset) % state_length;
-                n = cycles - remaining;
+                n += (repetitions - n) / state_length * state_length;
+                cache.clear();
+                if n == repetitions {
+                    break;
+                }
             }
-            else {
-                cache.insert(self.rocks.clone(), n); 
+            cache.insert(self.rocks.clone(), n);
+            for direction in program {
+                self.tilt(*direction);
             }
-            self.tilt(Direction::North);
-            self.tilt(Direction::West);
-            self.tilt(Direction::South);
-            self.tilt(Direction::East);
             n += 1;
         }
-        self.get_load(direction)
     }
-    #[allow(dead_code)]
-    fn display(&self) -> String{
-        let mut display_string = String::new();
-        for i in 0..self.height{
-            let mut row = String::new();
+    // Steps through every tilt, with no skipping ahead, offering the recorder the
+    // starting platform and then the platform after each tilt.
+    fn record_program(&mut self, program: &[Direction], repetitions: u64, recorder: &mut Recorder){
+        recorder.offer(|| self.to_frame());
+        for _ in 0..repetitions {
+            for direction in program {
+                self.tilt(*direction);
+                recorder.offer(|| self.to_frame());
+            }
+        }
+    }
+    // Rounded rocks in orange
+    fn to_frame(&self) -> Frame {
+        let mut frame = Frame::new(self.width, self.height, '.');
+        for i in 0..self.height {
             for j in 0..self.width {
-                if self.rocks.contains(&(i, j)) {
-                    row.push('O');
-                }
-                else if self.fixed.contains(&(i,j)) {
-                    row.push('#')
-                }
-                else if self.grid.contains_key(&(i, j)){
-                    row.push('.');
+                match self.get_char(i, j) {
+                    'O' => frame.set_coloured(i, j, 'O', [255, 160, 40]),
+                    c => frame.set(i, j, c),
                 }
             }
-            display_string.push_str(&row.to_string());
-            display_string.push_str(&format!("--- {}", (i - self.height).abs()));
+        }
+        frame
+    }
+    fn get_char(&self, i: usize, j: usize) -> char {
+        if self.contains_rock(i, j) {
+            'O'
+        }
+        else if self.cubes.get(i, j) {
+            '#'
+        }
+        else {
+            '.'
+        }
+    }
+    // The platform in the same text format that `parse_input` reads
+    fn export(&self) -> String {
+        let mut rows: Vec<String> = Vec::new();
+        for i in 0..self.height {
+            rows.push((0..self.width).map(|j| self.get_char(i, j)).collect());
+        }
+        rows.join("\n")
+    }
+    fn display(&self) -> String{
+        let mut display_string = String::new();
+        for i in 0..self.height{
+            let row: String = (0..self.width).map(|j| self.get_char(i, j)).collect();
+            display_string.push_str(&row);
+            display_string.push_str(&format!("--- {}", self.height - i));
             display_string.push('\n');
         }
         display_string
@@ -152,25 +323,50 @@ impl Platform {
 }
 
 
-fn parse_input(mut string: &str) -> Platform{
+fn get_segments(cubes: &BitGrid) -> Vec<Vec<(usize, usize)>> {
+    let mut segments: Vec<Vec<(usize, usize)>> = Vec::new();
+    for line in 0..cubes.lines {
+        let mut line_segments: Vec<(usize, usize)> = Vec::new();
+        let mut start: usize = 0;
+        for pos in 0..=cubes.line_length {
+            if pos == cubes.line_length || cubes.get(line, pos) {
+                if pos > start {
+                    line_segments.push((start, pos));
+                }
+                start = pos + 1;
+            }
+        }
+        segments.push(line_segments);
+    }
+    segments
+}
+
+
+// Rows must all be the same length, as the bit grids are sized from the first
+fn parse_input(mut string: &str) -> Result<Platform, String>{
     if string.is_empty(){
         string = include_str!("../input.txt");
     }
     let data = get_input_as_chars(string);
-    let mut platform: HashMap<(i32, i32), char> = HashMap::new();
-    let mut fixed_rocks: BTreeSet<(i32, i32)> = BTreeSet::new();
-    let mut rocks: BTreeSet<(i32, i32)> = BTreeSet::new();
-    for (i, row) in data.clone().iter().enumerate(){
+    let height = data.len();
+    let width = data.first().map_or(0, |row| row.len());
+    if let Some(i) = data.iter().position(|row| row.len() != width) {
+        return Err(format!("Row {} has {} cells, where the first has {}", i + 1, data[i].len(), width));
+    }
+    let mut cubes = BitGrid::new(height, width);
+    let mut rocks = BitGrid::new(height, width);
+    for (i, row) in data.iter().enumerate(){
         for (j, c) in row.iter().enumerate(){
-            platform.insert((i as i32, j as i32), *c);
             match c {
-                '#' => _ = fixed_rocks.insert((i as i32, j as i32)),
-                'O' => _ = rocks.insert((i as i32, j as i32)),
+                '#' => cubes.set(i, j),
+                'O' => rocks.set(i, j),
                 _ => (),
             }
         }
     }
-    Platform{height: data.len() as i32, width: data[0].len() as i32, grid: platform, rocks, fixed: fixed_rocks}
+    let row_segments = get_segments(&cubes);
+    let column_segments = get_segments(&cubes.transpose());
+    Ok(Platform{height, width, cubes, rocks, layout: Layout::Rows, row_segments, column_segments})
 }
 
 #[test]
@@ -194,10 +390,10 @@ O.#..O.#.#
 ..O..#O..O
 .......O..
 #....###..
-#OO..#...";
-    let mut platform1 = parse_input(input);
-    let platform2 = parse_input(expected_input);
-    assert_eq!(platform1.get_load_after(Direction::North, 1), platform2.get_load(Direction::North));
+#OO..#....";
+    let mut platform1 = parse_input(input).unwrap();
+    let platform2 = parse_input(expected_input).unwrap();
+    assert_eq!(platform1.get_load_after(&parse_program(SPIN_CYCLE).unwrap(), Direction::North, 1), platform2.get_load(Direction::North));
 }
 
 
@@ -223,9 +419,9 @@ O.#..O.#.#
 .......O..
 #....###..
 #OO..#....";
-    let mut platform1 = parse_input(input);
-    let platform2 = parse_input(expected_input);
-    assert_eq!(platform1.get_load_after(Direction::North, 2), platform2.get_load(Direction::North));
+    let mut platform1 = parse_input(input).unwrap();
+    let platform2 = parse_input(expected_input).unwrap();
+    assert_eq!(platform1.get_load_after(&parse_program(SPIN_CYCLE).unwrap(), Direction::North, 2), platform2.get_load(Direction::North));
 }
 
 #[test]
@@ -250,24 +446,165 @@ O.#..O.#.#
 .......O..
 #....###..
 #OO..#....";
-    let mut platform1 = parse_input(input);
-    let platform2 = parse_input(expected_input);
-    assert_eq!(platform1.get_load_after(Direction::North, 3), platform2.get_load(Direction::North));
+    let mut platform1 = parse_input(input).unwrap();
+    let platform2 = parse_input(expected_input).unwrap();
+    assert_eq!(platform1.get_load_after(&parse_program(SPIN_CYCLE).unwrap(), Direction::North, 3), platform2.get_load(Direction::North));
+}
+
+#[test]
+pub fn test_example_answers(){
+    let mut platform = parse_input(include_str!("../example.txt")).unwrap();
+    platform.tilt(Direction::North);
+    assert_eq!(platform.get_load(Direction::North), 136);
+    let mut platform = parse_input(include_str!("../example.txt")).unwrap();
+    assert_eq!(platform.get_load_after(&parse_program(SPIN_CYCLE).unwrap(), Direction::North, 1_000_000_000), 64);
+}
+
+#[test]
+pub fn test_tilt_across_word_boundaries(){
+    // 200 columns so each row spans four u64 words, with a cube rock at 100
+    let mut row: Vec<char> = vec!['.'; 200];
+    row[100] = '#';
+    row[70] = 'O';
+    row[150] = 'O';
+    row[199] = 'O';
+    let line: String = row.iter().collect();
+    let input = format!("{}\n{}\n{}", line, line, line);
+    let mut platform = parse_input(&input).unwrap();
+    platform.tilt(Direction::West);
+    let display = platform.display();
+    let first_row = display.lines().next().unwrap();
+    assert_eq!(&first_row[..3], "O..");
+    assert_eq!(&first_row[99..104], ".#OO.");
+    platform.tilt(Direction::East);
+    platform.tilt(Direction::South);
+    assert_eq!(platform.get_load(Direction::North), 3 * (3 + 2 + 1));
+    assert_eq!(platform.get_load(Direction::East), 3 * (100 + 199 + 200));
+}
+
+#[test]
+pub fn test_uneven_rows(){
+    // A longer row would spill into the padding, or the next row, of the bit grids
+    assert_eq!(parse_input("O.#\n.O.#O").unwrap_err(), "Row 2 has 5 cells, where the first has 3");
+    assert!(parse_input("O.#\n.O").is_err());
+    assert!(parse_input("O.#\n.O.").is_ok());
+}
+
+#[test]
+pub fn test_fuzz_regressions(){
+    for input in aoc_gen::fuzz_regressions("day14_parse_input") {
+        assert!(parse_input(&input).is_err(), "{:?}", input);
+    }
+}
+
+#[test]
+pub fn test_parse_program(){
+    let program = parse_program("N, e,N W").unwrap();
+    assert_eq!(program.len(), 4);
+    assert!(matches!(program[1], Direction::East));
+    assert!(parse_program("NWX").is_err());
+    assert!(parse_program(" , ").is_err());
+}
+
+#[test]
+pub fn test_parse_args(){
+    let args = |args: &[&str]| parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    let options = args(&[]).unwrap();
+    assert_eq!(options.program.len(), 4);
+    assert!(matches!(options.edge, Direction::North));
+    assert!(!options.export && !options.display && options.play.is_none());
+    let options = args(&["N,E,N,W", "w", "--export", "--play", "3"]).unwrap();
+    assert!(matches!(options.program[..], [Direction::North, Direction::East, Direction::North, Direction::West]));
+    assert!(matches!(options.edge, Direction::West));
+    assert!(options.export);
+    assert_eq!(options.play, Some(3));
+    assert!(args(&["NWSE", "NE"]).is_err());
+    assert!(args(&["NWSE", "N", "S"]).is_err());
+    assert!(args(&["--play"]).is_err());
+    assert!(args(&["--play", "x"]).is_err());
+    assert_eq!(args(&["--loud"]).err(), Some(USAGE.to_string()));
+}
+
+#[test]
+pub fn test_run_program(){
+    let mut platform1 = parse_input(include_str!("../example.txt")).unwrap();
+    let mut platform2 = platform1.clone();
+    platform1.run_program(&parse_program("NWSE").unwrap(), 1_000_000_000);
+    assert_eq!(platform1.get_load(Direction::North), platform2.get_load_after(&parse_program(SPIN_CYCLE).unwrap(), Direction::North, 1_000_000_000));
+
+    // A long run of an alternative spin must land on the same state as stepping it directly
+    let program = parse_program("N,E,N,W").unwrap();
+    let mut skipped = parse_input(include_str!("../example.txt")).unwrap();
+    let mut stepped = skipped.clone();
+    skipped.run_program(&program, 1_001);
+    for _ in 0..1_001 {
+        for direction in &program {
+            stepped.tilt(*direction);
+        }
+    }
+    assert_eq!(skipped.export(), stepped.export());
+    assert_eq!(skipped.get_load(Direction::West), stepped.get_load(Direction::West));
+}
+
+#[test]
+pub fn test_export_round_trip(){
+    let mut platform = parse_input(include_str!("../example.txt")).unwrap();
+    assert_eq!(platform.export(), include_str!("../example.txt").trim_end());
+    platform.tilt(Direction::North);
+    let exported = platform.export();
+    assert_eq!(exported.lines().next().unwrap(), "OOOO.#.O..");
+    let reparsed = parse_input(&exported).unwrap();
+    for direction in [Direction::North, Direction::East, Direction::South, Direction::West] {
+        assert_eq!(reparsed.get_load(direction), platform.get_load(direction));
+    }
+}
+
+#[test]
+pub fn test_record_program(){
+    let mut platform = parse_input(include_str!("../example.txt")).unwrap();
+    let mut recorder = Recorder::new().with_frame_skip(1);
+    platform.record_program(&parse_program(SPIN_CYCLE).unwrap(), 3, &mut recorder);
+    // The start and 12 tilts, every other one kept
+    assert_eq!(recorder.frames().len(), 7);
+    assert_eq!(recorder.frames()[0].to_text(), include_str!("../example.txt").trim_end());
+    assert_eq!(recorder.frames()[6].to_text(), platform.export());
+    assert_eq!(recorder.frames()[6].colour(0, 0), None);
+    let first_rock = platform.rock_coords()[0];
+    assert_eq!(recorder.frames()[6].colour(first_rock.0, first_rock.1), Some([255, 160, 40]));
 }
 
 fn part1(){
-    let mut platform: Platform = parse_input("");
+    let mut platform: Platform = parse_input("").unwrap();
     platform.tilt(Direction::North);
     println!("Part 1 Answer: {}", platform.get_load(Direction::North));
 }
 
 
-fn part2(){
-    let mut platform: Platform = parse_input("");
-    println!("Part 2 Answer: {}", platform.get_load_after(Direction::North, 1_000_000_000));
+fn part2(options: &Options){
+    let mut platform: Platform = parse_input("").unwrap();
+    if let Some(repetitions) = options.play {
+        let mut recorder = Recorder::new();
+        platform.clone().record_program(&options.program, repetitions, &mut recorder);
+        recorder.play(Duration::from_millis(100));
+    }
+    println!("Part 2 Answer: {}", platform.get_load_after(&options.program, options.edge, 1_000_000_000));
+    if options.export {
+        println!("{}", platform.export());
+    }
+    if options.display {
+        print!("{}", platform.display());
+    }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
     part1();
-    part2();
+    part2(&options);
 }
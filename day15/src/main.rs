@@ -1,5 +1,6 @@
 mod aoc_parser;
-use std::collections::{VecDeque, HashMap};
+use std::collections::HashMap;
+use std::io::Write;
 
 use aoc_parser::get_input_as_lines;
 
@@ -11,43 +12,139 @@ struct Lens{
 }
 
 
-#[derive(Clone, Debug, Hash)]
+// Lenses in insertion order. Removing a lens leaves a gap rather than shifting
+// the rest down, so both operations are a single lookup in `slots`; the gaps are
+// squeezed out once they make up half the box.
+#[derive(Clone, Debug)]
 struct Box{
     number: u32,
-    lenses: VecDeque<Lens>,
+    lenses: Vec<Option<Lens>>,
+    slots: HashMap<String, usize>,
 }
 
 impl Box {
+    fn new(number: u32) -> Box {
+        Box{number, lenses: Vec::new(), slots: HashMap::new()}
+    }
     fn insert_lens(&mut self, new_lens: &Lens){
-        for i in 0..self.lenses.len() {
-            if self.lenses[i].label == new_lens.label {
-                self.lenses[i] = new_lens.clone();
-                break;
+        match self.slots.get(&new_lens.label) {
+            Some(slot) => self.lenses[*slot] = Some(new_lens.clone()),
+            None => {
+                self.slots.insert(new_lens.label.clone(), self.lenses.len());
+                self.lenses.push(Some(new_lens.clone()));
             }
         }
-        if !self.lenses.contains(new_lens){
-            self.lenses.push_back(new_lens.clone());
-        }
     }
     fn remove_lens(&mut self, label: &str){
-        for i in 0..self.lenses.len() {
-            if self.lenses[i].label == label {
-                self.lenses.remove(i);
-                break;
+        if let Some(slot) = self.slots.remove(label) {
+            self.lenses[slot] = None;
+            if self.slots.len() * 2 < self.lenses.len() {
+                self.compact();
+            }
+        }
+    }
+    fn compact(&mut self) {
+        self.lenses.retain(|lens| lens.is_some());
+        for (slot, lens) in self.lenses.iter().enumerate() {
+            self.slots.insert(lens.as_ref().unwrap().label.clone(), slot);
+        }
+    }
+    fn iter(&self) -> impl Iterator<Item = &Lens> {
+        self.lenses.iter().flatten()
+    }
+    fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+    // None if the power doesn't fit in a u64
+    fn get_box_power(&self) -> Option<u64> {
+        let mut total: u64 = 0;
+        for (i, lens) in self.iter().enumerate(){
+            let power = (self.number as u64 + 1).checked_mul(i as u64 + 1)?.checked_mul(lens.focal_length as u64)?;
+            total = total.checked_add(power)?;
+        }
+        Some(total)
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Operation {
+    Insert(u32),
+    Remove,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Instruction {
+    label: String,
+    operation: Operation,
+}
+
+impl Instruction {
+    fn box_number(&self) -> u32 {
+        hash_char_vector(self.label.chars().collect())
+    }
+}
+
+fn parse_instruction(string: &str) -> Result<Instruction, String> {
+    let (label, operation) = match string.split_once('=') {
+        Some((label, focal_length)) => {
+            let focal_length = focal_length.parse::<u32>().map_err(|_| format!("Invalid focal length in '{}'", string))?;
+            (label, Operation::Insert(focal_length))
+        }
+        None => match string.strip_suffix('-') {
+            Some(label) => (label, Operation::Remove),
+            None => return Err(format!("Expected 'label=N' or 'label-', found '{}'", string)),
+        },
+    };
+    if label.is_empty() || label.contains(['=', '-']) {
+        return Err(format!("Invalid label in '{}'", string));
+    }
+    Ok(Instruction{label: label.to_string(), operation})
+}
+
+
+#[derive(Clone, Debug)]
+struct Facility {
+    boxes: Vec<Box>,
+}
+
+impl Facility {
+    fn new() -> Facility {
+        Facility{boxes: (0..256).map(Box::new).collect()}
+    }
+    fn apply(&mut self, instruction: &Instruction) {
+        let box_ = &mut self.boxes[instruction.box_number() as usize];
+        match instruction.operation {
+            Operation::Insert(focal_length) => box_.insert_lens(&Lens{label: instruction.label.clone(), focal_length}),
+            Operation::Remove => box_.remove_lens(&instruction.label),
+        }
+    }
+    // Writes the boxes after every step to `trace`, as in the puzzle walkthrough
+    fn run(&mut self, steps: &[String], mut trace: Option<&mut dyn Write>) -> Result<(), String> {
+        for step in steps {
+            self.apply(&parse_instruction(step)?);
+            if let Some(out) = trace.as_mut() {
+                writeln!(out, "After \"{}\":\n{}", step, self.display()).map_err(|error| error.to_string())?;
             }
         }
+        Ok(())
     }
-    fn get_box_power(&self) -> u32 {
-        let mut total: u32 = 0;
-        for (i, lens) in self.lenses.iter().enumerate(){
-            total += (self.number + 1)*(i as u32+1)*lens.focal_length;
+    fn get_focusing_power(&self) -> Option<u64> {
+        self.boxes.iter().try_fold(0u64, |total, box_| total.checked_add(box_.get_box_power()?))
+    }
+    fn display(&self) -> String {
+        let mut display_string = String::new();
+        for box_ in self.boxes.iter().filter(|box_| !box_.is_empty()) {
+            let lenses: Vec<String> = box_.iter().map(|lens| format!("[{} {}]", lens.label, lens.focal_length)).collect();
+            display_string.push_str(&format!("Box {}: {}\n", box_.number, lenses.join(" ")));
         }
-        total
+        display_string
     }
 }
 
+
 fn hash_char(data: char) -> u32 {
-    return data as u32
+    data as u32
 }
 
 
@@ -57,7 +154,7 @@ fn hash_char_vector(data: Vec<char>) -> u32{
         let value = hash_char(c);
         current += value;
         current *= 17;
-        current = current % 256
+        current %= 256
     }
     current
 }
@@ -106,68 +203,94 @@ fn test_part1_example() {
 #[test]
 fn test_part2_example() {
     let s = r"rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
-    assert_eq!(part2(s), 145);
+    assert_eq!(part2(s, false), Some(145));
 }
 
+#[test]
+fn test_trace() {
+    let steps: Vec<String> = ["rn=1", "cm-", "qp=3"].iter().map(|step| step.to_string()).collect();
+    let mut trace: Vec<u8> = Vec::new();
+    Facility::new().run(&steps, Some(&mut trace)).unwrap();
+    let expected = "After \"rn=1\":\nBox 0: [rn 1]\n\nAfter \"cm-\":\nBox 0: [rn 1]\n\nAfter \"qp=3\":\nBox 0: [rn 1]\nBox 1: [qp 3]\n\n";
+    assert_eq!(String::from_utf8(trace).unwrap(), expected);
+}
 
-fn part1(string: &'static str) -> u32{
-    let data = parse_input(&string);
-    let mut total: u32 = 0;
-    for cvector in data{
-        total += hash_char_vector(cvector);
-    }
-    println!("Part 1 Answer: {}", total);
-    total
+#[test]
+fn test_large_focal_lengths() {
+    let mut facility = Facility::new();
+    let steps: Vec<String> = ["rn=4294967295", "cm=4294967295", "ab=7"].iter().map(|step| step.to_string()).collect();
+    facility.run(&steps, None).unwrap();
+    // Box 0 holds rn and cm, and ab is in box 3
+    assert_eq!(facility.get_focusing_power(), Some(3 * u32::MAX as u64 + 4 * 7));
 }
 
-fn get_label_details(characters: Vec<char>) -> (Vec<char>, String){
-    let index: usize;
-    if characters.contains(&'-'){
-        index = 1;
-    }
-    else if characters.contains(&'='){
-        index = 2;
+
+#[test]
+fn test_instructions() {
+    assert_eq!(parse_instruction("rn=1"), Ok(Instruction{label: "rn".to_string(), operation: Operation::Insert(1)}));
+    assert_eq!(parse_instruction("cm-"), Ok(Instruction{label: "cm".to_string(), operation: Operation::Remove}));
+    // Long labels and focal lengths above 9
+    let instruction = parse_instruction("abcdef=12").unwrap();
+    assert_eq!(instruction.operation, Operation::Insert(12));
+    assert_eq!(instruction.box_number(), hash_char_vector("abcdef".chars().collect()));
+    assert!(parse_instruction("rn").is_err());
+    assert!(parse_instruction("=4").is_err());
+    assert!(parse_instruction("rn=x").is_err());
+    assert!(parse_instruction("rn-=4").is_err());
+}
+
+#[test]
+fn test_walkthrough() {
+    let mut facility = Facility::new();
+    for step in ["rn=1", "cm-", "qp=3", "cm=2", "qp-", "pc=4"] {
+        facility.apply(&parse_instruction(step).unwrap());
     }
-    else {
-        unreachable!();
+    assert_eq!(facility.display(), "Box 0: [rn 1] [cm 2]\nBox 3: [pc 4]\n");
+    // These all land in box 1. Removing most of a box squeezes out the gaps without reordering
+    for step in ["aiw=1", "ajf=2", "axx=3", "ayg=4", "aiw-", "axx-", "ajf=7", "bgx=5", "ayg-"] {
+        facility.apply(&parse_instruction(step).unwrap());
     }
-    let label_chars: Vec<char> = characters[..(characters.len()-index)].to_vec();
-    let mut label: String = String::new();
-    for c in label_chars.clone(){
-        label.push(c);
+    let box_ = &facility.boxes[1];
+    let labels: Vec<&str> = box_.iter().map(|lens| lens.label.as_str()).collect();
+    assert_eq!(labels, vec!["ajf", "bgx"]);
+    assert_eq!(box_.lenses.len(), 2);
+    assert_eq!(facility.run(&["ot=9".to_string(), "bad".to_string()], None), Err("Expected 'label=N' or 'label-', found 'bad'".to_string()));
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day15_parse_instruction") {
+        assert!(parse_instruction(&input).is_err(), "{:?}", input);
     }
-    (label_chars, label)
 }
 
 
-fn part2(string: &'static str) -> u32{
-    let mut boxes: HashMap<u32, Box> = HashMap::new();
-    let data: Vec<Vec<char>> = parse_input(&string);
+fn part1(string: &'static str) -> u32{
+    let data = parse_input(string);
     let mut total: u32 = 0;
-    for instruction in data{
-        let label_details = get_label_details(instruction.clone());
-        let box_num: u32 = hash_char_vector(label_details.0);
-        if !boxes.contains_key(&box_num){
-            boxes.insert(box_num, Box { number: box_num, lenses: VecDeque::new() });
-        }
-        if instruction.contains(&'='){
-            let focal_length: u32 = instruction[instruction.len()-1].to_digit(10).unwrap();
-            let lens: Lens = Lens{ label: label_details.1, focal_length: focal_length};
-            boxes.get_mut(&box_num).unwrap().insert_lens(&lens);
-
-        }
-        else if instruction.contains(&'-'){
-            boxes.get_mut(&box_num).unwrap().remove_lens(&label_details.1);
-        }
+    for cvector in data{
+        total += hash_char_vector(cvector);
     }
-    for (_k, box_) in boxes{
-        total += box_.get_box_power();
+    println!("Part 1 Answer: {}", total);
+    total
+}
+
+// With --trace, the boxes are printed after every step
+fn part2(string: &'static str, trace: bool) -> Option<u64>{
+    let steps: Vec<String> = parse_input(string).iter().map(|step| step.iter().collect()).collect();
+    let mut facility = Facility::new();
+    let mut stdout = std::io::stdout().lock();
+    facility.run(&steps, if trace { Some(&mut stdout) } else { None }).unwrap();
+    let total = facility.get_focusing_power();
+    match total {
+        Some(total) => println!("Part 2 Answer: {}", total),
+        None => println!("Part 2: the focusing power is too big"),
     }
-    println!("Part 2 Answer: {}", total);
     total
 }
 
 fn main() {
+    let trace = std::env::args().skip(1).any(|arg| arg == "--trace");
     part1("");
-    part2("");
+    part2("", trace);
 }
@@ -1,7 +1,9 @@
 mod aoc_parser; 
-use std::{collections::{HashMap, BTreeSet, HashSet}, cmp::max};
+use std::{collections::{HashMap, HashSet}, cmp::max};
 
 use aoc_parser::get_input_as_chars;
+use aoc_frames::{Frame, Output, Recorder};
+use aoc_coords::Vec2;
 
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -21,6 +23,17 @@ enum Direction {
     West,
 }
 
+impl Direction {
+    // Rows count down, so north is -y
+    fn offset(&self) -> Vec2<i32> {
+        match self {
+            Direction::North => Vec2::new(0, -1),
+            Direction::East => Vec2::new(1, 0),
+            Direction::South => Vec2::new(0, 1),
+            Direction::West => Vec2::new(-1, 0),
+        }
+    }
+}
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 struct Coord {
@@ -28,6 +41,19 @@ struct Coord {
     j: i32,
 }
 
+// Rows are y and columns are x
+impl From<Coord> for Vec2<i32> {
+    fn from(coord: Coord) -> Vec2<i32> {
+        Vec2::new(coord.j, coord.i)
+    }
+}
+
+impl From<Vec2<i32>> for Coord {
+    fn from(vector: Vec2<i32>) -> Coord {
+        Coord{i: vector.y, j: vector.x}
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 struct Tile {
     coord: Coord,
@@ -40,19 +66,11 @@ struct Tile {
 struct Beam{
     direction: Direction,
     coord: Coord,
-    path:  Vec<Coord>,
-    history: BTreeSet<Vec<Coord>>,
 }
 
 impl Beam{
     fn advance(&mut self) {
-        match self.direction {
-            Direction::North => self.coord.i -= 1,
-            Direction::East => self.coord.j += 1,
-            Direction::South => self.coord.i += 1,
-            Direction::West => self.coord.j -= 1,
-        }
-        self.path.push(self.coord);
+        self.coord = (Vec2::from(self.coord) + self.direction.offset()).into();
     }
     fn advance_east(&mut self) {
         self.direction = Direction::East;
@@ -71,17 +89,15 @@ impl Beam{
         self.advance();
     }
     fn split_north_south(&mut self) -> (Beam, Beam) {
-        self.history.insert(self.path.clone());
-        let mut beam1: Beam = Beam{direction: Direction::North, coord: self.coord, path: Vec::from([self.coord]), history: self.history.clone()};
-        let mut beam2: Beam = Beam{direction: Direction::South, coord: self.coord, path: Vec::from([self.coord]), history: self.history.clone()};
+        let mut beam1: Beam = Beam{direction: Direction::North, coord: self.coord};
+        let mut beam2: Beam = Beam{direction: Direction::South, coord: self.coord};
         beam1.advance();
         beam2.advance();
         (beam1, beam2)
     }
     fn split_east_west(&mut self) -> (Beam, Beam) {
-        self.history.insert(self.path.clone());
-        let mut beam1: Beam = Beam{direction: Direction::East, coord: self.coord, path: Vec::from([self.coord]), history: self.history.clone()};
-        let mut beam2: Beam = Beam{direction: Direction::West, coord: self.coord, path: Vec::from([self.coord]), history: self.history.clone()};
+        let mut beam1: Beam = Beam{direction: Direction::East, coord: self.coord};
+        let mut beam2: Beam = Beam{direction: Direction::West, coord: self.coord};
         beam1.advance();
         beam2.advance();
         (beam1, beam2)
@@ -89,9 +105,12 @@ impl Beam{
 }
 
 
-fn parse_input() -> HashMap<Coord, Tile>{
+fn parse_input(mut string: &str) -> Result<HashMap<Coord, Tile>, String>{
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
     let mut tiles: HashMap<Coord, Tile> = HashMap::new();
-    let data: Vec<Vec<char>> = get_input_as_chars(include_str!("../input.txt"));
+    let data: Vec<Vec<char>> = get_input_as_chars(string);
     for (i, row) in data.iter().enumerate(){
         for (j, c) in row.iter().enumerate() {
             let coord: Coord = Coord{i: i as i32, j: j as i32};
@@ -101,13 +120,13 @@ fn parse_input() -> HashMap<Coord, Tile>{
                 '/' => Splitter::ForwardDiagonal,
                 '\\' => Splitter::BackwardDiagonal,
                 '.' => Splitter::None,
-                _ => unreachable!(),
+                _ => return Err(format!("Unknown tile '{}' on row {}, column {}", c, i + 1, j + 1)),
             };
             let new_tile = Tile{ coord, splitter, energised: 0};
             tiles.insert(coord, new_tile);
         }
      }
-     tiles
+     Ok(tiles)
 }
 
 fn count_energised_tiles(tiles: &HashMap<Coord, Tile>) -> usize {
@@ -120,9 +139,54 @@ fn count_energised_tiles(tiles: &HashMap<Coord, Tile>) -> usize {
     total_energised
 }
 
-fn beam_passes_tiles<'a>(tiles: &'a mut HashMap<Coord, Tile>, mut beam: Beam, cache: &'a mut HashSet<(Coord, Direction)>) -> &'a HashMap<Coord, Tile>{
+fn get_splitter_char(splitter: Splitter) -> char {
+    match splitter {
+        Splitter::Horizontal => '-',
+        Splitter::Vertical => '|',
+        Splitter::ForwardDiagonal => '/',
+        Splitter::BackwardDiagonal => '\\',
+        Splitter::None => '.',
+    }
+}
+
+// Energised tiles in yellow and the tile the beam is on in red
+fn to_frame(tiles: &HashMap<Coord, Tile>, head: Coord) -> Frame {
+    let height = tiles.keys().map(|coord| coord.i + 1).max().unwrap_or(0);
+    let width = tiles.keys().map(|coord| coord.j + 1).max().unwrap_or(0);
+    let mut frame = Frame::new(width as usize, height as usize, '.');
+    for tile in tiles.values() {
+        let (i, j) = (tile.coord.i as usize, tile.coord.j as usize);
+        let c = get_splitter_char(tile.splitter);
+        if tile.coord == head {
+            frame.set_coloured(i, j, c, [255, 60, 60]);
+        }
+        else if tile.energised > 0 {
+            frame.set_coloured(i, j, c, [255, 220, 80]);
+        }
+        else {
+            frame.set(i, j, c);
+        }
+    }
+    frame
+}
+
+// The recorder is offered a frame every time the beam, or one of the beams it
+// splits into, moves onto a tile. Splits are followed from a stack rather than
+// by recursing, since a big grid can nest thousands of them.
+fn beam_passes_tiles<'a>(tiles: &'a mut HashMap<Coord, Tile>, beam: Beam, cache: &'a mut HashSet<(Coord, Direction)>, recorder: &mut Recorder) -> &'a HashMap<Coord, Tile>{
+    let mut pending: Vec<Beam> = vec![beam];
+    while let Some(beam) = pending.pop() {
+        follow_beam(tiles, beam, cache, recorder, &mut pending);
+    }
+    tiles
+}
+
+// Walks one beam until it leaves the grid or retraces its steps. At each split
+// it carries on along one side and leaves the other on `pending`.
+fn follow_beam(tiles: &mut HashMap<Coord, Tile>, mut beam: Beam, cache: &mut HashSet<(Coord, Direction)>, recorder: &mut Recorder, pending: &mut Vec<Beam>) {
     while tiles.contains_key(&beam.coord) && !cache.contains(&(beam.coord, beam.direction)){
         cache.insert((beam.coord, beam.direction));
+        recorder.offer(|| to_frame(tiles, beam.coord));
         let current_tile: &mut Tile = tiles.get_mut(&beam.coord).unwrap();
         current_tile.energised += 1;
         let splitter_situation: (Direction, Splitter) = (beam.direction, current_tile.splitter);
@@ -146,59 +210,162 @@ fn beam_passes_tiles<'a>(tiles: &'a mut HashMap<Coord, Tile>, mut beam: Beam, ca
             (Direction::East, Splitter::Vertical) 
             | (Direction::West, Splitter::Vertical) => {
                 let beams: (Beam, Beam) = beam.split_north_south();
-                beam = beams.0;
-                beam_passes_tiles(tiles, beams.1, cache);
+                pending.push(beams.0);
+                beam = beams.1;
             },
             (Direction::North, Splitter::Horizontal) 
             | (Direction::South, Splitter::Horizontal) => {
                 let beams = beam.split_east_west();
-                beam = beams.0;
-                beam_passes_tiles(tiles, beams.1, cache);
+                pending.push(beams.0);
+                beam = beams.1;
             },
         }
     }
-    tiles
 }
     
 
 
 
-fn find_energised_tiles_from_start(start: Coord, direction: Direction) -> usize{
-    let mut tiles: HashMap<Coord, Tile> = parse_input();
+fn find_energised_tiles_from_start(tiles: &HashMap<Coord, Tile>, start: Coord, direction: Direction, recorder: &mut Recorder) -> usize{
+    let mut tiles: HashMap<Coord, Tile> = tiles.clone();
     let mut binding = HashSet::new();
     let new_tiles = beam_passes_tiles(&mut tiles, Beam{
         direction, 
-        coord: start, 
-        path: Vec::from([start]), 
-        history: BTreeSet::new()
-    }, &mut binding);
+        coord: start,
+    }, &mut binding, recorder);
     count_energised_tiles(new_tiles)
 }
 
+#[test]
+fn test_example(){
+    let tiles = parse_input(include_str!("../example.txt")).unwrap();
+    let mut recorder = Recorder::new().with_max_frames(4);
+    assert_eq!(find_energised_tiles_from_start(&tiles, Coord{i: 0, j: 0}, Direction::East, &mut recorder), 46);
+    // Right onto the first splitter, then down from it
+    let frames = recorder.frames();
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[0].to_text(), include_str!("../example.txt").trim_end());
+    assert_eq!(frames[0].colour(0, 0), Some([255, 60, 60]));
+    assert_eq!(frames[1].colour(0, 0), Some([255, 220, 80]));
+    assert_eq!(frames[3].colour(2, 1), Some([255, 60, 60]));
+    assert_eq!(frames[3].colour(0, 2), None);
+    assert_eq!(find_energised_tiles_from_start(&tiles, Coord{i: 0, j: 3}, Direction::South, &mut Recorder::off()), 51);
+}
+
+#[test]
+fn test_parse_args(){
+    let args = |args: &[&str]| parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    assert_eq!(args(&[]), Ok(None));
+    assert_eq!(args(&["--play"]), Ok(Some(Output::Play)));
+    assert_eq!(args(&["--gif", "beam.gif"]), Ok(Some(Output::Gif("beam.gif".into()))));
+    assert_eq!(args(&["--ppm", "frames"]), Ok(Some(Output::Ppm("frames".into()))));
+    assert!(args(&["--gif"]).is_err());
+    assert!(args(&["--play", "--gif", "beam.gif"]).is_err());
+}
+
+#[test]
+fn test_vec2_conversion(){
+    let coord = Coord{i: 3, j: -2};
+    assert_eq!(Vec2::from(coord), Vec2::new(-2, 3));
+    assert_eq!(Coord::from(Vec2::from(coord)), coord);
+    let mut beam = Beam{direction: Direction::North, coord: Coord{i: 0, j: 4}};
+    beam.advance();
+    assert_eq!(beam.coord, Coord{i: -1, j: 4});
+    beam.advance_west();
+    assert_eq!(beam.coord, Coord{i: -1, j: 3});
+}
+
+#[test]
+fn test_generated_grid(){
+    let mut rng = aoc_gen::Rng::new(16);
+    let tiles = parse_input(&aoc_gen::mirror_grid(&mut rng, 60, 60, 0.1)).unwrap();
+    let energised = find_energised_tiles_from_start(&tiles, Coord{i: 0, j: 0}, Direction::East, &mut Recorder::off());
+    assert!((1..=3600).contains(&energised));
+}
+
+#[test]
+fn test_deeply_split_beam(){
+    // Entering on the second row, the beam reaches most of the grid through
+    // thousands of nested splits, which used to overflow the stack
+    let mut rng = aoc_gen::Rng::new(16);
+    let tiles = parse_input(&aoc_gen::mirror_grid(&mut rng, 800, 800, 0.1)).unwrap();
+    let energised = find_energised_tiles_from_start(&tiles, Coord{i: 1, j: 0}, Direction::East, &mut Recorder::off());
+    assert!(energised > 500_000, "{}", energised);
+}
+
+#[test]
+fn test_fuzz_regressions(){
+    for input in aoc_gen::fuzz_regressions("day16_parse_input") {
+        assert!(parse_input(&input).is_err(), "{:?}", input);
+    }
+}
+
 fn part1(){
-    let answer = find_energised_tiles_from_start(Coord{i: 0, j: 0}, Direction::East);
+    let tiles = parse_input("").unwrap();
+    let answer = find_energised_tiles_from_start(&tiles, Coord{i: 0, j: 0}, Direction::East, &mut Recorder::off());
     println!("Part 1 Answer: {}", answer);
 }
 
 fn part2() {
     let mut max_number = 0;
+    let tiles = parse_input("").unwrap();
     let data = get_input_as_chars(include_str!("../input.txt"));
     let n = data.len();
     let m = data[0].len();
     for i in 0..n{
-        max_number = max(find_energised_tiles_from_start(Coord { i: i as i32, j: 0 }, Direction::West), max_number);
-        max_number = max(find_energised_tiles_from_start(Coord { i: i as i32, j: m as i32 - 1 }, Direction::East), max_number);
+        max_number = max(find_energised_tiles_from_start(&tiles, Coord { i: i as i32, j: 0 }, Direction::West, &mut Recorder::off()), max_number);
+        max_number = max(find_energised_tiles_from_start(&tiles, Coord { i: i as i32, j: m as i32 - 1 }, Direction::East, &mut Recorder::off()), max_number);
     }
     for j in 0..m{
-        max_number = max(find_energised_tiles_from_start(Coord { i: 0, j: j as i32}, Direction::South), max_number);
-        max_number = max(find_energised_tiles_from_start(Coord { i: n as i32 - 1, j: j as i32 }, Direction::North), max_number);
+        max_number = max(find_energised_tiles_from_start(&tiles, Coord { i: 0, j: j as i32}, Direction::South, &mut Recorder::off()), max_number);
+        max_number = max(find_energised_tiles_from_start(&tiles, Coord { i: n as i32 - 1, j: j as i32 }, Direction::North, &mut Recorder::off()), max_number);
     }
     
     println!("Part 2 Answer: {}", max_number);
 }
 
+// Every 20th tile part 1's beam moves onto
+fn record(output: &Output) -> Result<(), String> {
+    let tiles = parse_input("")?;
+    let mut recorder = Recorder::new().with_frame_skip(19);
+    find_energised_tiles_from_start(&tiles, Coord{i: 0, j: 0}, Direction::East, &mut recorder);
+    recorder.output(output, "day16", 4)
+}
+
+const USAGE: &str = "Usage: day16 [--play | --gif <path> | --ppm <directory>]";
+
+fn parse_args(args: &[String]) -> Result<Option<Output>, String> {
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let next = match arg.as_str() {
+            "--play" => Output::Play,
+            "--gif" => Output::Gif(args.next().ok_or(USAGE)?.into()),
+            "--ppm" => Output::Ppm(args.next().ok_or(USAGE)?.into()),
+            _ => return Err(USAGE.to_string()),
+        };
+        if output.replace(next).is_some() {
+            return Err(USAGE.to_string());
+        }
+    }
+    Ok(output)
+}
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let output = match parse_args(&args) {
+        Ok(output) => output,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
     part1();
     part2();
+    if let Some(output) = output {
+        if let Err(error) = record(&output) {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
 }
@@ -1,16 +1,10 @@
 mod aoc_parser;
-use std::collections::{HashMap, HashSet, BTreeSet};
-use std::cmp::min;
+use std::collections::BinaryHeap;
+use std::cmp::Reverse;
 use std::time::Instant;
 
 use aoc_parser::get_input_as_chars;
-
-#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
-enum BlockType{
-    Start,
-    End,
-    None,
-}
+use aoc_coords::Vec2;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
 enum Direction {
@@ -20,311 +14,371 @@ enum Direction {
     W,
 }
 
-
-#[derive(Clone, Debug, Eq, PartialEq)]
-struct Network {
-    blocks: HashMap<BigCoord, Block>,
-    visited: HashSet<Coord>,
-    limits: (usize, usize),
-}
-
-impl Network {
-    fn dijkstra0(&mut self) {
-        let current_block: Block = self.get_start_block();
-        // Start the queue with 2 nodes one going E one going S
-        // They start with 1 step!
-        let mut queue: BTreeSet<(usize, BigCoord)> = BTreeSet::from(
-            [(0, BigCoord::from_coord(current_block.coord, Direction::E)), 
-             (0, BigCoord::from_coord(current_block.coord, Direction::S))]);
-        let mut big_coords_done_with: HashSet<BigCoord> = HashSet::from([
-            BigCoord::from_coord(current_block.coord, Direction::E), 
-            BigCoord::from_coord(current_block.coord, Direction::S)]);
-        
-
-        let mut visited: HashSet<BigCoord> = HashSet::new();
-
-        while queue.len() > 0 {
-            let current_node = queue.pop_first().unwrap();
-            visited.insert(current_node.1);
-            // current_block = *self.blocks.get_mut(&current_node.1).unwrap();
-
-            let neighbors = get_neighbor_coords0(current_node.1, self.limits);
-            for neighbor in neighbors {
-                // if the coord hasn't been worked on (copy of queue) AND hasn't been visited yet, slam it in
-                if !big_coords_done_with.contains(&neighbor) && !visited.contains(&neighbor){
-                    // get the block object which contains weights and such
-                    let block = self.blocks.get_mut(&neighbor).unwrap();
-                    let alternative_distance = current_node.0 + block.loss;
-                    // Do the dijkstra bit
-                    if alternative_distance < block.weight {
-                        block.weight = alternative_distance;
-                    }
-                    // add neighbor to queue to be considered next!
-                    queue.insert((block.weight, neighbor));
-                    big_coords_done_with.insert(neighbor);
-                }
-            }
-        }
-
-    }
-    fn dijkstra2(&mut self) {
-        let current_block: Block = self.get_start_block();
-        // Start the queue with 2 nodes one going E one going S
-        // They start with 1 step!
-        let mut queue: BTreeSet<(usize, BigCoord)> = BTreeSet::from(
-            [(0, BigCoord::from_coord(current_block.coord, Direction::E)), 
-             (0, BigCoord::from_coord(current_block.coord, Direction::S))]);
-        let mut big_coords_done_with: HashSet<BigCoord> = HashSet::from([
-            BigCoord::from_coord(current_block.coord, Direction::E), 
-            BigCoord::from_coord(current_block.coord, Direction::S)]);
-        
-
-        let mut visited: HashSet<BigCoord> = HashSet::new();
-
-        while queue.len() > 0 {
-            let current_node = queue.pop_first().unwrap();
-            visited.insert(current_node.1);
-            // current_block = *self.blocks.get_mut(&current_node.1).unwrap();
-
-            let neighbors = get_neighbor_coords2(current_node.1, self.limits);
-            for neighbor in neighbors {
-                // if the coord hasn't been worked on (copy of queue) AND hasn't been visited yet, slam it in
-                if !big_coords_done_with.contains(&neighbor) && !visited.contains(&neighbor){
-                    // get the block object which contains weights and such
-                    let block = self.blocks.get_mut(&neighbor).unwrap();
-                    let alternative_distance = current_node.0 + block.loss;
-                    // Do the dijkstra bit
-                    if alternative_distance < block.weight {
-                        block.weight = alternative_distance;
-                    }
-                    // add neighbor to queue to be considered next!
-                    queue.insert((block.weight, neighbor));
-                    big_coords_done_with.insert(neighbor);
-                }
-            }
-        }
-
-    }
-    fn get_end_block_weights(&self) -> usize{
-        let mut min_weight: usize = 999999999;
-        for (_k, block) in self.blocks.clone(){
-            if block.type_ == BlockType::End {
-                min_weight = min(block.weight, min_weight);
-            }
+impl Direction {
+    fn index(&self) -> usize {
+        match self {
+            Direction::N => 0,
+            Direction::S => 1,
+            Direction::E => 2,
+            Direction::W => 3,
         }
-        min_weight
     }
-    fn get_end_block_weights2(&self) -> usize{
-        let mut min_weight: usize = 999999999;
-        for (k, block) in self.blocks.clone(){
-            if block.type_ == BlockType::End && k.steps >= 4 {
-                min_weight = min(block.weight, min_weight);
-            }
+    fn turns(&self) -> [Direction; 2] {
+        match self {
+            Direction::N | Direction::S => [Direction::E, Direction::W],
+            Direction::E | Direction::W => [Direction::N, Direction::S],
         }
-        min_weight
     }
-    fn get_start_block(&mut self) -> Block{
-        for block in self.blocks.values(){
-            if block.type_ == BlockType::Start {
-                return block.clone();
-            }
+    fn arrow(&self) -> char {
+        match self {
+            Direction::N => '^',
+            Direction::S => 'v',
+            Direction::E => '>',
+            Direction::W => '<',
         }
-        unreachable!();
     }
 }
 
+const DIRECTIONS: [Direction; 4] = [Direction::N, Direction::S, Direction::E, Direction::W];
+
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
 struct Coord {
     i: usize,
     j: usize,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
-struct BigCoord {
-    coord: Coord,
-    direction: Direction,
-    steps: usize,
+// Rows are y and columns are x
+impl From<Coord> for Vec2<usize> {
+    fn from(coord: Coord) -> Vec2<usize> {
+        Vec2::new(coord.j, coord.i)
+    }
 }
 
-impl BigCoord {
-    fn from_coord(coord: Coord, direction: Direction) -> Self{
-        Self{coord, direction, steps: 0}
+impl From<Vec2<usize>> for Coord {
+    fn from(vector: Vec2<usize>) -> Coord {
+        Coord{i: vector.y, j: vector.x}
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-struct Block {
-    coord: Coord,
-    loss: usize,
-    weight: usize,
-    type_: BlockType,
+
+// How far a crucible must, and may, travel in a straight line before turning.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Crucible {
+    min_straight: usize,
+    max_straight: usize,
 }
 
+const CRUCIBLE: Crucible = Crucible{min_straight: 1, max_straight: 3};
+const ULTRA_CRUCIBLE: Crucible = Crucible{min_straight: 4, max_straight: 10};
 
-fn get_neighbor_coords2(start: BigCoord, limits: (usize, usize)) -> HashSet<BigCoord>{
-    let mut coords = HashSet::new();
-    if start.steps >= 4{
-        if start.coord.i > 0 && start.direction != Direction::S {
-            let new = Coord{i: start.coord.i - 1, j: start.coord.j};
-            if start.direction == Direction::N && start.steps < 10 {
-                coords.insert(BigCoord{coord: new, direction: Direction::N, steps: start.steps + 1});
-            }
-            else if start.direction != Direction::N {
-                coords.insert(BigCoord{coord: new, direction: Direction::N, steps: 1});
-            }  
-        }
-        if start.coord.j > 0 && start.direction != Direction::E {
-            let new = Coord{i: start.coord.i, j: start.coord.j - 1};
-            if start.direction == Direction::W && start.steps < 10 {
-                coords.insert(BigCoord{coord: new, direction: Direction::W, steps: start.steps + 1});
-            }
-            else if start.direction != Direction::W {
-                coords.insert(BigCoord{coord: new, direction: Direction::W, steps: 1});
-            }  
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Route {
+    heat_loss: usize,
+    // Every block entered after the start, with the direction it was entered in
+    steps: Vec<(Coord, Direction)>,
+}
+
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct City {
+    losses: Vec<Vec<usize>>,
+    height: usize,
+    width: usize,
+}
+
+impl City {
+    // None off any edge of the map, including below zero
+    fn step(&self, coord: Coord, direction: Direction) -> Option<Coord> {
+        let vector = Vec2::from(coord);
+        let next = match direction {
+            Direction::N => Vec2::new(vector.x, vector.y.checked_sub(1)?),
+            Direction::S => vector + Vec2::new(0, 1),
+            Direction::W => Vec2::new(vector.x.checked_sub(1)?, vector.y),
+            Direction::E => vector + Vec2::new(1, 0),
+        };
+        (next.x < self.width && next.y < self.height).then_some(next.into())
+    }
+    fn state_index(&self, coord: Coord, direction: Direction) -> usize {
+        (coord.i * self.width + coord.j) * 4 + direction.index()
+    }
+    // Dijkstra over (block, direction of the last straight run). Each edge is a
+    // whole run of between min_straight and max_straight blocks followed by a turn,
+    // so the step count never needs to be part of the state.
+    fn find_route(&self, crucible: Crucible, start: Coord, goal: Coord) -> Option<Route> {
+        if start == goal {
+            return Some(Route{heat_loss: 0, steps: Vec::new()});
         }
-        if start.coord.i < limits.0 && start.direction != Direction::N {
-            let new = Coord{i: start.coord.i + 1, j: start.coord.j};
-            if start.direction == Direction::S && start.steps < 10 {
-                coords.insert(BigCoord{coord: new, direction: Direction::S, steps: start.steps + 1});
+        let states = self.height * self.width * 4;
+        let mut distances: Vec<usize> = vec![usize::MAX; states];
+        let mut previous: Vec<Option<(Coord, Direction)>> = vec![None; states];
+        let mut queue: BinaryHeap<Reverse<(usize, Coord, Option<Direction>)>> = BinaryHeap::new();
+        queue.push(Reverse((0, start, None)));
+
+        while let Some(Reverse((distance, coord, last))) = queue.pop() {
+            if let Some(direction) = last {
+                if distance > distances[self.state_index(coord, direction)] {
+                    continue;
+                }
+                if coord == goal {
+                    return Some(self.trace_route(distance, &previous, start, (coord, direction)));
+                }
             }
-            else if start.direction != Direction::S {
-                coords.insert(BigCoord{coord: new, direction: Direction::S, steps: 1});
+            let next_directions: Vec<Direction> = match last {
+                Some(direction) => direction.turns().to_vec(),
+                None => DIRECTIONS.to_vec(),
+            };
+            for direction in next_directions {
+                let mut current = coord;
+                let mut loss = distance;
+                for run in 1..=crucible.max_straight {
+                    match self.step(current, direction) {
+                        Some(next) => current = next,
+                        None => break,
+                    }
+                    loss += self.losses[current.i][current.j];
+                    if run < crucible.min_straight {
+                        continue;
+                    }
+                    let index = self.state_index(current, direction);
+                    if loss < distances[index] {
+                        distances[index] = loss;
+                        previous[index] = last.map(|d| (coord, d));
+                        queue.push(Reverse((loss, current, Some(direction))));
+                    }
+                }
             }
         }
-        if start.coord.j < limits.1 && start.direction != Direction::W {
-            let new = Coord{i: start.coord.i, j: start.coord.j + 1};  
-            if start.direction == Direction::E && start.steps < 10 {
-                coords.insert(BigCoord{coord: new, direction: Direction::E, steps: start.steps + 1});
+        None
+    }
+    fn trace_route(&self, heat_loss: usize, previous: &[Option<(Coord, Direction)>], start: Coord, end: (Coord, Direction)) -> Route {
+        let mut runs: Vec<(Coord, Direction)> = vec![end];
+        while let Some(before) = previous[self.state_index(runs[runs.len() - 1].0, runs[runs.len() - 1].1)] {
+            runs.push(before);
+        }
+        runs.reverse();
+        // Each run begins where the one before it ended
+        let mut steps: Vec<(Coord, Direction)> = Vec::new();
+        let mut current = start;
+        for (run_end, direction) in runs {
+            while current != run_end {
+                current = self.step(current, direction).unwrap();
+                steps.push((current, direction));
             }
-            else if start.direction != Direction::E {
-                coords.insert(BigCoord{coord: new, direction: Direction::E, steps: 1});
-            }  
         }
+        Route{heat_loss, steps}
     }
-    else {
-        match start.direction{
-            Direction::E => {
-                if start.coord.j < limits.1{
-                    coords.insert(BigCoord{coord: Coord{i: start.coord.i, j: start.coord.j + 1}, direction: Direction::E, steps: start.steps + 1});
-                }
-            },
-            Direction::N => {
-                if start.coord.i > 0 {
-                    coords.insert(BigCoord{coord: Coord{i: start.coord.i - 1, j: start.coord.j}, direction: Direction::N, steps: start.steps + 1});
-                }
-            },
-            Direction::S =>  {
-                if start.coord.i < limits.0 {
-                    coords.insert(BigCoord{coord: Coord{i: start.coord.i + 1, j: start.coord.j}, direction: Direction::S, steps: start.steps + 1});
-                }
-            },
-            Direction::W =>  {
-                if start.coord.j > 0 {
-                    coords.insert(BigCoord{coord: Coord{i: start.coord.i, j: start.coord.j - 1}, direction: Direction::W, steps: start.steps + 1});
-                }
-            },
-        };
+    // The grid with the route drawn over it in arrows, as in the puzzle text
+    fn render_route(&self, route: &Route) -> String {
+        let mut cells: Vec<Vec<char>> = self.losses.iter()
+            .map(|row| row.iter().map(|loss| char::from_digit(*loss as u32, 10).unwrap()).collect())
+            .collect();
+        for (coord, direction) in &route.steps {
+            cells[coord.i][coord.j] = direction.arrow();
+        }
+        let rows: Vec<String> = cells.iter().map(|row| row.iter().collect()).collect();
+        rows.join("\n")
     }
-    coords
 }
 
-fn get_neighbor_coords0(start: BigCoord, limits: (usize, usize)) -> HashSet<BigCoord>{
-    let mut coords = HashSet::new();
-    if start.coord.i > 0 && start.direction != Direction::S {
-        let new = Coord{i: start.coord.i - 1, j: start.coord.j};
-        if start.direction == Direction::N && start.steps < 3 {
-            coords.insert(BigCoord{coord: new, direction: Direction::N, steps: start.steps + 1});
-        }
-        else if start.direction != Direction::N {
-            coords.insert(BigCoord{coord: new, direction: Direction::N, steps: 1});
-        }  
+
+fn parse_row(row: &[char], i: usize) -> Result<Vec<usize>, String> {
+    row.iter()
+        .map(|c| c.to_digit(10).map(|loss| loss as usize).ok_or(format!("Invalid heat loss '{}' on row {}", c, i + 1)))
+        .collect()
+}
+
+fn parse_input(mut string: &str) -> Result<City, String>{
+    if string.is_empty(){
+        string = include_str!("../input.txt");
     }
-    if start.coord.j > 0 && start.direction != Direction::E {
-        let new = Coord{i: start.coord.i, j: start.coord.j - 1};
-        if start.direction == Direction::W && start.steps < 3 {
-            coords.insert(BigCoord{coord: new, direction: Direction::W, steps: start.steps + 1});
-        }
-        else if start.direction != Direction::W {
-            coords.insert(BigCoord{coord: new, direction: Direction::W, steps: 1});
-        }  
+    let data = get_input_as_chars(string);
+    let losses: Vec<Vec<usize>> = data.iter().enumerate()
+        .map(|(i, row)| parse_row(row, i))
+        .collect::<Result<Vec<Vec<usize>>, String>>()?;
+    let height = losses.len();
+    let width = losses.first().map_or(0, |row| row.len());
+    if width == 0 {
+        return Err("The map is empty".to_string());
+    }
+    if let Some(i) = losses.iter().position(|row| row.len() != width) {
+        return Err(format!("Row {} has {} blocks, where the first has {}", i + 1, losses[i].len(), width));
     }
-    if start.coord.i < limits.0 && start.direction != Direction::N {
-        let new = Coord{i: start.coord.i + 1, j: start.coord.j};
-        if start.direction == Direction::S && start.steps < 3 {
-            coords.insert(BigCoord{coord: new, direction: Direction::S, steps: start.steps + 1});
+    Ok(City{losses, height, width})
+}
+
+#[cfg(test)]
+fn check_route(city: &City, crucible: Crucible, start: Coord, route: &Route) {
+    let mut current = start;
+    let mut loss: usize = 0;
+    let mut run: usize = 0;
+    let mut last: Option<Direction> = None;
+    for (coord, direction) in &route.steps {
+        assert_eq!(city.step(current, *direction), Some(*coord));
+        if last == Some(*direction) {
+            run += 1;
         }
-        else if start.direction != Direction::S {
-            coords.insert(BigCoord{coord: new, direction: Direction::S, steps: 1});
+        else {
+            assert!(last.is_none() || run >= crucible.min_straight);
+            run = 1;
         }
+        assert!(run <= crucible.max_straight);
+        loss += city.losses[coord.i][coord.j];
+        current = *coord;
+        last = Some(*direction);
     }
-    if start.coord.j < limits.1 && start.direction != Direction::W {
-        let new = Coord{i: start.coord.i, j: start.coord.j + 1};  
-        if start.direction == Direction::E && start.steps < 3 {
-            coords.insert(BigCoord{coord: new, direction: Direction::E, steps: start.steps + 1});
-        }
-        else if start.direction != Direction::E {
-            coords.insert(BigCoord{coord: new, direction: Direction::E, steps: 1});
-        }  
+    assert!(run >= crucible.min_straight);
+    assert_eq!(loss, route.heat_loss);
+}
+
+#[test]
+fn test_example_routes() {
+    let city = parse_input(include_str!("../example.txt")).unwrap();
+    let start = Coord{i: 0, j: 0};
+    let goal = Coord{i: 12, j: 12};
+    let route = city.find_route(CRUCIBLE, start, goal).unwrap();
+    assert_eq!(route.heat_loss, 102);
+    check_route(&city, CRUCIBLE, start, &route);
+    let route = city.find_route(ULTRA_CRUCIBLE, start, goal).unwrap();
+    assert_eq!(route.heat_loss, 94);
+    check_route(&city, ULTRA_CRUCIBLE, start, &route);
+
+    let city = parse_input(include_str!("../example5.txt")).unwrap();
+    let route = city.find_route(ULTRA_CRUCIBLE, start, Coord{i: 4, j: 11}).unwrap();
+    assert_eq!(route.heat_loss, 71);
+    check_route(&city, ULTRA_CRUCIBLE, start, &route);
+}
+
+#[test]
+fn test_runtime_endpoints() {
+    let city = parse_input(include_str!("../example.txt")).unwrap();
+    let start = Coord{i: 12, j: 12};
+    let goal = Coord{i: 0, j: 0};
+    let crucible = Crucible{min_straight: 2, max_straight: 5};
+    let route = city.find_route(crucible, start, goal).unwrap();
+    check_route(&city, crucible, start, &route);
+    assert_eq!(city.find_route(crucible, start, start).unwrap().heat_loss, 0);
+    // A 2x2 grid cannot fit a run of four blocks
+    let small = parse_input(include_str!("../example2.txt")).unwrap();
+    assert!(small.find_route(ULTRA_CRUCIBLE, goal, Coord{i: 1, j: 1}).is_none());
+}
+
+#[test]
+fn test_vec2_conversion() {
+    let coord = Coord{i: 2, j: 5};
+    assert_eq!(Vec2::from(coord), Vec2::new(5, 2));
+    assert_eq!(Coord::from(Vec2::from(coord)), coord);
+    let city = parse_input(include_str!("../example2.txt")).unwrap();
+    let corner = Coord{i: 0, j: 0};
+    assert_eq!(city.step(corner, Direction::N), None);
+    assert_eq!(city.step(corner, Direction::W), None);
+    assert_eq!(city.step(corner, Direction::S), Some(Coord{i: 1, j: 0}));
+    assert_eq!(city.step(Coord{i: 1, j: 0}, Direction::E), Some(Coord{i: 1, j: 1}));
+    assert_eq!(city.step(Coord{i: 1, j: 1}, Direction::E), None);
+}
+
+#[test]
+fn test_render_route() {
+    let city = parse_input(include_str!("../example7.txt")).unwrap();
+    let route = city.find_route(CRUCIBLE, Coord{i: 0, j: 0}, Coord{i: 4, j: 4}).unwrap();
+    let rendered = city.render_route(&route);
+    assert_eq!(rendered.lines().count(), 5);
+    assert_eq!(rendered.chars().filter(|c| "^v<>".contains(*c)).count(), route.steps.len());
+    assert!(rendered.ends_with('v'));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse_input("241\n3x5").unwrap_err(), "Invalid heat loss 'x' on row 2");
+    assert!(parse_input("241\n35").is_err());
+    assert!(parse_input("\n").is_err());
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day17_parse_input") {
+        assert!(parse_input(&input).is_err(), "{:?}", input);
     }
-    coords
 }
 
-fn parse_input2() -> Network{
-    let data = get_input_as_chars(include_str!("../input.txt"));
-    let imax = data.len()-1;
-    let jmax = data[0].len() -1;
-    let mut blocks: HashMap<BigCoord, Block> = HashMap::new();
-    for (i, row) in data.iter().enumerate(){
-        for (j, c) in row.iter().enumerate() {
-            let coord = Coord{i, j};
-            let block_type: BlockType;
-            let mut weight: usize = 999999999;
-            if (i, j) == (0, 0){
-                block_type = BlockType::Start;
-                weight = 0 ;
-            }
-            else if (i, j) == (imax, jmax) {
-                block_type = BlockType::End;
-            }
-            else{
-                block_type = BlockType::None;
-            }
-            for direction in [Direction::N, Direction::E, Direction::S, Direction::W]{
-                for steps in 0..11{
-                    blocks.insert(BigCoord{coord, direction, steps: steps as usize}, Block { 
-                        coord: Coord{i, j}, 
-                        loss: c.to_digit(10).unwrap() as usize ,
-                        type_: block_type, 
-                        weight: weight,
-                    });
-                }
+const USAGE: &str = "Usage: day17 [--route [<i,j> <i,j>]]";
 
-            }
+// Whether to draw the routes, and where they run between if not corner to corner
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Options {
+    show_route: bool,
+    endpoints: Option<(Coord, Coord)>,
+}
 
-            
+fn parse_coord(string: &str) -> Result<Coord, String> {
+    let error = || format!("'{}' is not a block, expected <row>,<column>", string);
+    let (i, j) = string.split_once(',').ok_or_else(error)?;
+    Ok(Coord{i: i.trim().parse::<usize>().map_err(|_| error())?, j: j.trim().parse::<usize>().map_err(|_| error())?})
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--route" => {
+                options.show_route = true;
+                if args.peek().is_some_and(|next| !next.starts_with("--")) {
+                    let start = parse_coord(args.next().unwrap())?;
+                    let goal = parse_coord(args.next().ok_or(USAGE)?)?;
+                    options.endpoints = Some((start, goal));
+                }
+            }
+            _ => return Err(USAGE.to_string()),
         }
     }
-    Network{blocks, visited: HashSet::new(), limits: (imax, jmax)}
+    Ok(options)
 }
 
-fn part1(){
-    let mut network = parse_input2();
-    network.dijkstra0();
-    println!("Part 1 Answer: {:?}", network.get_end_block_weights());
+#[test]
+fn test_parse_args() {
+    let args = |args: &[&str]| parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    assert_eq!(args(&[]), Ok(Options{show_route: false, endpoints: None}));
+    assert_eq!(args(&["--route"]), Ok(Options{show_route: true, endpoints: None}));
+    assert_eq!(args(&["--route", "0,2", "12,3"]), Ok(Options{show_route: true, endpoints: Some((Coord{i: 0, j: 2}, Coord{i: 12, j: 3}))}));
+    assert!(args(&["--route", "0,2"]).is_err());
+    assert!(args(&["--route", "0;2", "1,1"]).is_err());
+    assert!(args(&["--routes"]).is_err());
 }
 
-
-fn part2(){
-    let mut network = parse_input2();
-    network.dijkstra2();
-    println!("Part 2 Answer: {:?}", network.get_end_block_weights2());
+// With --route, each part draws the route it took over the map
+fn run_part(part: usize, crucible: Crucible, options: &Options) -> Result<(), String> {
+    let city = parse_input("")?;
+    let (start, goal) = options.endpoints.unwrap_or((Coord{i: 0, j: 0}, Coord{i: city.height - 1, j: city.width - 1}));
+    for coord in [start, goal] {
+        if coord.i >= city.height || coord.j >= city.width {
+            return Err(format!("Block {},{} is outside the {}x{} city", coord.i, coord.j, city.height, city.width));
+        }
+    }
+    let route = city.find_route(crucible, start, goal)
+        .ok_or(format!("There is no route from {},{} to {},{}", start.i, start.j, goal.i, goal.j))?;
+    if options.show_route {
+        println!("{}", city.render_route(&route));
+    }
+    println!("Part {} Answer: {:?}", part, route.heat_loss);
+    Ok(())
 }
 
 fn main() {
-    let start = Instant::now();
-    part1();
-    println!("*** Part 1 Took {:.2?} ***", start.elapsed());
-    let start2 = Instant::now();
-    part2();
-    println!("*** Part 2 Took {:.2?} ***", start2.elapsed());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    for (part, crucible) in [(1, CRUCIBLE), (2, ULTRA_CRUCIBLE)] {
+        let start = Instant::now();
+        if let Err(error) = run_part(part, crucible, &options) {
+            eprintln!("Part {}: {}", part, error);
+        }
+        println!("*** Part {} Took {:.2?} ***", part, start.elapsed());
+    }
 }
@@ -1,3 +1,4 @@
+use aoc_coords::Vec2;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
 #[allow(dead_code)]
@@ -31,8 +32,20 @@ pub struct Coord64 {
     pub y: i64,
 }
 
+impl From<Coord64> for Vec2<i64> {
+    fn from(coord: Coord64) -> Vec2<i64> {
+        Vec2::new(coord.x, coord.y)
+    }
+}
+
+impl From<Vec2<i64>> for Coord64 {
+    fn from(vector: Vec2<i64>) -> Coord64 {
+        Coord64{x: vector.x, y: vector.y}
+    }
+}
+
 #[allow(dead_code)]
-pub fn get_input_as_lines(input: &'static str) -> Vec<&'static str>{
+pub fn get_input_as_lines(input: &str) -> Vec<&str>{
     let mut data: Vec<&str> = Vec::new();
     for line in input.lines() {
         data.push(line)
@@ -1,73 +1,112 @@
 mod aoc_parser;
 use aoc_parser::{get_input_as_lines, Direction, Coord64};
+use std::collections::BTreeMap;
 use std::time::Instant;
+use csscolorparser::Color;
+use aoc_grid::{Point, SparseGrid};
+use aoc_coords::Vec2;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 struct Vertex{
     coord: Coord64,
     direction: Direction,
     amount: i64,
+    colour: Color,
 }
 
-fn get_vertex_from_row(row: &str, prev_coord: Coord64) -> (Vertex, Coord64){
+fn get_colour(part: &str) -> Result<Color, String> {
+    csscolorparser::parse(part.trim_start_matches('(').trim_end_matches(')')).map_err(|e| format!("Invalid colour '{}': {}", part, e))
+}
+
+// The plan has north as +y, the other way up to the grids
+fn get_offset(direction: Direction) -> Vec2<i64> {
+    match direction {
+        Direction::East => Vec2::new(1, 0),
+        Direction::North => Vec2::new(0, 1),
+        Direction::West => Vec2::new(-1, 0),
+        Direction::South => Vec2::new(0, -1),
+    }
+}
+
+fn move_coord(coord: Coord64, direction: Direction, amount: i64) -> Coord64 {
+    (Vec2::from(coord) + get_offset(direction) * amount).into()
+}
+
+// None when the move would run off the edge of an i64
+fn checked_move_coord(coord: Coord64, direction: Direction, amount: i64) -> Option<Coord64> {
+    match direction {
+        Direction::East => Some(Coord64{x: coord.x.checked_add(amount)?, y: coord.y}),
+        Direction::North => Some(Coord64{x: coord.x, y: coord.y.checked_add(amount)?}),
+        Direction::West => Some(Coord64{x: coord.x.checked_sub(amount)?, y: coord.y}),
+        Direction::South => Some(Coord64{x: coord.x, y: coord.y.checked_sub(amount)?}),
+    }
+}
+
+fn split_row(row: &str) -> Result<[&str; 3], String> {
     let parts: Vec<&str> = row.split_ascii_whitespace().collect();
-    let amount: i64 = parts[1].parse::<i64>().unwrap();
+    parts.try_into().map_err(|_| format!("Expected '<direction> <amount> (<colour>)', found '{}'", row))
+}
+
+fn make_vertex(row: &str, prev_coord: Coord64, direction: Direction, amount: i64, colour: &str) -> Result<(Vertex, Coord64), String> {
+    let new_coord = checked_move_coord(prev_coord, direction, amount).ok_or(format!("'{}' digs off the edge of the site", row))?;
+    Ok((Vertex { coord: prev_coord, direction, amount, colour: get_colour(colour)? }, new_coord))
+}
+
+fn get_vertex_from_row(row: &str, prev_coord: Coord64) -> Result<(Vertex, Coord64), String>{
+    let parts = split_row(row)?;
+    let amount: i64 = parts[1].parse::<i64>().map_err(|_| format!("Invalid amount '{}'", parts[1]))?;
     let direction: Direction = match parts[0] {
         "R" => Direction::East,
         "U" => Direction::North,
         "L" => Direction::West,
         "D" => Direction::South,
-        _ => unreachable!(),
-    };
-    let new_coord: Coord64 = match direction {
-        Direction::East => Coord64{x: prev_coord.x + amount, y: prev_coord.y},
-        Direction::North => Coord64{x: prev_coord.x, y: prev_coord.y + amount},
-        Direction::West => Coord64{x: prev_coord.x - amount, y: prev_coord.y},
-        Direction::South => Coord64{x: prev_coord.x, y: prev_coord.y - amount},
+        _ => return Err(format!("Invalid direction '{}'", parts[0])),
     };
-    (Vertex { coord: prev_coord, direction, amount }, new_coord)
+    make_vertex(row, prev_coord, direction, amount, parts[2])
 }
 
-fn get_vertex_from_row2(row: &str, prev_coord: Coord64) -> (Vertex, Coord64){
-    let parts: Vec<&str> = row.split_ascii_whitespace().collect();
+// The colour code holds the real instruction: five hex digits of distance and
+// a direction digit
+fn get_vertex_from_row2(row: &str, prev_coord: Coord64) -> Result<(Vertex, Coord64), String>{
+    let parts = split_row(row)?;
     let mut instruction: String = parts[2].replace(&['(', ')', '#'][..], "");
-    let direction_char = instruction.pop().unwrap();
-    let amount = i64::from_str_radix(&instruction, 16).unwrap();
+    let direction_char = instruction.pop().ok_or(format!("Missing instruction in '{}'", parts[2]))?;
+    let amount = i64::from_str_radix(&instruction, 16).map_err(|_| format!("Invalid distance in '{}'", parts[2]))?;
     let direction: Direction = match direction_char {
         '0' => Direction::East,
         '3' => Direction::North,
         '2' => Direction::West,
         '1' => Direction::South,
-        _ => unreachable!(),
+        _ => return Err(format!("Invalid direction '{}'", direction_char)),
     };
-    let new_coord: Coord64 = match direction {
-        Direction::East => Coord64{x: prev_coord.x + amount, y: prev_coord.y},
-        Direction::North => Coord64{x: prev_coord.x, y: prev_coord.y + amount},
-        Direction::West => Coord64{x: prev_coord.x - amount, y: prev_coord.y},
-        Direction::South => Coord64{x: prev_coord.x, y: prev_coord.y - amount},
-    };
-    (Vertex { coord: prev_coord, direction, amount }, new_coord)
+    make_vertex(row, prev_coord, direction, amount, parts[2])
 }
 
 
-fn parse_input() -> Vec<Vertex> {
-    let data = get_input_as_lines(include_str!("../input.txt"));
+fn parse_input(mut string: &str) -> Vec<Vertex> {
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    let data = get_input_as_lines(string);
     let mut vertices: Vec<Vertex> = Vec::new();
     let mut coord = Coord64{x: 0, y: 0};
     for row in data {
-        let vertexs = get_vertex_from_row(row, coord);
+        let vertexs = get_vertex_from_row(row, coord).unwrap();
         coord = vertexs.1;
         vertices.push(vertexs.0);
     }
     vertices
 }
 
-fn parse_input2() -> Vec<Vertex> {
-    let data = get_input_as_lines(include_str!("../input.txt"));
+fn parse_input2(mut string: &str) -> Vec<Vertex> {
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    let data = get_input_as_lines(string);
     let mut vertices: Vec<Vertex> = Vec::new();
     let mut coord = Coord64{x: 0, y: 0};
     for row in data {
-        let vertexs = get_vertex_from_row2(row, coord);
+        let vertexs = get_vertex_from_row2(row, coord).unwrap();
         coord = vertexs.1;
         vertices.push(vertexs.0);
     }
@@ -92,22 +131,464 @@ fn calculate_perimeter(vertices: Vec<Vertex>) -> i64 {
     total
 }
 
-fn part1(){
-    let vertices = parse_input();
-    println!("Part 1 Answer: {}", calculate_area_polygon(vertices.clone()));
+// Why a dig plan doesn't describe a simple closed loop. Indices are instruction
+// numbers, counting from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlanError {
+    Empty,
+    NotClosed(Coord64),
+    ZeroLength(usize),
+    Crossing(usize, usize),
+    Overlap(usize, usize),
+}
+
+// An instruction's trench as a span along a fixed row (horizontal) or column
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    index: usize,
+    horizontal: bool,
+    line: i64,
+    start: i64,
+    end: i64,
+}
+
+fn get_segments(vertices: &[Vertex]) -> Vec<Segment> {
+    vertices.iter().enumerate().map(|(index, v)| {
+        let end = move_coord(v.coord, v.direction, v.amount);
+        match v.direction {
+            Direction::East | Direction::West => Segment{index, horizontal: true, line: v.coord.y, start: v.coord.x.min(end.x), end: v.coord.x.max(end.x)},
+            Direction::North | Direction::South => Segment{index, horizontal: false, line: v.coord.x, start: v.coord.y.min(end.y), end: v.coord.y.max(end.y)},
+        }
+    }).collect()
+}
+
+// Two segments may only meet if they follow one another in the plan, and then
+// only at the corner they share.
+fn check_meeting(a: &Segment, b: &Segment, count: usize) -> Result<(), PlanError> {
+    let (first, second) = (a.index.min(b.index), a.index.max(b.index));
+    let adjacent = second == first + 1 || (first == 0 && second == count - 1);
+    if a.horizontal == b.horizontal {
+        let shared = a.end.min(b.end) - a.start.max(b.start);
+        if shared > 0 {
+            return Err(PlanError::Overlap(first, second));
+        }
+    }
+    if adjacent { Ok(()) } else { Err(PlanError::Crossing(first, second)) }
+}
+
+// Segments on the same row or column, swept in order of where they start
+fn check_collinear(segments: &[Segment], horizontal: bool, count: usize) -> Result<(), PlanError> {
+    let mut sorted: Vec<&Segment> = segments.iter().filter(|s| s.horizontal == horizontal).collect();
+    sorted.sort_by_key(|s| (s.line, s.start));
+    let mut active: Vec<&Segment> = Vec::new();
+    for segment in sorted {
+        active.retain(|other| other.line == segment.line && other.end >= segment.start);
+        for other in &active {
+            check_meeting(other, segment, count)?;
+        }
+        active.push(segment);
+    }
+    Ok(())
+}
+
+// Sweeps left to right with the rows of the horizontal segments under the sweep
+// line kept in order, so each vertical segment only looks at the rows it spans.
+fn check_perpendicular(segments: &[Segment], count: usize) -> Result<(), PlanError> {
+    // (x, 0 = horizontal starts, 1 = vertical, 2 = horizontal ends, segment)
+    let mut events: Vec<(i64, u8, usize)> = Vec::new();
+    for (k, segment) in segments.iter().enumerate() {
+        if segment.horizontal {
+            events.push((segment.start, 0, k));
+            events.push((segment.end, 2, k));
+        }
+        else {
+            events.push((segment.line, 1, k));
+        }
+    }
+    events.sort_unstable();
+    let mut rows: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    for (_, kind, k) in events {
+        let segment = &segments[k];
+        match kind {
+            0 => rows.entry(segment.line).or_default().push(k),
+            1 => {
+                for (_, crossing) in rows.range(segment.start..=segment.end) {
+                    for other in crossing {
+                        check_meeting(&segments[*other], segment, count)?;
+                    }
+                }
+            }
+            _ => {
+                let row = rows.get_mut(&segment.line).unwrap();
+                row.retain(|other| *other != k);
+                if row.is_empty() {
+                    rows.remove(&segment.line);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_plan(vertices: &[Vertex]) -> Result<(), PlanError> {
+    let last = vertices.last().ok_or(PlanError::Empty)?;
+    let end = move_coord(last.coord, last.direction, last.amount);
+    if end != vertices[0].coord {
+        return Err(PlanError::NotClosed(end));
+    }
+    if let Some(index) = vertices.iter().position(|v| v.amount <= 0) {
+        return Err(PlanError::ZeroLength(index));
+    }
+    let segments = get_segments(vertices);
+    check_collinear(&segments, true, vertices.len())?;
+    check_collinear(&segments, false, vertices.len())?;
+    check_perpendicular(&segments, vertices.len())
+}
+
+// The lagoon size, but only for plans that trace a simple closed loop
+fn get_dig_area(vertices: Vec<Vertex>) -> Result<i64, PlanError> {
+    validate_plan(&vertices)?;
+    Ok(calculate_area_polygon(vertices))
+}
+
+// An RGB image of the dig site, each pixel covering `scale` x `scale` cells.
+// North is at the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Raster {
+    width: usize,
+    height: usize,
+    scale: i64,
+    pixels: Vec<[u8; 3]>,
+}
+
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const LAGOON: [u8; 3] = [160, 160, 160];
+
+// (min x, max y) of the trench, the top left corner of the image
+fn get_corner(vertices: &[Vertex]) -> Coord64 {
+    Coord64{x: vertices.iter().map(|v| v.coord.x).min().unwrap_or(0), y: vertices.iter().map(|v| v.coord.y).max().unwrap_or(0)}
+}
+
+fn get_extent(vertices: &[Vertex]) -> Coord64 {
+    let corner = get_corner(vertices);
+    Coord64{
+        x: vertices.iter().map(|v| v.coord.x).max().unwrap_or(0) - corner.x + 1,
+        y: corner.y - vertices.iter().map(|v| v.coord.y).min().unwrap_or(0) + 1,
+    }
+}
+
+// The smallest scale at which the whole plan fits in `max_size` pixels each way
+fn fit_scale(vertices: &[Vertex], max_size: usize) -> i64 {
+    let extent = get_extent(vertices);
+    let max_size = max_size.max(1) as i64;
+    ((extent.x.max(extent.y) + max_size - 1) / max_size).max(1)
+}
+
+impl Raster {
+    // Each pixel row is filled between pairs of the vertical edges crossing the
+    // cell at its centre, then every trench segment is drawn over the top in its
+    // own colour. Both only touch pixels, so huge plans are cheap at a coarse scale.
+    fn new(vertices: &[Vertex], scale: i64) -> Raster {
+        let corner = get_corner(vertices);
+        let extent = get_extent(vertices);
+        let width = ((extent.x + scale - 1) / scale) as usize;
+        let height = ((extent.y + scale - 1) / scale) as usize;
+        let mut pixels: Vec<[u8; 3]> = vec![BACKGROUND; width * height];
+        for row in 0..height {
+            let y = corner.y - row as i64 * scale - scale / 2;
+            let mut crossings: Vec<i64> = vertices.iter().enumerate()
+                .filter_map(|(i, v)| {
+                    let next = &vertices[(i + 1) % vertices.len()].coord;
+                    let (low, high) = (v.coord.y.min(next.y), v.coord.y.max(next.y));
+                    (v.coord.x == next.x && low <= y && y < high).then_some(v.coord.x)
+                })
+                .collect();
+            crossings.sort_unstable();
+            for pair in crossings.chunks_exact(2) {
+                let start = ((pair[0] - corner.x) / scale) as usize;
+                let end = ((pair[1] - corner.x) / scale) as usize;
+                for pixel in &mut pixels[row * width + start..=row * width + end] {
+                    *pixel = LAGOON;
+                }
+            }
+        }
+        for (i, v) in vertices.iter().enumerate() {
+            let next = &vertices[(i + 1) % vertices.len()].coord;
+            let [r, g, b, _] = v.colour.to_rgba8();
+            let columns = ((v.coord.x.min(next.x) - corner.x) / scale) as usize..=((v.coord.x.max(next.x) - corner.x) / scale) as usize;
+            let rows = ((corner.y - v.coord.y.max(next.y)) / scale) as usize..=((corner.y - v.coord.y.min(next.y)) / scale) as usize;
+            for row in rows {
+                for column in columns.clone() {
+                    pixels[row * width + column] = [r, g, b];
+                }
+            }
+        }
+        Raster{width, height, scale, pixels}
+    }
+    fn to_ppm(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        bytes.extend(self.pixels.iter().flatten());
+        bytes
+    }
+    fn to_png(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&self.pixels.concat()).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
+}
+
+// The lagoon as one polygon through the cell centres, with a line per trench
+// segment in that segment's colour, in units of `scale` cells.
+fn to_svg(vertices: &[Vertex], scale: i64) -> String {
+    let corner = get_corner(vertices);
+    let extent = get_extent(vertices);
+    let point = |coord: &Coord64| ((coord.x - corner.x) as f64 / scale as f64 + 0.5, (corner.y - coord.y) as f64 / scale as f64 + 0.5);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\">\n",
+        extent.x as f64 / scale as f64, extent.y as f64 / scale as f64,
+    );
+    let points: Vec<String> = vertices.iter().map(|v| { let (x, y) = point(&v.coord); format!("{},{}", x, y) }).collect();
+    svg.push_str(&format!("<polygon points=\"{}\" fill=\"#a0a0a0\"/>\n", points.join(" ")));
+    for (i, v) in vertices.iter().enumerate() {
+        let (x1, y1) = point(&v.coord);
+        let (x2, y2) = point(&vertices[(i + 1) % vertices.len()].coord);
+        svg.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-linecap=\"square\"/>\n",
+            x1, y1, x2, y2, v.colour.to_hex_string(),
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// The trench cell by cell, in the colour each cell was dug with, for plans small
+// enough to walk. North is up, so y is flipped to count rows down the page.
+fn get_dig_site(vertices: &[Vertex]) -> SparseGrid<Color> {
+    let mut site: SparseGrid<Color> = SparseGrid::new();
+    for v in vertices {
+        for step in 1..=v.amount {
+            let coord = move_coord(v.coord, v.direction, step);
+            site.insert(Point::new(coord.x, -coord.y), v.colour.clone());
+        }
+    }
+    site
+}
+
+// The trench as text, as drawn in the puzzle
+fn show_dig_site(vertices: &[Vertex]) -> String {
+    get_dig_site(vertices).to_text(|_, colour| if colour.is_some() { '#' } else { '.' })
+}
+
+// Writes `<prefix>.ppm`, `<prefix>.png` and `<prefix>.svg`, scaled down to fit `max_size`
+fn export_plan(vertices: &[Vertex], prefix: &str, max_size: usize) -> std::io::Result<()> {
+    let scale = fit_scale(vertices, max_size);
+    let raster = Raster::new(vertices, scale);
+    std::fs::write(format!("{}.ppm", prefix), raster.to_ppm())?;
+    std::fs::write(format!("{}.png", prefix), raster.to_png())?;
+    std::fs::write(format!("{}.svg", prefix), to_svg(vertices, scale))
+}
+
+#[test]
+fn test_example() {
+    assert_eq!(calculate_area_polygon(parse_input(include_str!("../example.txt"))), 62);
+    assert_eq!(calculate_area_polygon(parse_input2(include_str!("../example.txt"))), 952408144115);
+}
+
+#[test]
+fn test_raster() {
+    let vertices = parse_input(include_str!("../example.txt"));
+    assert_eq!(vertices[0].colour.to_hex_string(), "#70c710");
+    let raster = Raster::new(&vertices, 1);
+    assert_eq!((raster.width, raster.height), (7, 10));
+    // Every trench or lagoon cell is coloured in, and nothing else
+    assert_eq!(raster.pixels.iter().filter(|pixel| **pixel != BACKGROUND).count() as i64, calculate_area_polygon(vertices.clone()));
+    assert_eq!(raster.pixels[1], [0x70, 0xc7, 0x10]);
+    assert_eq!(raster.pixels[raster.width + 1], LAGOON);
+    let ppm = raster.to_ppm();
+    assert!(ppm.starts_with(b"P6\n7 10\n255\n"));
+    assert_eq!(ppm.len(), 12 + 7 * 10 * 3);
+    assert!(raster.to_png().starts_with(b"\x89PNG"));
+    let svg = to_svg(&vertices, 1);
+    assert_eq!(svg.matches("<line").count(), vertices.len());
+    assert!(svg.contains("stroke=\"#0dc571\""));
+
+    // The hex plan spans over a million cells each way
+    let huge = parse_input2(include_str!("../example.txt"));
+    let scale = fit_scale(&huge, 100);
+    let raster = Raster::new(&huge, scale);
+    assert!(raster.width <= 100 && raster.height <= 100);
+    assert_eq!(raster.scale, scale);
+    assert!(raster.pixels.contains(&LAGOON));
+}
+
+#[test]
+fn test_export_plan() {
+    let directory = std::env::temp_dir().join(format!("day18_export_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let prefix = directory.join("plan");
+    export_plan(&parse_input(include_str!("../example.txt")), prefix.to_str().unwrap(), 5).unwrap();
+    let read = |extension: &str| std::fs::read(directory.join(format!("plan.{}", extension))).unwrap();
+    // Scaled down by 2 to fit a 7x10 site into 5 cells
+    assert!(read("ppm").starts_with(b"P6\n4 5\n255\n"));
+    assert!(read("png").starts_with(b"\x89PNG"));
+    assert!(String::from_utf8(read("svg")).unwrap().starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 3.5 5\">"));
+    assert!(export_plan(&parse_input(include_str!("../example.txt")), directory.join("missing/plan").to_str().unwrap(), 5).is_err());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_dig_site() {
+    let site = get_dig_site(&parse_input(include_str!("../example.txt")));
+    let bounds = site.bounds().unwrap();
+    assert_eq!((bounds.min, bounds.width(), bounds.height()), (Point::new(0, 0), 7, 10));
+    let draw = |_: Point, colour: Option<&Color>| if colour.is_some() { '#' } else { '.' };
+    assert_eq!(site.to_text(draw), "#######\n#.....#\n###...#\n..#...#\n..#...#\n###.###\n#...#..\n##..###\n.#....#\n.######");
+    assert_eq!(show_dig_site(&parse_input(include_str!("../example.txt"))), site.to_text(draw));
+    assert_eq!(site.render(aoc_grid::Window::new(Point::new(-1, -1), Point::new(2, 1)), draw), "....\n.###\n.#..");
+    assert_eq!(site.get(&Point::new(1, 0)).unwrap().to_hex_string(), "#70c710");
+}
+
+#[test]
+fn test_vec2_conversion() {
+    let coord = Coord64{x: -3, y: 7};
+    assert_eq!(Vec2::from(coord), Vec2::new(-3, 7));
+    assert_eq!(Coord64::from(Vec2::from(coord)), coord);
+    for direction in [Direction::East, Direction::North, Direction::West, Direction::South] {
+        assert_eq!(Some(move_coord(coord, direction, 5)), checked_move_coord(coord, direction, 5));
+    }
+    assert_eq!(move_coord(coord, Direction::South, 10), Coord64{x: -3, y: -3});
 }
 
+#[test]
+fn test_validate_plan() {
+    assert_eq!(get_dig_area(parse_input(include_str!("../example.txt"))), Ok(62));
+    assert_eq!(get_dig_area(parse_input2(include_str!("../example.txt"))), Ok(952408144115));
+    assert_eq!(validate_plan(&parse_input("R 2 (#000000)\nD 2 (#000000)\nL 2 (#000000)")), Err(PlanError::NotClosed(Coord64{x: 0, y: -2})));
+    assert_eq!(validate_plan(&[]), Err(PlanError::Empty));
+    // A figure of eight, crossing itself in the middle
+    let eight = "R 2 (#000000)\nD 4 (#000000)\nR 2 (#000000)\nU 2 (#000000)\nL 4 (#000000)\nU 2 (#000000)";
+    assert_eq!(validate_plan(&parse_input(eight)), Err(PlanError::Crossing(1, 4)));
+    // Doubling back along the last instruction
+    let back = "R 4 (#000000)\nL 2 (#000000)\nD 2 (#000000)\nL 2 (#000000)\nU 2 (#000000)";
+    assert_eq!(validate_plan(&parse_input(back)), Err(PlanError::Overlap(0, 1)));
+    // Two squares that only touch at a corner
+    let corners = "R 2 (#000000)\nD 2 (#000000)\nR 2 (#000000)\nD 2 (#000000)\nL 2 (#000000)\nU 2 (#000000)\nL 2 (#000000)\nU 2 (#000000)";
+    assert_eq!(validate_plan(&parse_input(corners)), Err(PlanError::Crossing(2, 6)));
+    // Coming back along the first instruction to close the loop
+    let wall = "R 4 (#000000)\nU 2 (#000000)\nL 2 (#000000)\nD 2 (#000000)\nL 2 (#000000)";
+    assert_eq!(validate_plan(&parse_input(wall)), Err(PlanError::Overlap(0, 4)));
+    assert_eq!(validate_plan(&parse_input("R 0 (#000000)\nR 2 (#000000)\nL 2 (#000000)")), Err(PlanError::ZeroLength(0)));
+}
 
-fn part2(){
-    let vertices = parse_input2();
-    println!("Part 2 Answer: {}", calculate_area_polygon(vertices.clone()));
+#[test]
+fn test_generated_plans() {
+    for seed in 0..10 {
+        let plan = &aoc_gen::dig_plan(&mut aoc_gen::Rng::new(seed), 40);
+        let vertices = parse_input(plan);
+        // Every cell inside the generated loops is on the trench itself
+        let trench: i64 = vertices.iter().map(|vertex| vertex.amount).sum();
+        assert_eq!(get_dig_area(vertices), Ok(trench));
+        let vertices = parse_input2(plan);
+        let scale = vertices[0].amount / parse_input(plan)[0].amount;
+        assert_eq!(get_dig_area(vertices), Ok(scale * scale * (trench / 2 - 1) + scale * trench / 2 + 1));
+    }
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    // Each is a plan whose rows are chained until one of them is rejected
+    for target in ["day18_get_vertex_from_row", "day18_get_vertex_from_row2"] {
+        let parser = if target.ends_with('2') { get_vertex_from_row2 } else { get_vertex_from_row };
+        for input in aoc_gen::fuzz_regressions(target) {
+            let rejected = input.lines()
+                .try_fold(Coord64{x: 0, y: 0}, |coord, row| parser(row, coord).map(|(_, next)| next))
+                .is_err();
+            assert!(rejected, "{:?}", input);
+        }
+    }
+}
+
+// The largest side, in pixels, of the images written by --export
+const EXPORT_SIZE: usize = 1000;
+
+// With `--export <prefix>`, each part's plan is also written out as images
+fn export(vertices: &[Vertex], prefix: Option<&str>, part: usize) {
+    if let Some(prefix) = prefix {
+        let prefix = format!("{}{}", prefix, part);
+        match export_plan(vertices, &prefix, EXPORT_SIZE) {
+            Ok(()) => println!("Exported part {} to {}.ppm, .png and .svg", part, prefix),
+            Err(error) => println!("Could not export part {}: {}", part, error),
+        }
+    }
+}
+
+const USAGE: &str = "Usage: day18 [--export <prefix>] [--show]";
+
+// The export prefix, if any, and whether to show the part 1 trench
+fn parse_args(args: &[String]) -> Result<(Option<&str>, bool), String> {
+    let mut export_prefix: Option<&str> = None;
+    let mut show = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--export" => export_prefix = Some(args.next().ok_or(USAGE)?),
+            "--show" => show = true,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok((export_prefix, show))
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+    assert_eq!(parse_args(&args(&[])), Ok((None, false)));
+    assert_eq!(parse_args(&args(&["--show", "--export", "plan"])), Ok((Some("plan"), true)));
+    assert!(parse_args(&args(&["--export"])).is_err());
+    assert!(parse_args(&args(&["--draw"])).is_err());
+}
+
+// With --show, part 1 prints its trench. Part 2's is far too big to draw.
+fn part1(export_prefix: Option<&str>, show: bool){
+    let vertices = parse_input("");
+    export(&vertices, export_prefix, 1);
+    if show {
+        println!("{}", show_dig_site(&vertices));
+    }
+    match get_dig_area(vertices) {
+        Ok(area) => println!("Part 1 Answer: {}", area),
+        Err(error) => println!("Part 1 has an invalid dig plan: {:?}", error),
+    }
+}
+
+
+fn part2(export_prefix: Option<&str>){
+    let vertices = parse_input2("");
+    export(&vertices, export_prefix, 2);
+    match get_dig_area(vertices) {
+        Ok(area) => println!("Part 2 Answer: {}", area),
+        Err(error) => println!("Part 2 has an invalid dig plan: {:?}", error),
+    }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (export_prefix, show) = match parse_args(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        },
+    };
     let start = Instant::now();
-    part1();
+    part1(export_prefix, show);
     println!("*** Part 1 Took {:.2?} ***", start.elapsed());
     let start2 = Instant::now();
-    part2();
+    part2(export_prefix);
     println!("*** Part 2 Took {:.2?} ***", start2.elapsed());
 }
@@ -1,9 +1,134 @@
 mod aoc_parser;
 use std::time::Instant;
+use aoc_graph::{GraphEdge, GraphExport, GraphNode};
+use aoc_parser::get_input_as_lines;
 
 
-fn parse_input() {
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Comparison {
+    Less,
+    Greater,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Rule {
+    category: char,
+    comparison: Comparison,
+    value: u32,
+    target: String,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Workflow {
+    name: String,
+    rules: Vec<Rule>,
+    fallback: String,
+}
+
+// The workflows in the order they were listed. Every rule sends a part on to
+// another workflow, or to A (accepted) or R (rejected).
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct System {
+    workflows: Vec<Workflow>,
+}
+
+impl GraphExport for System {
+    fn graph_nodes(&self) -> Vec<GraphNode> {
+        let mut nodes: Vec<GraphNode> = self.workflows.iter().map(|workflow| GraphNode::new(&workflow.name, &workflow.name)).collect();
+        nodes.push(GraphNode::new("A", "Accepted"));
+        nodes.push(GraphNode::new("R", "Rejected"));
+        nodes
+    }
+    fn graph_edges(&self) -> Vec<GraphEdge> {
+        let mut edges: Vec<GraphEdge> = Vec::new();
+        for workflow in &self.workflows {
+            for rule in &workflow.rules {
+                let symbol = match rule.comparison {
+                    Comparison::Less => '<',
+                    Comparison::Greater => '>',
+                };
+                let condition = format!("{}{}{}", rule.category, symbol, rule.value);
+                edges.push(GraphEdge::new(&workflow.name, &rule.target).with_label(&condition));
+            }
+            edges.push(GraphEdge::new(&workflow.name, &workflow.fallback).with_label("otherwise"));
+        }
+        edges
+    }
+    fn graph_name(&self) -> String {
+        "workflows".to_string()
+    }
+}
+
+
+fn parse_rule(string: &str) -> Result<Rule, String> {
+    let (condition, target) = string.split_once(':').ok_or(format!("Missing ':' in rule '{}'", string))?;
+    let mut chars = condition.chars();
+    let category = match chars.next() {
+        Some(category) if "xmas".contains(category) => category,
+        _ => return Err(format!("Invalid category in rule '{}'", string)),
+    };
+    let comparison = match chars.next() {
+        Some('<') => Comparison::Less,
+        Some('>') => Comparison::Greater,
+        _ => return Err(format!("Invalid comparison in rule '{}'", string)),
+    };
+    let value = chars.as_str().parse::<u32>().map_err(|_| format!("Invalid value in rule '{}'", string))?;
+    if target.is_empty() {
+        return Err(format!("Missing target in rule '{}'", string));
+    }
+    Ok(Rule{category, comparison, value, target: target.to_string()})
+}
+
+fn parse_workflow(line: &str) -> Result<Workflow, String> {
+    let (name, body) = line.strip_suffix('}').and_then(|line| line.split_once('{')).ok_or(format!("Expected 'name{{rules}}', found '{}'", line))?;
+    if name.is_empty() {
+        return Err(format!("Missing workflow name in '{}'", line));
+    }
+    let mut steps: Vec<&str> = body.split(',').collect();
+    let fallback = steps.pop().filter(|fallback| !fallback.is_empty()).ok_or(format!("Missing fallback in '{}'", line))?;
+    let rules = steps.into_iter().map(parse_rule).collect::<Result<Vec<Rule>, String>>()?;
+    Ok(Workflow{name: name.to_string(), rules, fallback: fallback.to_string()})
+}
 
+fn parse_input(mut string: &'static str) -> Result<System, String> {
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    let workflows = get_input_as_lines(string).into_iter()
+        .take_while(|line| !line.is_empty())
+        .map(parse_workflow)
+        .collect::<Result<Vec<Workflow>, String>>()?;
+    Ok(System{workflows})
+}
+
+#[test]
+fn test_workflow_graph() {
+    let system = parse_input(include_str!("../example.txt")).unwrap();
+    assert_eq!(system.workflows.len(), 11);
+    assert_eq!(system.workflows[0].rules[1], Rule{category: 'm', comparison: Comparison::Greater, value: 2090, target: "A".to_string()});
+    assert_eq!(system.graph_nodes().len(), 13);
+    assert_eq!(system.graph_edges().len(), 11 + 14);
+    let dot = system.to_dot();
+    assert!(dot.contains("    \"in\" -> \"px\" [label=\"s<1351\"];\n"));
+    assert!(dot.contains("    \"in\" -> \"qqz\" [label=\"otherwise\"];\n"));
+    assert!(system.to_node_link_json().contains("{\"id\": \"R\", \"label\": \"Rejected\"}"));
+    assert!(parse_input("in{x<1:A,R}\nbad\n\n{x=1}").is_err());
+}
+
+#[test]
+fn test_export_args() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+    assert_eq!(export(&args(&["--export", "dot"])), Ok(parse_input("").unwrap().to_dot()));
+    assert!(export(&args(&["--export", "json"])).unwrap().starts_with("{\"directed\": true"));
+    assert!(export(&args(&["--export", "png"])).is_err());
+    assert!(export(&args(&["--dot"])).is_err());
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day19_parse_workflow") {
+        assert!(parse_workflow(&input).is_err(), "{:?}", input);
+    }
 }
 
 fn part1(){
@@ -15,7 +140,26 @@ fn part2(){
     println!("Part 2 Answer: {}", 0);
 }
 
+// With `--export <dot|json>`, prints the workflows as a graph instead of solving
+fn export(args: &[String]) -> Result<String, String> {
+    match args {
+        [flag, format] if flag == "--export" => parse_input("")?.export(format),
+        _ => Err("Usage: day19 [--export <dot|json>]".to_string()),
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match export(&args) {
+            Ok(graph) => print!("{}", graph),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
     let start = Instant::now();
     part1();
     println!("*** Part 1 Took {:.2?} ***", start.elapsed());
@@ -1,11 +1,13 @@
-use std::cmp::max;
+use std::collections::BTreeMap;
 
+const COLOURS: [&str; 3] = ["red", "green", "blue"];
 
-#[derive(Clone, Copy, Debug)]
+
+// A number of cubes of each colour. Parsed hands hold every colour the parser
+// was told about, with zero for the colours that weren't drawn.
+#[derive(Clone, Debug, PartialEq, Eq)]
 struct Hand {
-    red: usize,
-    blue: usize,
-    green: usize,
+    cubes: BTreeMap<String, usize>,
 }
 
 #[derive(Clone, Debug)]
@@ -14,28 +16,69 @@ struct Game {
     hands: Vec<Hand>,
 }
 
+// How a single colour was drawn across a set of games
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ColourStats {
+    max: usize,
+    total: usize,
+    hands: usize,
+}
+
 
 impl Hand {
-    fn compatible_with_hand(&self, other: Hand) -> bool{
-        self.red <= other.red && self.blue <= other.blue && self.green <= other.green 
+    fn from_counts(counts: &[(&str, usize)]) -> Hand {
+        Hand{cubes: counts.iter().map(|(colour, amount)| (colour.to_string(), *amount)).collect()}
+    }
+    fn get(&self, colour: &str) -> usize {
+        self.cubes.get(colour).copied().unwrap_or(0)
+    }
+    fn compatible_with_hand(&self, other: &Hand) -> bool{
+        self.cubes.iter().all(|(colour, amount)| *amount <= other.get(colour))
     }
-    fn get_hand_power(&self) -> usize {
-        self.red*self.blue*self.green
+    // None if the product doesn't fit
+    fn get_hand_power(&self) -> Option<usize> {
+        self.cubes.values().try_fold(1usize, |power, amount| power.checked_mul(*amount))
     }
 }
 
 impl Game {
     fn get_minimum_hand(&self) -> Hand {
-        let mut red: usize = 0;
-        let mut blue: usize = 0;
-        let mut green: usize = 0;
-        for hand in self.hands.clone() {
-            red = max(red, hand.red);
-            blue = max(blue, hand.blue);
-            green = max(green, hand.green);
+        let mut cubes: BTreeMap<String, usize> = BTreeMap::new();
+        for hand in &self.hands {
+            for (colour, amount) in &hand.cubes {
+                let minimum = cubes.entry(colour.clone()).or_insert(0);
+                *minimum = (*minimum).max(*amount);
+            }
+        }
+        Hand { cubes }
+    }
+    fn is_possible_with(&self, bag: &Hand) -> bool {
+        self.hands.iter().all(|hand| hand.compatible_with_hand(bag))
+    }
+}
+
+
+fn get_colour_stats(games: &[Game]) -> Result<BTreeMap<String, ColourStats>, String> {
+    let mut stats: BTreeMap<String, ColourStats> = BTreeMap::new();
+    for hand in games.iter().flat_map(|game| game.hands.iter()) {
+        for (colour, amount) in &hand.cubes {
+            let colour_stats = stats.entry(colour.clone()).or_default();
+            colour_stats.max = colour_stats.max.max(*amount);
+            colour_stats.total = colour_stats.total.checked_add(*amount).ok_or(format!("Too many {} cubes drawn in total", colour))?;
+            if *amount > 0 {
+                colour_stats.hands += 1;
+            }
         }
-        Hand { red, blue, green }
     }
+    Ok(stats)
+}
+
+fn show_colour_stats(stats: &BTreeMap<String, ColourStats>) -> String {
+    let mut text = String::new();
+    for (colour, colour_stats) in stats {
+        text += &format!("{}: at most {} in a hand, {} in total over {} hands\n", colour, colour_stats.max, colour_stats.total, colour_stats.hands);
+    }
+    text
 }
 
 
@@ -48,63 +91,101 @@ fn get_input_lines() -> Vec<&'static str>{
     data
 }
 
-fn parse_hand(string: &str) -> Hand {
-    let cube_sets: Vec<&str> = string.split(',').collect();
-    let mut details: Vec<&str>;
-    let mut red: usize = 0;
-    let mut green: usize = 0;
-    let mut blue: usize = 0;
-    let mut amount: usize;
-    for cube_set in cube_sets {
-        details = cube_set.split_whitespace().collect();
-        amount = details[0].parse::<usize>().unwrap();
-        match details[1] {
-            "red" => red = amount,
-            "green" => green = amount,
-            "blue" => blue = amount,
-            _ => (),
-        } 
+fn parse_hand(string: &str, colours: &[&str]) -> Result<Hand, String> {
+    let mut cubes: BTreeMap<String, usize> = colours.iter().map(|colour| (colour.to_string(), 0)).collect();
+    for cube_set in string.split(',') {
+        let details: Vec<&str> = cube_set.split_whitespace().collect();
+        if details.len() != 2 {
+            return Err(format!("Expected '<amount> <colour>', found '{}'", cube_set.trim()));
+        }
+        let amount = details[0].parse::<usize>().map_err(|_| format!("Invalid cube count '{}'", details[0]))?;
+        match cubes.get_mut(details[1]) {
+            Some(count) => *count = count.checked_add(amount).ok_or(format!("Too many {} cubes in '{}'", details[1], string.trim()))?,
+            None => return Err(format!("Unknown colour '{}'", details[1])),
+        }
     }
-    Hand{red, blue, green}
+    Ok(Hand{cubes})
 }
 
-fn parse_line(line: &str) -> Game {
+fn parse_line(line: &str, colours: &[&str]) -> Result<Game, String> {
     let mut hands: Vec<Hand> = Vec::new();
-    let blocks: Vec<&str> = line.split(':').collect();
-    let id_: u32 = blocks[0].replace("Game ", "").parse::<u32>().unwrap();
-    let string_hands: Vec<&str> = blocks[1].split(';').collect();
-    for hand_string in string_hands {
-        hands.push(parse_hand(hand_string))
+    let (game, string_hands) = line.split_once(':').ok_or(format!("Missing ':' in '{}'", line))?;
+    let id_: u32 = match game.strip_prefix("Game ").map(|id| id.parse::<u32>()) {
+        Some(Ok(id)) => id,
+        _ => return Err(format!("Invalid game label '{}'", game)),
+    };
+    for hand_string in string_hands.split(';') {
+        hands.push(parse_hand(hand_string, colours)?)
     }
-    Game{id: id_, hands}
+    Ok(Game{id: id_, hands})
 }
 
 
-fn parse_input() -> Vec<Game> {
-    let data = get_input_lines();
+fn parse_lines(data: Vec<&str>, colours: &[&str]) -> Result<Vec<Game>, String> {
     let mut games: Vec<Game> = Vec::new();
     for line in data {
-        games.push(parse_line(line))
+        games.push(parse_line(line, colours)?)
     }
-    games
+    Ok(games)
 }
 
-fn is_possible_game(game: Game, min_hand: Hand) -> bool {
-    for hand in game.hands {
-        if !hand.compatible_with_hand(min_hand) {
-            return false;
-        }
+fn parse_input() -> Vec<Game> {
+    parse_lines(get_input_lines(), &COLOURS).unwrap()
+}
+
+#[test]
+fn test_example() {
+    let games = parse_lines(include_str!("../example.txt").lines().collect(), &COLOURS).unwrap();
+    let bag = Hand::from_counts(&[("red", 12), ("green", 13), ("blue", 14)]);
+    let possible: u32 = games.iter().filter(|game| game.is_possible_with(&bag)).map(|game| game.id).sum();
+    assert_eq!(possible, 8);
+    let power: usize = games.iter().map(|game| game.get_minimum_hand().get_hand_power().unwrap()).sum();
+    assert_eq!(power, 2286);
+    assert_eq!(games[0].get_minimum_hand(), Hand::from_counts(&[("red", 4), ("green", 2), ("blue", 6)]));
+    let stats = get_colour_stats(&games).unwrap();
+    assert_eq!(stats["red"], ColourStats{max: 20, total: 61, hands: 11});
+    assert_eq!(show_colour_stats(&stats).lines().nth(2), Some("red: at most 20 in a hand, 61 in total over 11 hands"));
+}
+
+#[test]
+fn test_custom_colours() {
+    let colours = ["red", "yellow"];
+    let game = parse_line("Game 7: 2 yellow; 1 red, 3 yellow", &colours).unwrap();
+    assert_eq!(game.get_minimum_hand(), Hand::from_counts(&[("red", 1), ("yellow", 3)]));
+    assert_eq!(game.get_minimum_hand().get_hand_power(), Some(3));
+    assert!(game.is_possible_with(&Hand::from_counts(&[("red", 1), ("yellow", 3), ("blue", 5)])));
+    assert!(!game.is_possible_with(&Hand::from_counts(&[("red", 1)])));
+    // Colours outside the palette are errors rather than being dropped
+    assert!(parse_line("Game 1: 3 green", &colours).is_err());
+    assert!(parse_line("Game 1: three red", &colours).is_err());
+    assert!(parse_line("Game x: 3 red", &colours).is_err());
+    assert!(parse_line("3 red", &colours).is_err());
+}
+
+#[test]
+fn test_overflow() {
+    let error = parse_line("Game 1: 18446744073709551615 red, 1 red", &COLOURS).unwrap_err();
+    assert_eq!(error, "Too many red cubes in '18446744073709551615 red, 1 red'");
+    let game = parse_line("Game 1: 18446744073709551615 red, 2 green, 1 blue", &COLOURS).unwrap();
+    assert_eq!(game.get_minimum_hand().get_hand_power(), None);
+    let games = parse_lines(vec!["Game 1: 18446744073709551615 red", "Game 2: 1 red"], &COLOURS).unwrap();
+    assert_eq!(get_colour_stats(&games).unwrap_err(), "Too many red cubes drawn in total");
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day2_parse_line") {
+        assert!(parse_line(&input, &COLOURS).is_err(), "{:?}", input);
     }
-    true
 }
 
 
 fn part1() {
     let games: Vec<Game> = parse_input();
-    let minimum_hand: Hand = Hand { red: 12, blue: 14, green: 13 };
+    let bag: Hand = Hand::from_counts(&[("red", 12), ("green", 13), ("blue", 14)]);
     let mut total: u32 = 0;
     for game in games {
-        if is_possible_game(game.clone(), minimum_hand) {
+        if game.is_possible_with(&bag) {
             total += game.id;
         }
     }
@@ -114,14 +195,40 @@ fn part1() {
 
 fn part2() {
     let games: Vec<Game> = parse_input();
-    let mut total: usize = 0;
-    for game in games {
-        total += game.get_minimum_hand().get_hand_power();
+    let total: Option<usize> = games.iter()
+        .try_fold(0usize, |total, game| total.checked_add(game.get_minimum_hand().get_hand_power()?));
+    match total {
+        Some(total) => println!("Part2 Answer: {}", total),
+        None => println!("Part2: the total power is too big"),
+    }
+}
+
+const USAGE: &str = "Usage: day2 [--stats]";
+
+fn parse_args(args: &[String]) -> Result<bool, String> {
+    let mut stats = false;
+    for arg in args {
+        match arg.as_str() {
+            "--stats" => stats = true,
+            _ => return Err(USAGE.to_string()),
+        }
     }
-    println!("Part2 Answer: {}", total);
+    Ok(stats)
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match parse_args(&args) {
+        Ok(true) => match get_colour_stats(&parse_input()) {
+            Ok(stats) => print!("{}", show_colour_stats(&stats)),
+            Err(error) => eprintln!("{}", error),
+        },
+        Ok(false) => (),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
     part1();
     part2();
 }
@@ -1,26 +1,126 @@
 mod aoc_parser;
 use std::time::Instant;
+use aoc_graph::{GraphEdge, GraphExport, GraphNode};
+use aoc_parser::get_input_as_lines;
 
-#[derive(Clone, Copy, Debug)]
-enum Pulse {
-    High,
-    Low,
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum ModuleKind {
+    Broadcaster,
+    FlipFlop,
+    Conjunction,
+    // Only ever sent to, like `output` and `rx`
+    Untyped,
 }
 
 
-enum FlipFlop {
-    On,
-    Off,
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Module {
+    name: String,
+    kind: ModuleKind,
+    targets: Vec<String>,
 }
 
+impl Module {
+    fn label(&self) -> String {
+        match self.kind {
+            ModuleKind::FlipFlop => format!("%{}", self.name),
+            ModuleKind::Conjunction => format!("&{}", self.name),
+            ModuleKind::Broadcaster | ModuleKind::Untyped => self.name.clone(),
+        }
+    }
+}
+
+
+// Every module, in the order listed, followed by any that are only targets
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Wiring {
+    modules: Vec<Module>,
+}
 
-struct FlipFlopModule {
-    activity: FlipFlop,
-    targets:
+impl GraphExport for Wiring {
+    fn graph_nodes(&self) -> Vec<GraphNode> {
+        self.modules.iter().map(|module| GraphNode::new(&module.name, &module.label())).collect()
+    }
+    fn graph_edges(&self) -> Vec<GraphEdge> {
+        self.modules.iter()
+            .flat_map(|module| module.targets.iter().map(|target| GraphEdge::new(&module.name, target)))
+            .collect()
+    }
+    fn graph_name(&self) -> String {
+        "modules".to_string()
+    }
 }
 
-fn parse_input() {
 
+fn parse_module(line: &str) -> Result<Module, String> {
+    let (name, targets) = line.split_once(" -> ").ok_or(format!("Missing ' -> ' in '{}'", line))?;
+    let (name, kind) = if let Some(name) = name.strip_prefix('%') {
+        (name, ModuleKind::FlipFlop)
+    }
+    else if let Some(name) = name.strip_prefix('&') {
+        (name, ModuleKind::Conjunction)
+    }
+    else if name == "broadcaster" {
+        (name, ModuleKind::Broadcaster)
+    }
+    else {
+        return Err(format!("Unknown module type for '{}'", name));
+    };
+    if name.is_empty() {
+        return Err(format!("Missing module name in '{}'", line));
+    }
+    let targets: Vec<String> = targets.split(',').map(|target| target.trim().to_string()).collect();
+    if targets.iter().any(|target| target.is_empty()) {
+        return Err(format!("Missing target in '{}'", line));
+    }
+    Ok(Module{name: name.to_string(), kind, targets})
+}
+
+fn parse_input(mut string: &'static str) -> Result<Wiring, String> {
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    let mut modules: Vec<Module> = get_input_as_lines(string).into_iter().map(parse_module).collect::<Result<Vec<Module>, String>>()?;
+    let mut untyped: Vec<String> = Vec::new();
+    for module in &modules {
+        for target in &module.targets {
+            if !modules.iter().any(|other| &other.name == target) && !untyped.contains(target) {
+                untyped.push(target.clone());
+            }
+        }
+    }
+    modules.extend(untyped.into_iter().map(|name| Module{name, kind: ModuleKind::Untyped, targets: Vec::new()}));
+    Ok(Wiring{modules})
+}
+
+#[test]
+fn test_wiring_graph() {
+    let wiring = parse_input(include_str!("../example1.txt")).unwrap();
+    assert_eq!(wiring.modules.len(), 5);
+    assert_eq!(wiring.modules[1], Module{name: "a".to_string(), kind: ModuleKind::FlipFlop, targets: vec!["b".to_string()]});
+    assert_eq!(wiring.graph_edges().len(), 7);
+    assert!(wiring.to_dot().contains("    \"inv\" [label=\"&inv\"];\n"));
+
+    let wiring = parse_input(include_str!("../example2.txt")).unwrap();
+    assert_eq!(wiring.modules.last().unwrap().kind, ModuleKind::Untyped);
+    assert!(wiring.to_node_link_json().contains("{\"id\": \"output\", \"label\": \"output\"}"));
+    assert!(wiring.to_node_link_json().contains("{\"source\": \"con\", \"target\": \"output\"}"));
+    assert!(parse_input("broadcaster -> a\na -> b").is_err());
+}
+
+#[test]
+fn test_export_args() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+    assert_eq!(export(&args(&["--export", "json"])), Ok(parse_input("").unwrap().to_node_link_json()));
+    assert!(export(&args(&["--export", "dot"])).unwrap().starts_with("digraph \"modules\""));
+    assert!(export(&args(&["--export"])).is_err());
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day20_parse_module") {
+        assert!(parse_module(&input).is_err(), "{:?}", input);
+    }
 }
 
 fn part1(){
@@ -32,7 +132,26 @@ fn part2(){
     println!("Part 2 Answer: {}", 0);
 }
 
+// With `--export <dot|json>`, prints the module wiring as a graph instead of solving
+fn export(args: &[String]) -> Result<String, String> {
+    match args {
+        [flag, format] if flag == "--export" => parse_input("")?.export(format),
+        _ => Err("Usage: day20 [--export <dot|json>]".to_string()),
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match export(&args) {
+            Ok(graph) => print!("{}", graph),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
     let start = Instant::now();
     part1();
     println!("*** Part 1 Took {:.2?} ***", start.elapsed());
@@ -1,36 +1,11 @@
 mod aoc_parser;
 use aoc_parser::get_input_as_chars;
-use std::{time::Instant, collections::{BTreeSet, HashMap}, borrow::BorrowMut};
+use aoc_frames::{Frame, Output, Recorder};
+use aoc_coords::Vec2;
+use aoc_grid::{Point, Torus, Window};
+use std::{time::Instant, collections::BTreeSet};
 
 
-fn get_count_per_square(coords: BTreeSet<Coord32>) -> HashMap<(i32, i32), BTreeSet<Coord32>> {
-    let mut counts = HashMap::new();
-    for coord in coords{
-        let key = (coord.x_iteration, coord.y_iteration);
-        if !counts.contains_key(&key){
-            counts.insert(key, BTreeSet::from([coord.get_base()]));
-        }
-        else {
-            counts.entry(key).and_modify(|coords2: &mut BTreeSet<Coord32>| {coords2.insert(coord);}).or_insert(BTreeSet::from([coord.get_base()]));
-        }
-    }
-    counts
-}
-
-fn get_iteration_states(coords: BTreeSet<Coord32>) -> HashMap<(i32, i32), BTreeSet<Coord32>> {
-    let mut counts = HashMap::new();
-    for coord in coords{
-        let key = (coord.x_iteration, coord.y_iteration);
-        if !counts.contains_key(&key){
-            counts.insert(key, BTreeSet::from([coord.get_base()]));
-        }
-        else {
-            counts.entry(key).and_modify(|coords2: &mut BTreeSet<Coord32>| {coords2.insert(coord);}).or_insert(BTreeSet::from([coord.get_base()]));
-        }
-    }
-    counts
-}
-
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
 pub struct Coord32 {
     pub x: i32,
@@ -46,7 +21,7 @@ impl Coord32 {
 }
 
 struct Garden {
-    edges: BTreeSet<Coord32>,
+    plots: Torus<char>,
     ground: BTreeSet<Coord32>,
     start: Coord32,
     xmax: i32,
@@ -54,8 +29,47 @@ struct Garden {
 }
 
 impl Garden {
+    // A coord's position on the infinite tiling, with the original garden's top
+    // left at the origin, and back again
+    fn position(&self, coord: Coord32) -> Point {
+        self.plots.point(Vec2::new(coord.x_iteration as i64, coord.y_iteration as i64), Vec2::new(coord.x as usize, coord.y as usize))
+    }
+    fn coord_at(&self, point: Point) -> Coord32 {
+        let (tile, cell) = self.plots.locate(point);
+        Coord32{x: cell.x as i32, y: cell.y as i32, x_iteration: tile.x as i32, y_iteration: tile.y as i32}
+    }
+    // Any stretch of the tiling, with the frontier as O
+    fn to_text(&self, frontier: &BTreeSet<Coord32>, window: Window) -> String {
+        self.plots.render(window, |point, c| match c {
+            _ if frontier.contains(&self.coord_at(point)) => 'O',
+            'S' => '.',
+            c => *c,
+        })
+    }
+    // The frontier after n steps, on just enough of the tiling to hold it
+    fn show(&self, n: usize) -> String {
+        let frontier = self.unique_squares_after_n_steps(n);
+        let mut window = Window::around(self.position(self.start));
+        for coord in &frontier {
+            window.include(self.position(*coord));
+        }
+        self.to_text(&frontier, window)
+    }
     fn unique_squares_after_n_steps(&self, n: usize) -> BTreeSet<Coord32>{
+        self.record_n_steps(n, &mut Recorder::off())
+    }
+    // The recorder is offered the frontier at every step, starting from step 0,
+    // framed on enough copies of the garden to hold all n steps.
+    fn record_n_steps(&self, n: usize, recorder: &mut Recorder) -> BTreeSet<Coord32>{
+        let reach = |gap: i32, size: i32| ((n as i32 - gap).max(0) as u32).div_ceil(size as u32) as i32;
+        let radius = [
+            reach(self.start.x, self.xmax + 1),
+            reach(self.xmax - self.start.x, self.xmax + 1),
+            reach(self.start.y, self.ymax + 1),
+            reach(self.ymax - self.start.y, self.ymax + 1),
+        ].into_iter().max().unwrap();
         let mut queue: BTreeSet<Coord32> = BTreeSet::from([self.start]);
+        recorder.offer(|| self.to_frame(&queue, radius));
         for _k in 0..n{
             let mut new_queue: BTreeSet<Coord32> = BTreeSet::new();
             for current_square in queue.clone(){
@@ -67,10 +81,34 @@ impl Garden {
                 }
             }
             queue = new_queue;
+            recorder.offer(|| self.to_frame(&queue, radius));
         }
 
         queue
     }
+    // The garden tiled `radius` copies out in every direction from the original,
+    // with the frontier in green
+    fn to_frame(&self, frontier: &BTreeSet<Coord32>, radius: i32) -> Frame {
+        let (width, height) = (self.xmax + 1, self.ymax + 1);
+        let copies = (2 * radius + 1) as usize;
+        let mut frame = Frame::new(width as usize * copies, height as usize * copies, '#');
+        for i in 0..frame.height {
+            for j in 0..frame.width {
+                let base = Coord32{x: j as i32 % width, y: i as i32 % height, x_iteration: 0, y_iteration: 0};
+                if self.ground.contains(&base) {
+                    frame.set(i, j, '.');
+                }
+            }
+        }
+        for coord in frontier {
+            let j = (coord.x_iteration + radius) * width + coord.x;
+            let i = (coord.y_iteration + radius) * height + coord.y;
+            if (0..frame.width as i32).contains(&j) && (0..frame.height as i32).contains(&i) {
+                frame.set_coloured(i as usize, j as usize, 'O', [80, 220, 80]);
+            }
+        }
+        frame
+    }
 //    fn unique_squares_after_many_steps(&self, n: usize) -> (BTreeSet<Coord32>, HashMap<(i32, i32), usize>){
 //        let mut queue: BTreeSet<Coord32> = BTreeSet::from([self.start]);
 //        let mut states: HashMap<(i32, i32), BTreeSet<usize>> = HashMap::new();
@@ -106,104 +144,123 @@ impl Garden {
 
 //}
     fn get_next_coords(&self, coordinate: Coord32) -> BTreeSet<Coord32> {
-        let mut west_new = Coord32{x: coordinate.x - 1, y: coordinate.y, x_iteration: coordinate.x_iteration, y_iteration: coordinate.y_iteration};
-        let mut east_new = Coord32{x: coordinate.x + 1, y: coordinate.y, x_iteration: coordinate.x_iteration, y_iteration: coordinate.y_iteration};
-        let mut north_new = Coord32{x: coordinate.x, y: coordinate.y - 1, x_iteration: coordinate.x_iteration, y_iteration: coordinate.y_iteration};
-        let mut south_new = Coord32{x: coordinate.x, y: coordinate.y + 1, x_iteration: coordinate.x_iteration, y_iteration: coordinate.y_iteration};
-        if self.edges.contains(&coordinate.get_base()){
-            if west_new.x < 0 {
-                west_new.x = self.xmax;
-                west_new.x_iteration -= 1;
-            }
-            else if west_new.x > self.xmax {
-                west_new.x = 0;
-                west_new.x_iteration += 1;
-            }
-            if east_new.x < 0 {
-                east_new.x = self.xmax;
-                east_new.x_iteration -= 1;
-            }
-            else if east_new.x > self.xmax {
-                east_new.x = 0;
-                east_new.x_iteration += 1;
-            }
-            //
-            if north_new.y < 0 {
-                north_new.y = self.ymax;
-                north_new.y_iteration -= 1;
-            }
-            else if north_new.y > self.ymax {
-                north_new.y = 0;
-                north_new.y_iteration += 1;
-            }
-            if south_new.y < 0 {
-                south_new.y = self.ymax;
-                south_new.y_iteration -= 1;
-            }
-            else if south_new.y > self.ymax {
-                south_new.y = 0;
-                south_new.y_iteration += 1;
-            }
-            
-        }
-
-        BTreeSet::from([east_new, west_new, north_new, south_new])
+        self.position(coordinate).neighbours4().map(|point| self.coord_at(point)).collect()
     }
 }
 
 
-fn parse_input(input: &str) -> Garden{
+// The garden has to be a rectangle, to tile, with exactly one start
+fn parse_input(input: &str) -> Result<Garden, String>{
     let data = get_input_as_chars(input);
+    let plots = Torus::new(data.clone())?;
     let mut ground: BTreeSet<Coord32> = BTreeSet::new();
     let mut rocks: BTreeSet<Coord32> = BTreeSet::new();
     let mut start: BTreeSet<Coord32> = BTreeSet::new();
-    let mut edges: BTreeSet<Coord32> = BTreeSet::new();
-    let xmax: i32 = (data[0].len() - 1) as i32;
-    let ymax: i32 = (data.len() - 1) as i32;
+    let xmax: i32 = (plots.width() - 1) as i32;
+    let ymax: i32 = (plots.height() - 1) as i32;
     for (i, row) in data.iter().enumerate() {
-        edges.insert(Coord32{x: 0, y: i as i32, x_iteration: 0, y_iteration: 0});
-        edges.insert(Coord32{x: (data.len() - 1) as i32, y: i as i32, x_iteration: 0, y_iteration: 0});
         for (j, c) in row.iter().enumerate(){
-            edges.insert(Coord32{x: j as i32, y: 0, x_iteration: 0, y_iteration: 0});
-            edges.insert(Coord32{x: j as i32, y: (row.len()-1) as i32, x_iteration: 0, y_iteration: 0});
             match c {
                 '.' => ground.insert(Coord32{x: j as i32, y: i as i32, x_iteration: 0, y_iteration: 0}),
                 '#' => rocks.insert(Coord32{x: j as i32, y: i as i32, x_iteration: 0, y_iteration: 0}),
                 'S' => start.insert(Coord32{x: j as i32, y: i as i32, x_iteration: 0, y_iteration: 0}),
-                _ => unreachable!(),
+                _ => return Err(format!("Unknown plot '{}' on row {}, column {}", c, i + 1, j + 1)),
             };
         }
     }
 
+    if start.len() != 1 {
+        return Err(format!("The garden needs one start, but has {}", start.len()));
+    }
     ground.append(&mut start.clone());
-    Garden{edges, ground, start: start.pop_first().unwrap(), xmax, ymax}
+    Ok(Garden{plots, ground, start: start.pop_first().unwrap(), xmax, ymax})
 
 }
 
 #[test]
 fn test_example_part1(){
-    let garden: Garden = parse_input(include_str!("../example.txt"));
+    let garden: Garden = parse_input(include_str!("../example.txt")).unwrap();
     let result: BTreeSet<Coord32> = garden.unique_squares_after_n_steps(6);
     assert_eq!(result.len(), 16);
 }
 
 #[test]
 fn test_example_part2a(){
-    let garden: Garden = parse_input(include_str!("../example.txt"));
+    let garden: Garden = parse_input(include_str!("../example.txt")).unwrap();
     let result: BTreeSet<Coord32> = garden.unique_squares_after_n_steps(10);
     assert_eq!(result.len(), 50);
 }
 
 #[test]
 fn test_example_part2b(){
-    let garden: Garden = parse_input(include_str!("../example.txt"));
-    let result: BTreeSet<Coord32> = garden.unique_squares_after_n_steps(200);
-    dbg!(get_count_per_square(result.clone()));
+    let garden: Garden = parse_input(include_str!("../example.txt")).unwrap();
+    let result: BTreeSet<Coord32> = garden.unique_squares_after_n_steps(100);
     assert_eq!(result.len(), 6536);
 }
 
+#[test]
+fn test_record_n_steps(){
+    let garden: Garden = parse_input(include_str!("../example.txt")).unwrap();
+    let mut recorder = Recorder::new();
+    let result: BTreeSet<Coord32> = garden.record_n_steps(12, &mut recorder);
+    // Steps 0 to 12, on a 3x3 block of gardens
+    let frames = recorder.frames();
+    assert_eq!(frames.len(), 13);
+    assert_eq!((frames[0].width, frames[0].height), (33, 33));
+    assert_eq!(frames[0].get(16, 16), 'O');
+    assert_eq!(frames[0].get(16, 5), '.');
+    assert_eq!(frames[0].get(12, 6), '#');
+    assert_eq!(frames[12].to_text().matches('O').count(), result.len());
+    assert_eq!(frames[1].colour(15, 16), Some([80, 220, 80]));
+}
+
+#[test]
+fn test_parse_args(){
+    let args = |args: &[&str]| parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    assert_eq!(args(&[]), Ok(Options{show: None, output: None}));
+    assert_eq!(args(&["--play"]), Ok(Options{show: None, output: Some(Output::Play)}));
+    assert_eq!(args(&["--gif", "walk.gif", "--show", "20"]), Ok(Options{show: Some(20), output: Some(Output::Gif("walk.gif".into()))}));
+    assert_eq!(args(&["--ppm", "frames"]).unwrap().output, Some(Output::Ppm("frames".into())));
+    assert!(args(&["--gif"]).is_err());
+    assert!(args(&["--show", "x"]).is_err());
+    assert!(args(&["--play", "--gif", "walk.gif"]).is_err());
+}
+
+#[test]
+fn test_tiling(){
+    let garden: Garden = parse_input(include_str!("../example.txt")).unwrap();
+    let coord = Coord32{x: 3, y: 10, x_iteration: -2, y_iteration: 1};
+    assert_eq!(garden.position(coord), Vec2::new(-19, 21));
+    assert_eq!(garden.coord_at(Vec2::new(-19, 21)), coord);
+    assert_eq!(garden.coord_at(Vec2::new(-1, 0)), Coord32{x: 10, y: 0, x_iteration: -1, y_iteration: 0});
+    // The puzzle's picture after 6 steps, and the same garden from one to the left
+    let picture = garden.to_text(&garden.unique_squares_after_n_steps(6), Window::new(Vec2::new(0, 0), Vec2::new(10, 10)));
+    assert_eq!(picture, "...........\n.....###.#.\n.###.##.O#.\n.O#O#O.O#..\nO.O.#.#.O..\n.##O.O####.\n.##.O#O..#.\n.O.O.O.##..\n.##.#.####.\n.##O.##.##.\n...........");
+    // Just the rows and columns the frontier reaches
+    let shown = garden.show(6);
+    assert_eq!(shown.lines().count(), 8);
+    assert_eq!(shown.lines().next(), picture.lines().nth(2).map(|row| &row[..9]));
+    assert_eq!(shown.matches('O').count(), 16);
+    assert_eq!(garden.show(0), "O");
+    let shifted = garden.to_text(&BTreeSet::new(), Window::new(Vec2::new(-11, 0), Vec2::new(-1, 10)));
+    assert_eq!(shifted, picture.replace('O', "."));
+    // Every step of the walk is one apart on the tiling, including across the edges
+    for coord in garden.unique_squares_after_n_steps(30) {
+        for next in garden.get_next_coords(coord) {
+            assert_eq!(garden.position(next).manhattan(&garden.position(coord)), 1);
+        }
+    }
+}
+
+#[test]
+fn test_fuzz_regressions(){
+    for input in aoc_gen::fuzz_regressions("day21_parse_input") {
+        assert!(parse_input(&input).is_err(), "{:?}", input);
+    }
+}
+
 fn parts(){
-    let garden: Garden = parse_input(include_str!("../input.txt"));
+    let garden: Garden = parse_input(include_str!("../input.txt")).unwrap();
     let result: BTreeSet<Coord32> = garden.unique_squares_after_n_steps(65);
     println!("Part 1 Answer x = 0: {}", result.len());
     let result: BTreeSet<Coord32> = garden.unique_squares_after_n_steps(65+131);
@@ -221,8 +278,68 @@ fn parts(){
 
 }
 
+// Every step of the first 65, which just reach the edges of the garden
+fn record(output: &Output) -> Result<(), String> {
+    let garden: Garden = parse_input(include_str!("../input.txt"))?;
+    let mut recorder = Recorder::new();
+    garden.record_n_steps(65, &mut recorder);
+    recorder.output(output, "day21", 4)
+}
+
+const USAGE: &str = "Usage: day21 [--show <steps>] [--play | --gif <path> | --ppm <directory>]";
+
+// The number of steps to draw the frontier after, and what to do with a
+// recording of the walk
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Options {
+    show: Option<usize>,
+    output: Option<Output>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options{show: None, output: None};
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let next = match arg.as_str() {
+            "--show" => {
+                let steps = args.next().ok_or(USAGE)?;
+                options.show = Some(steps.parse::<usize>().map_err(|_| format!("'{}' is not a number of steps", steps))?);
+                continue;
+            }
+            "--play" => Output::Play,
+            "--gif" => Output::Gif(args.next().ok_or(USAGE)?.into()),
+            "--ppm" => Output::Ppm(args.next().ok_or(USAGE)?.into()),
+            _ => return Err(USAGE.to_string()),
+        };
+        if options.output.replace(next).is_some() {
+            return Err(USAGE.to_string());
+        }
+    }
+    Ok(options)
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
     let start = Instant::now();
     parts();
     println!("*** Part 1 Took {:.2?} ***", start.elapsed());
+    if let Some(steps) = options.show {
+        match parse_input(include_str!("../input.txt")) {
+            Ok(garden) => println!("{}", garden.show(steps)),
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+    if let Some(output) = options.output {
+        if let Err(error) = record(&output) {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
 }
@@ -1,3 +1,5 @@
+use aoc_coords::Vec3;
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
 #[allow(dead_code)]
 pub enum InputType {
@@ -31,6 +33,18 @@ pub struct Coord3D {
     pub y: i64,
 }
 
+impl From<Coord3D> for Vec3<i64> {
+    fn from(coord: Coord3D) -> Vec3<i64> {
+        Vec3::new(coord.x, coord.y, coord.z)
+    }
+}
+
+impl From<Vec3<i64>> for Coord3D {
+    fn from(vector: Vec3<i64>) -> Coord3D {
+        Coord3D{z: vector.z, x: vector.x, y: vector.y}
+    }
+}
+
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
 #[allow(dead_code)]
@@ -91,7 +105,7 @@ fn calculate_perimeter64(coordinates: Vec<Coord64>) -> i64 {
 }
 
 #[allow(dead_code)]
-pub fn get_input_as_lines(input: &'static str) -> Vec<&'static str>{
+pub fn get_input_as_lines(input: &str) -> Vec<&str>{
     let mut data: Vec<&str> = Vec::new();
     for line in input.lines() {
         data.push(line)
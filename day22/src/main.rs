@@ -1,6 +1,11 @@
 mod aoc_parser;
 use std::{time::Instant, collections::{BTreeSet, HashMap, HashSet}, fs::File, io::{Write, BufWriter}};
 use aoc_parser::{get_input_as_lines, Coord3D};
+use aoc_graph::{GraphEdge, GraphExport, GraphNode};
+use aoc_coords::Vec3;
+
+// One block higher up the tower
+const UP: Vec3<i64> = Vec3{x: 0, y: 0, z: 1};
 
 
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
@@ -10,6 +15,14 @@ struct Tower {
 
 impl Tower {
     fn let_bricks_fall(&mut self){
+        self.settle();
+        let _ = self.save();
+        println!("done!")
+    
+    }
+
+    // Drops every brick as far as it will go
+    fn settle(&mut self){
         let mut fallen_bricks = BTreeSet::new(); //self.get_bricks_at_base().clone();
         for brick in self.bricks.clone(){
             let mut mut_brick = brick.clone();
@@ -28,9 +41,6 @@ impl Tower {
             fallen_bricks.insert(mut_brick.clone());
         }
         self.bricks = fallen_bricks.clone();
-        let _ = self.save();
-        println!("done!")
-    
     }
 
     fn save(&self) -> std::io::Result<()>{
@@ -48,15 +58,15 @@ impl Tower {
         Ok(())
     }
 
-    fn brick_in_freefall(&mut self, mut brick: Brick, fallen_bricks: BTreeSet<Brick>) -> bool{
+    fn brick_in_freefall(&mut self, brick: Brick, fallen_bricks: BTreeSet<Brick>) -> bool{
         for coord in brick.get_coords(){
-            let c = Coord3D{z: coord.z - 1, x: coord.x, y: coord.y};
+            let c = Coord3D::from(Vec3::from(coord) - UP);
             if c.z < 1 {
                 return false;
             }
             for brick2 in fallen_bricks.clone().iter().rev(){
                 //dbg!(brick2);
-                let brick2_coords = brick2.clone().get_coords();
+                let brick2_coords = brick2.get_coords();
                 if brick2_coords.contains(&c){
                     return false;
                 }
@@ -69,7 +79,7 @@ impl Tower {
         let mut total: usize = 0;
         for brick in self.bricks.clone(){
             let supported_bricks = self.get_neighbours_to(brick.clone());
-            if supported_bricks.len() > 0{
+            if !supported_bricks.is_empty(){
                 let mut other_supports: HashSet<bool> = HashSet::new();
                 for supported_brick in supported_bricks{
                     let supporting_bricks = self.get_supporting_bricks(supported_brick);
@@ -92,7 +102,7 @@ impl Tower {
         total
     }
 
-    fn find_load_bearing_bricks(&mut self) -> BTreeSet<Brick>{
+    fn find_load_bearing_bricks(&self) -> BTreeSet<Brick>{
         let mut load_bearers: BTreeSet<Brick> = BTreeSet::new();
         let mut parents: HashMap<Brick, Brick> = HashMap::new();
         let mut explored: HashSet<Brick> = HashSet::new();
@@ -129,45 +139,62 @@ impl Tower {
         }
         bricks
     }
-    fn get_brick_containing_coord(self, coord: Coord3D) -> Brick{
-        for mut brick in self.bricks{
-            if brick.get_coords().contains(&coord){
-                return brick;
-            }
-        }
-        unreachable!();
+    fn get_brick_containing_coord(&self, coord: Coord3D) -> Option<&Brick>{
+        self.bricks.iter().find(|brick| brick.get_coords().contains(&coord))
     }
-    fn is_there_a_brick_containing_coord(self, coord: Coord3D) -> bool{
-        for mut brick in self.bricks{
-            let brick_coords = brick.get_coords();
-            if brick_coords.contains(&coord){
-                return true;
-            }
-        }
-        false
-    }
-    fn get_neighbours_to(&mut self, mut brick: Brick) -> BTreeSet<Brick>{
+    fn get_neighbours_to(&self, brick: Brick) -> BTreeSet<Brick>{
         let mut neighbours: BTreeSet<Brick> = BTreeSet::new();
         for coord in brick.get_coords(){
-            let c = Coord3D{z: coord.z + 1, x: coord.x, y: coord.y};
-            if !brick.get_coords().contains(&c) && self.clone().is_there_a_brick_containing_coord(c){
-                neighbours.insert(self.clone().get_brick_containing_coord(c));
+            let c = Coord3D::from(Vec3::from(coord) + UP);
+            if !brick.get_coords().contains(&c) {
+                neighbours.extend(self.get_brick_containing_coord(c).cloned());
             }
         }
         neighbours
     } 
-    fn get_supporting_bricks(&mut self, mut brick: Brick) -> BTreeSet<Brick>{
+    fn get_supporting_bricks(&self, brick: Brick) -> BTreeSet<Brick>{
         let mut neighbours: BTreeSet<Brick> = BTreeSet::new();
         for coord in brick.get_coords(){
-            let c = Coord3D{z: coord.z - 1, x: coord.x, y: coord.y};
-            if !brick.get_coords().contains(&c) && self.clone().is_there_a_brick_containing_coord(c){
-                neighbours.insert(self.clone().get_brick_containing_coord(c));
+            let c = Coord3D::from(Vec3::from(coord) - UP);
+            if !brick.get_coords().contains(&c) {
+                neighbours.extend(self.get_brick_containing_coord(c).cloned());
             }
         }
         neighbours
     } 
 }
 
+// Bricks are numbered in order of height, and each edge runs from a brick to
+// one resting directly on top of it.
+impl GraphExport for Tower {
+    fn graph_nodes(&self) -> Vec<GraphNode> {
+        self.bricks.iter().enumerate()
+            .map(|(i, brick)| GraphNode::new(&i.to_string(), &brick.to_string()))
+            .collect()
+    }
+    fn graph_edges(&self) -> Vec<GraphEdge> {
+        // Which brick fills each cube, so the one above any cube is a single lookup
+        let mut owners: HashMap<Coord3D, usize> = HashMap::new();
+        for (i, brick) in self.bricks.iter().enumerate() {
+            owners.extend(brick.get_coords().into_iter().map(|coord| (coord, i)));
+        }
+        let mut edges: Vec<GraphEdge> = Vec::new();
+        for (i, brick) in self.bricks.iter().enumerate() {
+            let supported: BTreeSet<usize> = brick.get_coords().into_iter()
+                .filter_map(|coord| owners.get(&Coord3D::from(Vec3::from(coord) + UP)).copied())
+                .filter(|j| *j != i)
+                .collect();
+            for j in supported {
+                edges.push(GraphEdge::new(&i.to_string(), &j.to_string()));
+            }
+        }
+        edges
+    }
+    fn graph_name(&self) -> String {
+        "bricks".to_string()
+    }
+}
+
 
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
 struct Brick{
@@ -175,7 +202,7 @@ struct Brick{
 }
 
 impl Brick{
-    fn get_coords(&mut self) -> BTreeSet<Coord3D>{
+    fn get_coords(&self) -> BTreeSet<Coord3D>{
         let mut coords: BTreeSet<Coord3D> = BTreeSet::new();
         let xdiff = self.ends.0.x.abs_diff(self.ends.1.x);
         let ydiff = self.ends.0.y.abs_diff(self.ends.1.y);
@@ -195,37 +222,124 @@ impl Brick{
     }
 }
 
+impl std::fmt::Display for Brick {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{},{},{}~{},{},{}", self.ends.0.x, self.ends.0.y, self.ends.0.z, self.ends.1.x, self.ends.1.y, self.ends.1.z)
+    }
+}
+
 
-fn string_to_coord3d(string: &str) -> Coord3D {
-    let bindings: Vec<&str> = string.split(',').collect();
-    let x: i64 = bindings[0].parse::<i64>().unwrap();
-    let y: i64 = bindings[1].parse::<i64>().unwrap();
-    let z: i64 = bindings[2].parse::<i64>().unwrap();
-    Coord3D{z, x, y}
+fn string_to_coord3d(string: &str) -> Result<Coord3D, String> {
+    let bindings: Vec<i64> = string.split(',')
+        .map(|number| number.trim().parse::<i64>().map_err(|_| format!("Invalid number '{}'", number)))
+        .collect::<Result<_, _>>()?;
+    match bindings[..] {
+        [x, y, z] => Ok(Coord3D{z, x, y}),
+        _ => Err(format!("Expected 'x,y,z', found '{}'", string)),
+    }
 }
 
 
-fn parse_row_into_brick(line: &str) -> Brick{
-    let ends_string: Vec<&str> = line.split('~').collect();
-    let start: Coord3D = string_to_coord3d(ends_string[0]);
-    let end: Coord3D = string_to_coord3d(ends_string[1]);
-    Brick { ends: (start, end) }
+// Both ends are inclusive, and the first is never past the second on any axis.
+// The ground is at z = 0, so every brick starts at z = 1 or above.
+fn parse_row_into_brick(line: &str) -> Result<Brick, String>{
+    let (start, end) = line.split_once('~').ok_or(format!("Missing '~' in '{}'", line))?;
+    let start: Coord3D = string_to_coord3d(start)?;
+    let end: Coord3D = string_to_coord3d(end)?;
+    if start.x > end.x || start.y > end.y || start.z > end.z {
+        return Err(format!("The ends of '{}' are the wrong way round", line));
+    }
+    if start.z < 1 {
+        return Err(format!("'{}' is below the ground", line));
+    }
+    Ok(Brick { ends: (start, end) })
 }
 
 
-fn parse_input() -> Tower{
-    let data = get_input_as_lines(include_str!("../input.txt"));
+fn parse_input(mut string: &str) -> Result<Tower, String>{
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    let data = get_input_as_lines(string);
     let mut bricks: BTreeSet<Brick> = BTreeSet::new();
-    for row in data {
-        bricks.insert(parse_row_into_brick(row));
+    for (i, row) in data.iter().enumerate() {
+        bricks.insert(parse_row_into_brick(row).map_err(|e| format!("Line {}: {}", i + 1, e))?);
+    }
+    Ok(Tower { bricks })
+}
+
+#[test]
+fn test_support_graph() {
+    // The example after the bricks have settled
+    let tower = parse_input(include_str!("../example3.txt")).unwrap();
+    let nodes = tower.graph_nodes();
+    assert_eq!(nodes.len(), 7);
+    assert_eq!(nodes[0].label, "1,0,1~1,2,1");
+    let edges: Vec<(String, String)> = tower.graph_edges().into_iter().map(|edge| (edge.source, edge.target)).collect();
+    let expected = [("0", "1"), ("0", "2"), ("1", "3"), ("1", "4"), ("2", "3"), ("2", "4"), ("3", "5"), ("4", "5"), ("5", "6")];
+    assert_eq!(edges, expected.map(|(a, b)| (a.to_string(), b.to_string())).to_vec());
+    assert!(tower.to_dot().contains("    \"6\" [label=\"1,1,5~1,1,6\"];\n"));
+}
+
+#[test]
+fn test_support_chain() {
+    let tower = parse_input(include_str!("../example3.txt")).unwrap();
+    assert_eq!(tower.get_bricks_at_base().len(), 1);
+    // From the ground brick up through one brick of each layer to the top one
+    let chain: Vec<String> = tower.find_load_bearing_bricks().iter().map(|brick| brick.to_string()).collect();
+    assert_eq!(chain.len(), 5);
+    assert_eq!(chain.first().map(|brick| brick.as_str()), Some("1,0,1~1,2,1"));
+    assert_eq!(chain.last().map(|brick| brick.as_str()), Some("1,1,5~1,1,6"));
+}
+
+#[test]
+fn test_export_args() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+    let mut tower = parse_input("").unwrap();
+    tower.settle();
+    assert_eq!(export(&args(&["--export", "dot"])), Ok(tower.to_dot()));
+    assert!(export(&args(&["--export", "json"])).unwrap().starts_with("{\"directed\": true"));
+    assert!(export(&args(&["--export", "png"])).is_err());
+    assert!(export(&args(&["--dot"])).is_err());
+}
+
+#[test]
+fn test_generated_snapshot() {
+    let tower = parse_input(&aoc_gen::brick_snapshot(&mut aoc_gen::Rng::new(22), 300, 10)).unwrap();
+    assert_eq!(tower.bricks.len(), 300);
+    assert!(tower.bricks.iter().all(|brick| brick.ends.0.z >= 1 && brick.ends.0.z <= brick.ends.1.z));
+}
+
+#[test]
+fn test_vec3_conversion() {
+    // ByZ sorts the same way as Coord3D's derived order
+    let coords = [Coord3D{z: 2, x: 0, y: 5}, Coord3D{z: 1, x: 3, y: 0}, Coord3D{z: 1, x: 0, y: 9}];
+    let mut sorted = coords.to_vec();
+    sorted.sort();
+    let mut by_z: Vec<aoc_coords::ByZ<i64>> = coords.iter().map(|coord| aoc_coords::ByZ((*coord).into())).collect();
+    by_z.sort();
+    let converted: Vec<Coord3D> = by_z.into_iter().map(|aoc_coords::ByZ(vector)| vector.into()).collect();
+    assert_eq!(converted, sorted);
+    assert_eq!(Vec3::from(coords[0]), Vec3::new(0, 5, 2));
+    assert_eq!(Coord3D::from(Vec3::from(coords[0]) - UP), Coord3D{z: 1, x: 0, y: 5});
+    for coord in coords {
+        assert_eq!(Coord3D::from(Vec3::from(coord)), coord);
+    }
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day22_parse_row_into_brick") {
+        assert!(parse_row_into_brick(&input).is_err(), "{:?}", input);
     }
-    Tower { bricks }
 }
 
 fn part1(){
-    let mut tower: Tower = parse_input();
+    let mut tower: Tower = parse_input("").unwrap();
     tower.let_bricks_fall();
     dbg!("bricks fallen!");
+    println!("{} bricks rest on the ground, and {} make up a stack from there to the top brick",
+        tower.get_bricks_at_base().len(), tower.find_load_bearing_bricks().len());
     //dbg!(bricks.clone());
     // let difference: Vec<Brick> = tower.bricks.difference(&bricks).cloned().collect();
     println!("Part 1 Answer: {}", tower.find_bricks_could_be_disintegrated());
@@ -236,7 +350,31 @@ fn part2(){
     println!("Part 2 Answer: {}", 0);
 }
 
+// With `--export <dot|json>`, prints which settled bricks rest on which as a
+// graph instead of solving
+fn export(args: &[String]) -> Result<String, String> {
+    match args {
+        [flag, format] if flag == "--export" => {
+            let mut tower = parse_input("")?;
+            tower.settle();
+            tower.export(format)
+        }
+        _ => Err("Usage: day22 [--export <dot|json>]".to_string()),
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match export(&args) {
+            Ok(graph) => print!("{}", graph),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
     let start = Instant::now();
     part1();
     println!("*** Part 1 Took {:.2?} ***", start.elapsed());
@@ -1,3 +1,5 @@
+use aoc_coords::Vec2;
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
 #[allow(dead_code)]
 pub enum InputType {
@@ -22,6 +24,18 @@ pub struct Coord32 {
     pub y: i32,
 }
 
+impl From<Coord32> for Vec2<i32> {
+    fn from(coord: Coord32) -> Vec2<i32> {
+        Vec2::new(coord.x, coord.y)
+    }
+}
+
+impl From<Vec2<i32>> for Coord32 {
+    fn from(vector: Vec2<i32>) -> Coord32 {
+        Coord32{x: vector.x, y: vector.y}
+    }
+}
+
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
 #[allow(dead_code)]
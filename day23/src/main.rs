@@ -1,6 +1,8 @@
 mod aoc_parser;
 use std::{time::Instant, collections::{HashMap, BTreeSet, BTreeMap}, cmp::max};
-use aoc_parser::{Coord32, get_input_as_chars, get_input_as_lines};
+use aoc_parser::{Coord32, get_input_as_chars};
+use aoc_graph::{GraphEdge, GraphExport, GraphNode};
+use aoc_coords::Vec2;
 
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
@@ -21,25 +23,17 @@ struct Tile {
 
 impl Tile {
     fn get_neighbors1(&self) -> Vec<Coord32>{
+        let point = Vec2::from(self.point);
         match self.land {
-            Land::East => vec![Coord32{x: self.point.x+1, y: self.point.y}],
-            Land::South => vec![Coord32{x: self.point.x, y: self.point.y+1}],
-            Land::FLat => vec![
-                Coord32{x: self.point.x+1, y: self.point.y},
-                Coord32{x: self.point.x-1, y: self.point.y},
-                Coord32{x: self.point.x, y: self.point.y+1},
-                Coord32{x: self.point.x, y: self.point.y-1}],
+            Land::East => vec![(point + Vec2::new(1, 0)).into()],
+            Land::South => vec![(point + Vec2::new(0, 1)).into()],
+            Land::FLat => self.get_neighbors2(),
             Land::Forest => Vec::new(),
 
         }
     }
     fn get_neighbors2(&self) -> Vec<Coord32>{
-        vec![
-            Coord32{x: self.point.x+1, y: self.point.y},
-            Coord32{x: self.point.x-1, y: self.point.y},
-            Coord32{x: self.point.x, y: self.point.y+1},
-            Coord32{x: self.point.x, y: self.point.y-1}
-            ]
+        Vec2::from(self.point).neighbours4().map(Coord32::from).collect()
     }
 }
 
@@ -75,7 +69,7 @@ impl Trail {
                 neighbors.push(n);
             }
         }
-        while neighbors.len() > 0 {
+        while !neighbors.is_empty() {
             if neighbors.len() == 1 {
                 let neighbor = neighbors.pop().unwrap();
                 current_distance += 1;
@@ -114,7 +108,7 @@ impl Trail {
                 neighbors.push(n);
             }
         }
-        while neighbors.len() > 0 {
+        while !neighbors.is_empty() {
             if neighbors.len() == 1 {
                 let neighbor = neighbors.pop().unwrap();
                 current_distance += 1;
@@ -140,8 +134,103 @@ impl Trail {
     }
 }
 
-fn parse_input() -> Trail{
-    let data = get_input_as_chars(include_str!("../input.txt"));
+// The trail reduced to the start, the end and every tile where paths meet, with
+// the length of the corridor between each pair of them. Corridors can only be
+// walked one way when the slopes are being followed.
+struct Junctions {
+    points: Vec<Coord32>,
+    edges: Vec<(usize, usize, usize)>,
+    slopes: bool,
+}
+
+impl GraphExport for Junctions {
+    fn graph_nodes(&self) -> Vec<GraphNode> {
+        self.points.iter().enumerate()
+            .map(|(i, point)| GraphNode::new(&i.to_string(), &format!("{},{}", point.x, point.y)))
+            .collect()
+    }
+    fn graph_edges(&self) -> Vec<GraphEdge> {
+        self.edges.iter()
+            .map(|(from, to, length)| GraphEdge::new(&from.to_string(), &to.to_string()).with_weight(*length as i64))
+            .collect()
+    }
+    fn graph_name(&self) -> String {
+        "junctions".to_string()
+    }
+    fn is_directed(&self) -> bool {
+        self.slopes
+    }
+}
+
+impl Trail {
+    fn get_steps(&self, point: Coord32, slopes: bool) -> Vec<Coord32> {
+        let tile = self.tiles.get(&point).unwrap();
+        let steps = if slopes { tile.get_neighbors1() } else { tile.get_neighbors2() };
+        steps.into_iter().filter(|n| self.ground.contains(n)).collect()
+    }
+    fn get_junctions(&self, slopes: bool) -> Junctions {
+        let (_, ymax) = self.get_limits();
+        let points: Vec<Coord32> = self.ground.iter()
+            .filter(|point| point.y == 0 || point.y == ymax || self.get_steps(**point, false).len() > 2)
+            .copied()
+            .collect();
+        let ids: HashMap<Coord32, usize> = points.iter().enumerate().map(|(i, point)| (*point, i)).collect();
+        let mut edges: Vec<(usize, usize, usize)> = Vec::new();
+        for (from, point) in points.iter().enumerate() {
+            for first in self.get_steps(*point, slopes) {
+                let mut previous = *point;
+                let mut current = first;
+                let mut length: usize = 1;
+                while !ids.contains_key(&current) {
+                    match self.get_steps(current, slopes).into_iter().find(|n| *n != previous) {
+                        Some(next) => {
+                            previous = current;
+                            current = next;
+                            length += 1;
+                        }
+                        None => break,
+                    }
+                }
+                if let Some(to) = ids.get(&current) {
+                    // Without slopes every corridor is found once from each end
+                    if slopes || from < *to {
+                        edges.push((from, *to, length));
+                    }
+                }
+            }
+        }
+        Junctions{points, edges, slopes}
+    }
+}
+
+// The longest walk between two junctions that never visits one twice
+fn longest_walk(junctions: &Junctions, current: usize, goal: usize, visited: &mut Vec<bool>) -> Option<usize> {
+    if current == goal {
+        return Some(0);
+    }
+    visited[current] = true;
+    let mut best: Option<usize> = None;
+    for (from, to, length) in &junctions.edges {
+        let next = match (*from == current, *to == current && !junctions.slopes) {
+            (true, _) => *to,
+            (false, true) => *from,
+            _ => continue,
+        };
+        if !visited[next] {
+            if let Some(rest) = longest_walk(junctions, next, goal, visited) {
+                best = best.max(Some(rest + length));
+            }
+        }
+    }
+    visited[current] = false;
+    best
+}
+
+fn parse_input(mut string: &str) -> Result<Trail, String>{
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    let data = get_input_as_chars(string);
     let mut tiles: HashMap<Coord32, Tile> = HashMap::new();
     let mut ground: BTreeSet<Coord32> = BTreeSet::new();
     let mut distances: HashMap<Coord32, usize> = HashMap::new();
@@ -153,7 +242,7 @@ fn parse_input() -> Trail{
                 '.' => Land::FLat,
                 '>' => Land::East,
                 'v' => Land::South,
-                _ => unreachable!(),
+                _ => return Err(format!("Unknown tile '{}' on row {}, column {}", c, j + 1, i + 1)),
             };
             if tile_type != Land::Forest {
                 ground.insert(coord);
@@ -163,38 +252,138 @@ fn parse_input() -> Trail{
 
         }
     }
-    Trail{tiles, ground, distances, cache: BTreeMap::new()}
+    Ok(Trail{tiles, ground, distances, cache: BTreeMap::new()})
+
+}
+
+#[test]
+fn test_junction_graph() {
+    let trail = parse_input(include_str!("../example.txt")).unwrap();
+    let junctions = trail.get_junctions(true);
+    assert_eq!(junctions.points.len(), 9);
+    let goal = junctions.points.len() - 1;
+    assert_eq!(longest_walk(&junctions, 0, goal, &mut vec![false; 9]), Some(94));
+    assert!(junctions.to_dot().starts_with("digraph"));
+    assert!(junctions.to_dot().contains("    \"0\" -> \"1\" [weight=15, label=\"15\"];\n"));
+
+    let junctions = trail.get_junctions(false);
+    assert_eq!(longest_walk(&junctions, 0, goal, &mut vec![false; 9]), Some(154));
+    assert!(junctions.to_node_link_json().starts_with("{\"directed\": false, \"multigraph\": false"));
+}
+
+#[test]
+fn test_vec2_conversion() {
+    let coord = Coord32{x: 4, y: 0};
+    assert_eq!(Vec2::from(coord), Vec2::new(4, 0));
+    assert_eq!(Coord32::from(Vec2::from(coord)), coord);
+    let slope = Tile{point: coord, land: Land::South};
+    assert_eq!(slope.get_neighbors1(), vec![Coord32{x: 4, y: 1}]);
+    let flat = Tile{point: coord, land: Land::FLat};
+    assert_eq!(flat.get_neighbors2(), vec![Coord32{x: 4, y: -1}, Coord32{x: 5, y: 0}, Coord32{x: 4, y: 1}, Coord32{x: 3, y: 0}]);
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day23_parse_input") {
+        assert!(parse_input(&input).is_err(), "{:?}", input);
+    }
+}
 
+#[test]
+fn test_parse_args() {
+    let args = |args: &[&str]| parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    assert_eq!(args(&[]), Ok(Options{junctions: false, export: None}));
+    assert_eq!(args(&["--junctions"]), Ok(Options{junctions: true, export: None}));
+    assert_eq!(args(&["--export", "json"]), Ok(Options{junctions: false, export: Some("json".to_string())}));
+    assert!(args(&["--export"]).is_err());
+    assert!(args(&["--dot"]).is_err());
+    assert_eq!(longest_walk_through_junctions(&parse_input("").unwrap(), true), Some(94));
 }
 
-fn parse_input2(){
-    let data = get_input_as_lines("../input2.txt");
-    
+// From the start to the end over the junction graph, rather than tile by tile
+fn longest_walk_through_junctions(trail: &Trail, slopes: bool) -> Option<usize> {
+    let junctions = trail.get_junctions(slopes);
+    let goal = junctions.points.len().checked_sub(1)?;
+    longest_walk(&junctions, 0, goal, &mut vec![false; junctions.points.len()])
 }
 
-fn part1(){
-    let mut trail: Trail = parse_input();
-    let start = Coord32{x: 1 as i32, y: 0 as i32};
+fn part1(junctions: bool){
+    let mut trail: Trail = parse_input("").unwrap();
+    if junctions {
+        match longest_walk_through_junctions(&trail, true) {
+            Some(steps) => println!("Part 1 Answer: {}", steps),
+            None => println!("Part 1: there is no walk from the start to the end"),
+        }
+        return;
+    }
+    let start = Coord32{x: 1, y: 0};
     let limits = trail.get_limits();
     trail.find_longest_path_with_slopes(start, 0, BTreeSet::new());
     println!("Part 1 Answer: {}", trail.distances.get(&Coord32{x: limits.0-1, y: limits.1}).unwrap());
 }
 
 
-fn part2(){
-    let mut trail: Trail = parse_input();
-    let start = Coord32{x: 1 as i32, y: 0 as i32};
+fn part2(junctions: bool){
+    let mut trail: Trail = parse_input("").unwrap();
+    if junctions {
+        match longest_walk_through_junctions(&trail, false) {
+            Some(steps) => println!("Part 2 Answer: {}", steps),
+            None => println!("Part 2: there is no walk from the start to the end"),
+        }
+        return;
+    }
+    let start = Coord32{x: 1, y: 0};
     let limits = trail.get_limits();
     trail.find_longest_path_without_slopes(start, 0, BTreeSet::new());
     println!("Part 2 Answer: {}", trail.distances.get(&Coord32{x: limits.0-1, y: limits.1}).unwrap());
 }
 
+const USAGE: &str = "Usage: day23 [--junctions] [--export <dot|json>]";
+
+// --junctions solves over the junction graph, and --export prints the graph
+// the slopes allow instead of solving
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Options {
+    junctions: bool,
+    export: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options{junctions: false, export: None};
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--junctions" => options.junctions = true,
+            "--export" => options.export = Some(args.next().ok_or(USAGE)?.to_string()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(options)
+}
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    if let Some(format) = &options.export {
+        match parse_input("").and_then(|trail| trail.get_junctions(true).export(format)) {
+            Ok(graph) => print!("{}", graph),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
     let start = Instant::now();
-    part1();
+    part1(options.junctions);
     println!("*** Part 1 Took {:.2?} ***", start.elapsed());
     let start2 = Instant::now();
-    part2();
+    part2(options.junctions);
     println!("*** Part 2 Took {:.2?} ***", start2.elapsed());
 }
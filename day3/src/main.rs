@@ -1,146 +1,228 @@
-use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 
-#[derive(Clone, Debug)]
-struct Grid {
-    chars: Vec<Vec<char>>,
-    gears: HashMap<(usize, usize), Vec<usize>>,
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct PartNumber {
+    value: usize,
+    row: usize,
+    col_span: Range<usize>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Symbol {
+    char: char,
+    pos: (usize, usize),
+}
 
-impl Grid {
-    fn is_symbol_adjacent(&self, i: usize, j: usize) -> (bool, HashSet<(usize, usize)>) {
-        let mut iplus: usize = i + 1;
-        let mut iminus_temp: i32 = i as i32;
-        let mut jplus: usize = j + 1;
-        let mut jminus_temp: i32 = j as i32;
-        let iminus: usize;
-        let jminus: usize;
-        iminus_temp -= 1;
-        jminus_temp -= 1;
-        if iplus >= self.chars.len() {
-            iplus = self.chars.len() - 1;
-        }
-                
-        if jplus >= self.chars[0].len() {
-            jplus = self.chars[0].len() - 1;
-        }
+// The parsed engine schematic. Numbers and symbols are indexed by their position
+// in `numbers` and `symbols`, and adjacency is stored both ways round.
+#[derive(Clone, Debug)]
+struct Schematic {
+    chars: Vec<Vec<char>>,
+    numbers: Vec<PartNumber>,
+    symbols: Vec<Symbol>,
+    number_symbols: Vec<Vec<usize>>,
+    symbol_numbers: Vec<Vec<usize>>,
+}
 
-        if iminus_temp < 0 {
-            iminus = 0;
+impl Schematic {
+    fn is_part(&self, number: usize) -> bool {
+        !self.symbols_touching(number).is_empty()
+    }
+    fn part_numbers(&self) -> Vec<&PartNumber> {
+        (0..self.numbers.len()).filter(|n| self.is_part(*n)).map(|n| &self.numbers[n]).collect()
+    }
+    fn numbers_touching(&self, symbol: usize) -> Vec<&PartNumber> {
+        self.symbol_numbers[symbol].iter().map(|n| &self.numbers[*n]).collect()
+    }
+    fn symbols_touching(&self, number: usize) -> Vec<&Symbol> {
+        self.number_symbols[number].iter().map(|s| &self.symbols[*s]).collect()
+    }
+    fn symbols_with_neighbours(&self, symbol_char: char, neighbours: usize) -> Vec<usize> {
+        (0..self.symbols.len())
+            .filter(|s| self.symbols[*s].char == symbol_char && self.symbol_numbers[*s].len() == neighbours)
+            .collect()
+    }
+    // Sum over every `symbol_char` touching exactly `neighbours` numbers of the
+    // product of those numbers. Gear ratios are ('*', 2). None if the total
+    // doesn't fit in a usize.
+    fn sum_of_products(&self, symbol_char: char, neighbours: usize) -> Option<usize> {
+        let mut total: usize = 0;
+        for s in self.symbols_with_neighbours(symbol_char, neighbours) {
+            let product = self.numbers_touching(s).iter().try_fold(1usize, |product, number| product.checked_mul(number.value))?;
+            total = total.checked_add(product)?;
         }
-        else {
-            iminus = i - 1;
+        Some(total)
+    }
+    // The schematic with part numbers in green, other numbers in red and symbols in yellow
+    fn highlight(&self) -> String {
+        let mut colours: Vec<Vec<Option<&str>>> = self.chars.iter().map(|row| vec![None; row.len()]).collect();
+        for (n, number) in self.numbers.iter().enumerate() {
+            let colour = if self.is_part(n) { "\x1b[32m" } else { "\x1b[31m" };
+            for j in number.col_span.clone() {
+                colours[number.row][j] = Some(colour);
+            }
         }
-                
-        if jminus_temp < 0 {
-            jminus = 0;
+        for symbol in &self.symbols {
+            colours[symbol.pos.0][symbol.pos.1] = Some("\x1b[33m");
         }
-        else {
-            jminus = j - 1;
+        let mut display_string = String::new();
+        for (row, row_colours) in self.chars.iter().zip(colours) {
+            for (c, colour) in row.iter().zip(row_colours) {
+                match colour {
+                    Some(colour) => display_string.push_str(&format!("{}{}\x1b[0m", colour, c)),
+                    None => display_string.push(*c),
+                }
+            }
+            display_string.push('\n');
         }
+        display_string
+    }
+}
 
-        let pairs: [(usize, usize); 8] = [
-            (iminus, j), 
-            (iplus, j), 
-            (iminus, jminus), 
-            (iplus, jminus), 
-            (iminus, jplus), 
-            (iplus, jplus), 
-            (i, jminus), 
-            (i, jplus)
-            ];
-
-        let mut stars: HashSet<(usize, usize)> = HashSet::new();
-        let mut value: bool = false;
-        for pair in pairs.into_iter() {
-            if !(self.chars[pair.0][pair.1] == '.' || self.chars[pair.0][pair.1].is_ascii_digit()){
-                value = true
-            }
-            if self.chars[pair.0][pair.1]  == '*' {
-                stars.insert(pair);
+fn neighbours(i: usize, j: usize, height: usize, width: usize) -> Vec<(usize, usize)> {
+    let mut cells: Vec<(usize, usize)> = Vec::new();
+    for ii in i.saturating_sub(1)..(i + 2).min(height) {
+        for jj in j.saturating_sub(1)..(j + 2).min(width) {
+            if (ii, jj) != (i, j) {
+                cells.push((ii, jj));
             }
         }
-        return (value, stars)
+    }
+    cells
+}
 
+fn parse_input(mut string: &str) -> Result<Schematic, String> {
+    if string.is_empty(){
+        string = include_str!("../input.txt");
     }
-    fn get_number(&self, i: usize, j: usize) -> (usize, bool, usize, HashSet<(usize, usize)>) {
-        let ii: usize = i;
-        let mut jj: usize = j;
-        let mut number: String = String::new();
-        let mut is_adj_symb: (bool, HashSet<(usize, usize)>) = (false, HashSet::new());
-        let mut stars: HashSet<(usize, usize)> = HashSet::new();
-        
-        while ii < self.chars.len() && jj < self.chars[0].len() && self.chars[ii][jj].is_ascii_digit(){
-            if !is_adj_symb.0 {
-                is_adj_symb = self.is_symbol_adjacent(ii, jj);
-                stars.extend(&mut is_adj_symb.1.iter());
+    let chars: Vec<Vec<char>> = string.lines().map(|line| line.chars().collect()).collect();
+    let mut numbers: Vec<PartNumber> = Vec::new();
+    let mut symbols: Vec<Symbol> = Vec::new();
+    for (i, row) in chars.iter().enumerate() {
+        let mut j: usize = 0;
+        while j < row.len() {
+            if row[j].is_ascii_digit() {
+                let start = j;
+                while j < row.len() && row[j].is_ascii_digit() {
+                    j += 1;
+                }
+                let digits: String = row[start..j].iter().collect();
+                let value = digits.parse::<usize>().map_err(|_| format!("Number {} on row {} is too big", digits, i + 1))?;
+                numbers.push(PartNumber{value, row: i, col_span: start..j});
+                continue;
             }
-            if self.chars[ii][jj].is_ascii_digit() {
-                number.push(self.chars[ii][jj])
+            if row[j] != '.' {
+                symbols.push(Symbol{char: row[j], pos: (i, j)});
             }
-            else {
-                return (number.parse::<usize>().unwrap(), is_adj_symb.0, jj, stars);
+            j += 1;
+        }
+    }
+    // Look up which symbol, if any, sits at each cell
+    let mut symbol_at: Vec<Vec<Option<usize>>> = chars.iter().map(|row| vec![None; row.len()]).collect();
+    for (s, symbol) in symbols.iter().enumerate() {
+        symbol_at[symbol.pos.0][symbol.pos.1] = Some(s);
+    }
+    let mut number_symbols: Vec<Vec<usize>> = vec![Vec::new(); numbers.len()];
+    let mut symbol_numbers: Vec<Vec<usize>> = vec![Vec::new(); symbols.len()];
+    for (n, number) in numbers.iter().enumerate() {
+        for j in number.col_span.clone() {
+            for (ii, jj) in neighbours(number.row, j, chars.len(), chars[number.row].len()) {
+                if let Some(s) = symbol_at[ii].get(jj).copied().flatten() {
+                    if !number_symbols[n].contains(&s) {
+                        number_symbols[n].push(s);
+                        symbol_numbers[s].push(n);
+                    }
+                }
             }
-            jj += 1;
         }
-        return (number.parse::<usize>().unwrap(), is_adj_symb.0, jj, stars);
     }
+    Ok(Schematic{chars, numbers, symbols, number_symbols, symbol_numbers})
 }
 
-fn parse_input() -> Grid {
-    let input: &str = include_str!("../input.txt");
-    let mut char_vec: Vec<char>;
-    let mut data: Vec<Vec<char>> = Vec::new();
-    for line in input.lines() {
-        char_vec = line.chars().collect();
-        data.push(char_vec)
-    }
-    return Grid{chars: data, gears: HashMap::new()};
+#[test]
+fn test_example() {
+    let schematic = parse_input(include_str!("../example.txt")).unwrap();
+    assert_eq!(schematic.part_numbers().iter().map(|number| number.value).sum::<usize>(), 4361);
+    assert_eq!(schematic.sum_of_products('*', 2), Some(467835));
+    assert_eq!(schematic.numbers[0], PartNumber{value: 467, row: 0, col_span: 0..3});
+    assert_eq!(schematic.symbols_touching(0), vec![&Symbol{char: '*', pos: (1, 3)}]);
+    // 114 and 58 touch nothing
+    let loose: Vec<usize> = (0..schematic.numbers.len()).filter(|n| !schematic.is_part(*n)).map(|n| schematic.numbers[n].value).collect();
+    assert_eq!(loose, vec![114, 58]);
+    // The star next to 617 only has the one neighbour
+    assert_eq!(schematic.symbols_with_neighbours('*', 1).len(), 1);
+    assert_eq!(schematic.sum_of_products('#', 1), Some(633));
 }
 
-fn part1(){
-    let grid: Grid = parse_input();
-    let mut info: (usize, bool, usize, HashSet<(usize, usize)>);
-    let mut total: usize = 0;
-    for (i, row) in grid.chars.iter().enumerate() {
-        info = (0, false, 0, HashSet::new());
-        for (j, c) in row.iter().enumerate() {
-            if c.is_ascii_digit() && (j > info.2 || j == 0){
-                info = grid.get_number(i, j);
-                if info.1 {
-                    total += info.0;
-                }
-            }
-    }
+#[test]
+fn test_edges_and_highlight() {
+    // Numbers on the grid edges, and one touching two symbols
+    let schematic = parse_input("12.\n.*#\n..9").unwrap();
+    assert_eq!(schematic.numbers.len(), 2);
+    assert_eq!(schematic.symbols_touching(0).len(), 2);
+    assert_eq!(schematic.sum_of_products('*', 2), Some(108));
+    let highlighted = schematic.highlight();
+    assert!(highlighted.starts_with("\x1b[32m1\x1b[0m\x1b[32m2\x1b[0m."));
+    assert_eq!(highlighted.lines().count(), 3);
+}
+
+#[test]
+fn test_product_overflow() {
+    // Two nineteen digit gears multiply past any usize
+    let schematic = parse_input("9999999999999999999*9999999999999999999").unwrap();
+    assert_eq!(schematic.sum_of_products('*', 2), None);
+    // Each of these fits, but three of them add up past it
+    let gears = "3037000499*3037000499\n.....................\n".repeat(3);
+    assert_eq!(parse_input(&gears).unwrap().symbols_with_neighbours('*', 2).len(), 3);
+    assert_eq!(parse_input(&gears).unwrap().sum_of_products('*', 2), None);
+    assert!(parse_input(&gears[..88]).unwrap().sum_of_products('*', 2).is_some());
+    assert_eq!(parse_input("2.\n*3\n").unwrap().sum_of_products('*', 2), Some(6));
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day3_parse_input") {
+        assert!(parse_input(&input).is_err(), "{:?}", input);
     }
+}
+
+fn part1(){
+    let schematic: Schematic = parse_input("").unwrap();
+    let total: usize = schematic.part_numbers().iter().map(|number| number.value).sum();
     println!("Part1 Answer: {}", total);
 }
 
 fn part2() {
-    let mut grid: Grid = parse_input();
-    let mut info: (usize, bool, usize, HashSet<(usize, usize)>);
-    let mut total: usize = 0;
-    for (i, row) in grid.chars.iter().enumerate() {
-        info = (0, false, 0, HashSet::new());
-        for (j, c) in row.iter().enumerate() {
-            if c.is_ascii_digit() && (j > info.2 || j == 0){
-                info = grid.get_number(i, j);
-                for star in info.3 {
-                    grid.gears.entry(star).or_insert(Vec::new()).push(info.0);
-                }
-            }
-    }
+    let schematic: Schematic = parse_input("").unwrap();
+    match schematic.sum_of_products('*', 2) {
+        Some(total) => println!("Part2 Answer: {}", total),
+        None => println!("Part2 Answer: the gear ratios overflow"),
     }
-    for (star, nums) in grid.gears{
-        //dbg!(star, nums);
-        if nums.len() == 2 {
-            total += nums[0] * nums[1];
+}
+
+const USAGE: &str = "Usage: day3 [--highlight]";
+
+fn parse_args(args: &[String]) -> Result<bool, String> {
+    let mut highlight = false;
+    for arg in args {
+        match arg.as_str() {
+            "--highlight" => highlight = true,
+            _ => return Err(USAGE.to_string()),
         }
     }
-    println!("Part2 Answer: {}", total);
+    Ok(highlight)
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match parse_args(&args) {
+        Ok(true) => print!("{}", parse_input("").unwrap().highlight()),
+        Ok(false) => (),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
     part1();
     part2();
 }
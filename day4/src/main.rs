@@ -1,11 +1,15 @@
 use std::collections::HashSet;
-use std::collections::BTreeMap;
+use std::io::{IsTerminal, Read};
+use std::ops::RangeInclusive;
 use num_traits::pow;
 
+// The scratchcard table, with how many copies of each card end up being held.
+// Cards are stored in table order and their ids must run consecutively.
 #[derive(Clone, Debug)]
 struct Deck {
-    cards: BTreeMap<usize, Scratchcard>,
-    numbers: BTreeMap<usize, usize>,
+    cards: Vec<Scratchcard>,
+    copies: Vec<usize>,
+    overflows: Vec<Overflow>,
 }
 
 #[derive(Clone, Debug)]
@@ -15,6 +19,13 @@ struct Scratchcard {
     winning_numbers: HashSet<usize>,
 }
 
+// A card that would win copies of cards beyond the end of the table
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Overflow {
+    card: u32,
+    missing: RangeInclusive<u32>,
+}
+
 impl Scratchcard {
     fn get_num_wins(&self) -> usize {
         let player_score: HashSet<usize> = self.player_numbers.intersection(&self.winning_numbers).copied().collect();
@@ -32,83 +43,200 @@ impl Scratchcard {
 }
 
 impl Deck {
-    fn get_size(&mut self) -> usize {
-        self.evaluate();
-        self.numbers.values().sum()
-    }
-    fn evaluate(&mut self) {
-        let mut start_id: usize;
-        let mut end_id: usize;
-        let mut card_score: usize;
-        for (id_, card) in &self.cards {
-            card_score = card.get_num_wins();
-            start_id = id_ + 1;
-            end_id = id_ + card_score;
-            for i in start_id..=end_id {
-                *self.numbers.entry(i).or_insert(0) += self.numbers[id_];
+    // Copies only ever flow forward, so by the time a card is reached every copy
+    // of it has already been won and one pass settles the whole table.
+    fn new(cards: Vec<Scratchcard>) -> Result<Deck, String> {
+        for pair in cards.windows(2) {
+            if pair[0].number.checked_add(1) != Some(pair[1].number) {
+                return Err(format!("Card {} follows card {}", pair[1].number, pair[0].number));
+            }
+        }
+        let mut copies: Vec<usize> = vec![1; cards.len()];
+        let mut overflows: Vec<Overflow> = Vec::new();
+        for (i, card) in cards.iter().enumerate() {
+            let wins = card.get_num_wins();
+            let end = (i + wins).min(cards.len() - 1);
+            for j in i + 1..=end {
+                copies[j] = copies[j].checked_add(copies[i]).ok_or(format!("Too many copies of card {}", cards[j].number))?;
+            }
+            if i + wins > end {
+                let last = cards[end].number;
+                let missing_end = u32::try_from(wins).ok().and_then(|wins| card.number.checked_add(wins))
+                    .ok_or(format!("Card {} wins cards past the highest possible id", card.number))?;
+                overflows.push(Overflow{card: card.number, missing: last + 1..=missing_end});
             }
         }
+        Ok(Deck{cards, copies, overflows})
+    }
+    // None if there are too many cards to count
+    fn get_size(&self) -> Option<usize> {
+        self.copies.iter().try_fold(0usize, |size, copies| size.checked_add(*copies))
+    }
+    fn copies_of(&self, card: u32) -> Option<usize> {
+        let first = self.cards.first()?.number;
+        self.copies.get(card.checked_sub(first)? as usize).copied()
+    }
+    // The ids of the cards that one copy of `card` wins, clipped to the table
+    fn won_range(&self, card: u32) -> Option<RangeInclusive<u32>> {
+        let first = self.cards.first()?.number;
+        let scratchcard = self.cards.get(card.checked_sub(first)? as usize)?;
+        let last = self.cards[self.cards.len() - 1].number;
+        let end = u32::try_from(scratchcard.get_num_wins()).ok().and_then(|wins| card.checked_add(wins)).map_or(last, |end| end.min(last));
+        if end > card { Some(card + 1..=end) } else { None }
+    }
+    // How many copies of `card` are held, and which cards each copy wins
+    fn describe(&self, card: u32) -> Result<String, String> {
+        let copies = self.copies_of(card).ok_or(format!("There is no card {}", card))?;
+        Ok(match self.won_range(card) {
+            Some(range) => format!("Card {}: {} copies, each winning cards {} to {}", card, copies, range.start(), range.end()),
+            None => format!("Card {}: {} copies, winning no cards", card, copies),
+        })
     }
 }
 
 
-fn get_input_lines() -> Vec<&'static str>{
-    let input: &str = include_str!("../example.txt");
-    let mut data: Vec<&str> = Vec::new();
-    for line in input.lines() {
-        data.push(line)
-    }
-    data
-}
-
-fn parse_line(line: &str) -> Scratchcard {
-    let mut player_nums: HashSet<usize> = HashSet::new();
-    let mut winner_nums: HashSet<usize> = HashSet::new();
-    let blocks1: Vec<&str> = line.split(':').collect();
-    let id_: u32 = blocks1[0].replace("Card", "").trim().parse::<u32>().unwrap();
-    let string_numbers: Vec<&str> = blocks1[1].split('|').collect();
-    let winning_number_strings: Vec<&str> = string_numbers[0].split(' ').collect();
-    let player_number_strings: Vec<&str> = string_numbers[1].split(' ').collect();
-    for win in winning_number_strings {
-        if !win.is_empty() {
-            winner_nums.insert(win.parse::<usize>().unwrap());
-        }
-        
+fn parse_numbers(string: &str) -> Result<HashSet<usize>, String> {
+    string.split_whitespace()
+        .map(|number| number.parse::<usize>().map_err(|_| format!("Invalid number '{}'", number)))
+        .collect()
+}
+
+fn parse_line(line: &str) -> Result<Scratchcard, String> {
+    let (label, numbers) = line.split_once(':').ok_or(format!("Missing ':' in '{}'", line))?;
+    let id_: u32 = match label.strip_prefix("Card").map(|id| id.trim().parse::<u32>()) {
+        Some(Ok(id)) => id,
+        _ => return Err(format!("Invalid card label '{}'", label)),
+    };
+    let (winning, player) = numbers.split_once('|').ok_or(format!("Missing '|' in '{}'", line))?;
+    Ok(Scratchcard { number: id_, player_numbers: parse_numbers(player)?, winning_numbers: parse_numbers(winning)? })
+}
+
+fn parse_input(string: &str) -> Result<Vec<Scratchcard>, String> {
+    string.lines().filter(|line| !line.trim().is_empty()).map(parse_line).collect()
+}
+
+#[test]
+fn test_example() {
+    let cards = parse_input(include_str!("../example.txt")).unwrap();
+    assert_eq!(cards.iter().map(|card| card.get_score()).sum::<usize>(), 13);
+    let deck = Deck::new(cards).unwrap();
+    assert_eq!(deck.get_size(), Some(30));
+    assert_eq!(deck.copies, vec![1, 2, 4, 8, 14, 1]);
+    assert_eq!(deck.copies_of(5), Some(14));
+    assert_eq!(deck.copies_of(7), None);
+    assert_eq!(deck.won_range(1), Some(2..=5));
+    assert_eq!(deck.won_range(4), Some(5..=5));
+    assert_eq!(deck.won_range(6), None);
+    assert!(deck.overflows.is_empty());
+    assert_eq!(deck.describe(3), Ok("Card 3: 4 copies, each winning cards 4 to 5".to_string()));
+    assert_eq!(deck.describe(6), Ok("Card 6: 1 copies, winning no cards".to_string()));
+    assert!(deck.describe(0).is_err());
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |args: &[&str]| parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+    assert_eq!(args(&[]), Ok((None, None)));
+    assert_eq!(args(&["cards.txt", "--card", "4"]), Ok((Some("cards.txt".to_string()), Some(4))));
+    assert!(args(&["--card", "four"]).is_err());
+    assert!(args(&["a.txt", "b.txt"]).is_err());
+    assert!(args(&["--cards"]).is_err());
+}
+
+#[test]
+fn test_overflow_and_errors() {
+    let cards = parse_input("Card 3: 1 2 | 3\nCard 4: 1 2 3 | 1 2 3 4").unwrap();
+    let deck = Deck::new(cards).unwrap();
+    assert_eq!(deck.copies, vec![1, 1]);
+    assert_eq!(deck.won_range(4), None);
+    assert_eq!(deck.overflows, vec![Overflow{card: 4, missing: 5..=7}]);
+
+    assert!(parse_line("Card 1: 1 2 3").is_err());
+    assert!(parse_line("Card x: 1 | 2").is_err());
+    assert!(parse_line("Card 1: 1 | two").is_err());
+    assert!(Deck::new(parse_input("Card 1: 1 | 2\nCard 3: 1 | 2").unwrap()).is_err());
+}
+
+#[test]
+fn test_large_ids() {
+    // A win past the last possible id can't be reported as a range of ids
+    let last = format!("Card {}: 1 | 1", u32::MAX);
+    assert!(Deck::new(parse_input(&last).unwrap()).is_err());
+    let deck = Deck::new(parse_input(&format!("Card {}: 1 | 1\nCard {}: 1 | 2", u32::MAX - 1, u32::MAX)).unwrap()).unwrap();
+    assert_eq!(deck.won_range(u32::MAX - 1), Some(u32::MAX..=u32::MAX));
+    assert_eq!(deck.get_size(), Some(3));
+    assert!(Deck::new(parse_input(&format!("{}\nCard 0: 1 | 2", last)).unwrap()).is_err());
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day4_parse_line") {
+        assert!(parse_line(&input).is_err(), "{:?}", input);
     }
-    
-    for play in player_number_strings {
-        if !play.is_empty() {
-            player_nums.insert(play.parse::<usize>().unwrap());
+}
+
+const USAGE: &str = "Usage: day4 [path] [--card <id>]";
+
+// The input file, if one is named, and the card to describe, if any
+fn parse_args(args: &[String]) -> Result<(Option<String>, Option<u32>), String> {
+    let (mut path, mut card) = (None, None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--card" => {
+                let id = args.next().ok_or(USAGE)?;
+                card = Some(id.parse::<u32>().map_err(|_| format!("'{}' is not a card id", id))?);
+            }
+            arg if arg.starts_with("--") || path.is_some() => return Err(USAGE.to_string()),
+            arg => path = Some(arg.to_string()),
         }
     }
-
-    Scratchcard { number: id_, player_numbers: player_nums, winning_numbers: winner_nums }
+    Ok((path, card))
 }
 
-fn part1() {
-    let data = get_input_lines();
-    let mut total: usize = 0;
-    let mut card: Scratchcard;
-    for line in data {
-        card = parse_line(line);
-        total += card.get_score();
+// The table from the named file, or from stdin when something is piped in, or
+// else the puzzle input
+fn read_input(path: Option<&str>) -> Result<String, String> {
+    match path {
+        Some(path) => std::fs::read_to_string(path).map_err(|error| format!("Could not read '{}': {}", path, error)),
+        None if std::io::stdin().is_terminal() => Ok(include_str!("../input.txt").to_string()),
+        None => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input).map_err(|error| format!("Could not read stdin: {}", error))?;
+            Ok(input)
+        },
     }
+}
+
+fn part1(cards: &[Scratchcard]) {
+    let total: usize = cards.iter().map(|card| card.get_score()).sum();
     println!("Part1 Answer: {}", total)
 }
 
-fn part2() {
-    let data = get_input_lines();
-    let mut card: Scratchcard;
-    let mut deck: Deck = Deck { cards: BTreeMap::new(), numbers: BTreeMap::new() };
-    for line in data {
-        card = parse_line(line);
-        deck.cards.insert(card.number as usize, card.clone());
-        deck.numbers.insert(card.number as usize, 1);
+fn part2(cards: Vec<Scratchcard>, card: Option<u32>) -> Result<(), String> {
+    let deck: Deck = Deck::new(cards)?;
+    if let Some(card) = card {
+        println!("{}", deck.describe(card)?);
+    }
+    for overflow in &deck.overflows {
+        println!("Card {} would win cards {:?}, past the end of the table", overflow.card, overflow.missing);
     }
-    println!("Part2 Answer: {}", deck.get_size())
+    match deck.get_size() {
+        Some(size) => println!("Part2 Answer: {}", size),
+        None => println!("Part2: there are too many cards to count"),
+    }
+    Ok(())
 }
 
 fn main() {
-    part1();
-    part2();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = parse_args(&args).and_then(|(path, card)| {
+        let cards = parse_input(&read_input(path.as_deref())?)?;
+        part1(&cards);
+        part2(cards, card)
+    });
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
 }
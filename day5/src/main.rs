@@ -1,163 +1,235 @@
 use itertools::izip;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-enum Overlap {
-    PartialLeft,
-    PartialRight,
-    PartialMiddle,
-    Total,
-    None,
+#[derive(Clone, Debug)]
+struct AlmanacMap {
+    in_name: String,
+    out_name: String,
+    in_nums: Vec<usize>,
+    out_nums: Vec<usize>,
+    numbers: Vec<usize>,
 }
 
-#[derive(Clone, Debug)]
-struct Range2 {
-    start: usize,
-    size: usize,
+// Everything past the last range maps to itself, up to here
+const DOMAIN_END: i64 = 1 << 62;
+
+// A half-open interval [start, end)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Interval {
+    start: i64,
+    end: i64,
 }
 
-impl Range2 {
-    fn overlap(&self, other: Range2) -> Overlap{
-        let a0: usize = self.start;
-        let a1: usize = self.start + self.size - 1;
-        let b0: usize = other.start;
-        let b1: usize = other.start + other.size - 1;
-        // dbg!(a0, a1, b0, b1);
-        if a0 > b1 || b0 > a1 {
-            return Overlap::None;
-        }
-        else if a0 <= b0 && a1 < b1 {
-            return Overlap::PartialLeft;
-        }
-        else if a0 > b0 && a1 >= b1 {
-            return  Overlap::PartialRight;
+impl Interval {
+    fn intersect(&self, other: &Interval) -> Option<Interval> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        if start < end { Some(Interval{start, end}) } else { None }
+    }
+    fn shift(&self, offset: i64) -> Interval {
+        Interval{start: self.start + offset, end: self.end + offset}
+    }
+}
+
+// Every value in `source` maps to itself plus `offset`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Piece {
+    source: Interval,
+    offset: i64,
+}
+
+impl Piece {
+    fn image(&self) -> Interval {
+        self.source.shift(self.offset)
+    }
+}
+
+// A map from [0, DOMAIN_END) onto numbers, as sorted pieces that cover the whole
+// domain without overlapping. Neighbouring pieces never share an offset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct PiecewiseMap {
+    pieces: Vec<Piece>,
+}
+
+impl PiecewiseMap {
+    fn identity() -> PiecewiseMap {
+        PiecewiseMap{pieces: vec![Piece{source: Interval{start: 0, end: DOMAIN_END}, offset: 0}]}
+    }
+    // Sorts the ranges, drops any part of a range already covered by an earlier
+    // one and fills the gaps between them with the identity.
+    fn from_almanac(almanac: &AlmanacMap) -> PiecewiseMap {
+        let mut ranges: Vec<Piece> = Vec::new();
+        for (in_num, out_num, number) in izip!(&almanac.in_nums, &almanac.out_nums, &almanac.numbers) {
+            let source = Interval{start: *in_num as i64, end: (*in_num + *number) as i64};
+            ranges.push(Piece{source, offset: *out_num as i64 - *in_num as i64});
         }
-        else if a0 > b0 && a1 < b1 {
-            return Overlap::PartialMiddle;
+        ranges.sort_by_key(|piece| piece.source.start);
+        let mut pieces: Vec<Piece> = Vec::new();
+        let mut covered: i64 = 0;
+        for mut piece in ranges {
+            piece.source.start = piece.source.start.max(covered);
+            if piece.source.start >= piece.source.end {
+                continue;
+            }
+            if piece.source.start > covered {
+                pieces.push(Piece{source: Interval{start: covered, end: piece.source.start}, offset: 0});
+            }
+            covered = piece.source.end;
+            pieces.push(piece);
         }
-        else if a0 <= b0 && a1 >= b1 {
-            return Overlap::Total;
+        if covered < DOMAIN_END {
+            pieces.push(Piece{source: Interval{start: covered, end: DOMAIN_END}, offset: 0});
         }
-        else {
-            unreachable!();
+        PiecewiseMap::normalised(pieces)
+    }
+    fn normalised(pieces: Vec<Piece>) -> PiecewiseMap {
+        let mut merged: Vec<Piece> = Vec::new();
+        for piece in pieces {
+            match merged.last_mut() {
+                Some(last) if last.offset == piece.offset && last.source.end == piece.source.start => {
+                    last.source.end = piece.source.end;
+                },
+                _ => merged.push(piece),
+            }
         }
+        PiecewiseMap{pieces: merged}
     }
-    fn map_to_partial_left(&self, other: Range2, out: Range2) -> Vec<Range2> {
-        let a0: usize = self.start;
-        let a1: usize = self.start + self.size;
-        let b0: usize = other.start;
-        let b1: usize = other.start + other.size;
-        let trunc_range: Range2 = Range2 { start: a1, size: b1-a1 };
-        let mapped_range: Range2 = Range2 { start: b0-a0+out.start, size: a1-b0 };
-        assert_eq!(trunc_range.size+mapped_range.size, other.size);
-        return vec![trunc_range, mapped_range];
+    fn piece_for(&self, value: i64) -> &Piece {
+        let index = self.pieces.partition_point(|piece| piece.source.end <= value);
+        &self.pieces[index]
     }
-    fn map_to_partial_right(&self, other: Range2, out: Range2) -> Vec<Range2> {
-        let a0: usize = self.start;
-        let b0: usize = other.start;
-        let b1: usize = other.start + other.size;
-        let trunc_range: Range2 = Range2 { start: b0, size: a0-b0 };
-        let mapped_range: Range2 = Range2 { start: out.start, size: b1-a0 };
-        assert_eq!(trunc_range.size+mapped_range.size, other.size);
-        return vec![trunc_range, mapped_range];
+    fn apply(&self, value: i64) -> i64 {
+        value + self.piece_for(value).offset
     }
-    fn map_to_partial_middle(&self, other: Range2, out: Range2) -> Vec<Range2> {
-        let a0: usize = self.start;
-        let a1: usize = self.start + self.size;
-        let b0: usize = other.start;
-        let b1: usize = other.start + other.size;
-        let trunc_range1: Range2 = Range2 { start: b0, size: a0-b0 };
-        let trunc_range2: Range2 = Range2 { start: a1, size: b1-a1 };
-        let mapped_range: Range2 = Range2 { start: out.start, size: out.size };
-        assert_eq!(trunc_range1.size+trunc_range2.size+mapped_range.size, other.size);
-        return vec![trunc_range1, trunc_range2, mapped_range];
+    // This map followed by `next`
+    fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut pieces: Vec<Piece> = Vec::new();
+        for piece in &self.pieces {
+            let image = piece.image();
+            let first = next.pieces.partition_point(|other| other.source.end <= image.start);
+            for other in &next.pieces[first..] {
+                match other.source.intersect(&image) {
+                    Some(overlap) => pieces.push(Piece{source: overlap.shift(-piece.offset), offset: piece.offset + other.offset}),
+                    None => break,
+                }
+            }
+        }
+        PiecewiseMap::normalised(pieces)
     }
-    fn map_to_total(&self, other: Range2, out: Range2) -> Vec<Range2> {
-        let a0: usize = self.start;
-        let b0: usize = other.start;
-        let mapped_range: Range2 = Range2 { start: b0-a0+out.start, size: other.size };
-        assert_eq!(mapped_range.size, other.size);
-        return vec![mapped_range];
+    // Each piece is increasing, so the lowest output from a range is at the
+    // start of one of the pieces it overlaps.
+    fn lowest_image(&self, ranges: &[Interval]) -> Option<i64> {
+        let mut lowest: Option<i64> = None;
+        for range in ranges {
+            let first = self.pieces.partition_point(|piece| piece.source.end <= range.start);
+            for piece in &self.pieces[first..] {
+                match piece.source.intersect(range) {
+                    Some(overlap) => {
+                        let value = overlap.start + piece.offset;
+                        lowest = Some(lowest.map_or(value, |l| l.min(value)));
+                    },
+                    None => break,
+                }
+            }
+        }
+        lowest
+    }
+    fn inverse(&self) -> InverseMap {
+        let mut pieces: Vec<Piece> = self.pieces.iter().map(|piece| Piece{source: piece.image(), offset: -piece.offset}).collect();
+        pieces.sort_by_key(|piece| (piece.source.start, piece.source.end));
+        InverseMap{pieces}
     }
 }
 
-#[derive(Clone, Debug)]
-struct MultiRange {
-    ranges: Vec<Range2>,
+
+// A PiecewiseMap swapped round, as pieces sorted by image. Images can overlap,
+// so more than one input can share an output, and some outputs have none.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct InverseMap {
+    pieces: Vec<Piece>,
 }
 
-#[derive(Clone, Debug)]
-struct AlmanacMap2 {
-    in_name: String,
-    out_name: String,
-    in_range: MultiRange,
-    out_range: MultiRange,
+impl InverseMap {
+    // Every input that maps to `value`, lowest first
+    fn apply(&self, value: i64) -> Vec<i64> {
+        let mut inputs: Vec<i64> = Vec::new();
+        for piece in &self.pieces {
+            if piece.source.start > value {
+                break;
+            }
+            if value < piece.source.end {
+                inputs.push(value + piece.offset);
+            }
+        }
+        inputs.sort();
+        inputs
+    }
 }
 
+
+// The categories and the maps between them, from the block headers. A category
+// can map to more than one other category.
 #[derive(Clone, Debug)]
-struct AlmanacMap {
-    in_name: String,
-    out_name: String,
-    in_nums: Vec<usize>,
-    out_nums: Vec<usize>,
-    numbers: Vec<usize>,
+struct Almanac {
+    maps: HashMap<String, Vec<(String, PiecewiseMap)>>,
 }
 
-impl MultiRange {
-    fn get_lowest_value(&self) -> usize {
-        let mut value: usize = usize::MAX;
-        // if we filter out values equal to zero it works for the input
-        // if not it only works for the example. Why??
-        for range2 in self.ranges.clone() {
-            value = value.min(range2.start);
+impl Almanac {
+    fn new(almanac_maps: &[AlmanacMap]) -> Almanac {
+        let mut maps: HashMap<String, Vec<(String, PiecewiseMap)>> = HashMap::new();
+        for almanac in almanac_maps {
+            maps.entry(almanac.in_name.clone()).or_default().push((almanac.out_name.clone(), PiecewiseMap::from_almanac(almanac)));
         }
-        value
+        Almanac{maps}
     }
-}
-
-impl AlmanacMap {
-    fn map_to(&self, num: usize) -> usize {
-        let mut upper_range: usize;
-        for (in_num, out_num, number) in izip!(&self.in_nums, &self.out_nums, &self.numbers) {
-            upper_range = in_num+number;
-            if &num >= in_num && num <= upper_range {
-                return  num - in_num + out_num;
+    fn get_map(&self, from: &str, to: &str) -> Option<&PiecewiseMap> {
+        self.maps.get(from)?.iter().find(|(name, _)| name == to).map(|(_, map)| map)
+    }
+    // Shortest chain of categories from one to the other, both included
+    fn path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut previous: HashMap<String, String> = HashMap::new();
+        let mut queue: VecDeque<String> = VecDeque::from([from.to_string()]);
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path: Vec<String> = vec![current];
+                while let Some(before) = previous.get(&path[path.len() - 1]) {
+                    path.push(before.clone());
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (next, _) in self.maps.get(&current).into_iter().flatten() {
+                if next != from && !previous.contains_key(next) {
+                    previous.insert(next.clone(), current.clone());
+                    queue.push_back(next.clone());
+                }
             }
         }
-        return num
+        None
     }
-    fn to_almanac2(&self) -> AlmanacMap2{
-        let mut in_ranges: Vec<Range2> = Vec::new();
-        let mut out_ranges: Vec<Range2> = Vec::new();
-        for (in_num, out_num, number) in izip!(&self.in_nums, &self.out_nums, &self.numbers) {
-            in_ranges.push(Range2{start: in_num.clone(), size: number.clone()});
-            out_ranges.push(Range2{start: out_num.clone(), size: number.clone()});
+    // The maps along the path between two categories, composed into one
+    fn compose(&self, from: &str, to: &str) -> Option<PiecewiseMap> {
+        let path = self.path(from, to)?;
+        let mut composed = PiecewiseMap::identity();
+        for pair in path.windows(2) {
+            composed = composed.then(self.get_map(&pair[0], &pair[1])?);
         }
-        return AlmanacMap2{in_name: self.in_name.clone(), out_name: self.out_name.clone(), in_range: MultiRange { ranges: in_ranges }, out_range: MultiRange { ranges: out_ranges }};
+        Some(composed)
     }
-    
-}
-
-impl AlmanacMap2 {
-    fn map_ranges_to_ranges(&self, ranges: MultiRange) -> MultiRange{
-        let mut new_ranges: Vec<Range2> = Vec::new();
-        let mut overlap: Overlap;
-        for range2 in ranges.ranges.clone(){
-            for (in_range, out_range) in izip!(self.in_range.ranges.clone(), self.out_range.ranges.clone()) {
-                overlap = in_range.overlap(range2.clone());
-                match overlap {
-                    Overlap::Total => new_ranges.append(&mut in_range.map_to_total(range2.clone(), out_range)),
-                    Overlap::PartialLeft => new_ranges.append(&mut in_range.map_to_partial_left(range2.clone(), out_range)),
-                    Overlap::PartialRight => new_ranges.append(&mut in_range.map_to_partial_right(range2.clone(), out_range)),
-                    Overlap::PartialMiddle => new_ranges.append(&mut in_range.map_to_partial_middle(range2.clone(), out_range)),
-                    Overlap::None => (),
+    // The value in every category reachable from `from`, nearest categories first
+    fn trace(&self, from: &str, value: i64) -> Vec<(String, i64)> {
+        let mut visited: Vec<(String, i64)> = vec![(from.to_string(), value)];
+        let mut index: usize = 0;
+        while index < visited.len() {
+            let (current, current_value) = visited[index].clone();
+            for (next, map) in self.maps.get(&current).into_iter().flatten() {
+                if !visited.iter().any(|(name, _)| name == next) {
+                    visited.push((next.clone(), map.apply(current_value)));
                 }
             }
-            if new_ranges.len() == 0 {
-                new_ranges.push(range2);
-            }
+            index += 1;
         }
-        MultiRange { ranges: new_ranges }
+        visited
     }
 }
 
@@ -171,15 +243,17 @@ fn get_input_lines() -> Vec<&'static str>{
     data
 }
 
-fn parse_input() -> (Vec<usize>, HashMap<String, AlmanacMap>){
-    let lines = get_input_lines();
-    let seed_line = lines[0].replace("seeds: ", "");
-    let mut seeds: Vec<usize> = Vec::new();
+fn parse_input() -> (Vec<usize>, Vec<AlmanacMap>){
+    parse_lines(get_input_lines()).unwrap()
+}
+
+fn parse_lines(lines: Vec<&str>) -> Result<(Vec<usize>, Vec<AlmanacMap>), String>{
+    let first = lines.first().ok_or("The almanac is empty")?;
+    let seed_line = first.strip_prefix("seeds:").ok_or(format!("Expected a list of seeds, found '{}'", first))?;
     let mut blocks: Vec<Vec<&str>> = Vec::new();
     let mut block: Vec<&str> = Vec::new();
-    let mut almanac_maps: HashMap<String, AlmanacMap> = HashMap::new();
-    let mut almanac: AlmanacMap;
-    for line in lines[2..].into_iter(){
+    let mut almanac_maps: Vec<AlmanacMap> = Vec::new();
+    for line in lines.iter().skip(2){
         if line.is_empty(){
             blocks.push(block);
             block = Vec::new();
@@ -190,229 +264,196 @@ fn parse_input() -> (Vec<usize>, HashMap<String, AlmanacMap>){
     }
     blocks.push(block); 
     for block in blocks {
-        almanac = parse_block(block);
-        almanac_maps.insert(almanac.in_name.clone(), almanac);
-
-    }
-    for seed_string in seed_line.split(" ") {
-        seeds.push(seed_string.parse::<usize>().unwrap());
+        almanac_maps.push(parse_block(block)?);
     }
-    return (seeds, almanac_maps);
+    let seeds: Vec<usize> = parse_numbers(seed_line)?;
+    Ok((seeds, almanac_maps))
 }
 
-fn get_seed_ranges(seeds: Vec<usize>) -> MultiRange {
-    let mut ranges: Vec<Range2> = Vec::new();
-    for pair in seeds.chunks(2){
-        let single_range: Range2 = Range2 { start: pair[0], size: pair[1] };
-        ranges.push(single_range);
+// Seeds have to sit inside the domain like the map ranges do
+fn seed_interval(start: usize, length: usize) -> Result<Interval, String> {
+    match start.checked_add(length) {
+        Some(end) if end <= DOMAIN_END as usize => Ok(Interval{start: start as i64, end: end as i64}),
+        _ => Err(format!("Seeds {} to {} run past the end of the domain", start, start as u128 + length as u128)),
     }
-    MultiRange { ranges }
 }
 
-fn parse_block(block: Vec<&str>) -> AlmanacMap{
-    let binding = block[0].replace(" map:", "");
-    let names: Vec<&str> = binding.split("-to-").collect();
-    let mut numbers: Vec<&str>;
-    let mut in_number: Vec<usize> = Vec::new();
-    let mut out_number: Vec<usize> = Vec::new();
-    let mut range: Vec<usize> = Vec::new();
-    for line in block[1..].into_iter() {
-        numbers = line.split(" ").collect();
-        out_number.push(numbers[0].parse::<usize>().unwrap());
-        in_number.push(numbers[1].parse::<usize>().unwrap());
-        range.push(numbers[2].parse::<usize>().unwrap());
+fn get_seed_ranges(seeds: &[usize]) -> Result<Vec<Interval>, String> {
+    if !seeds.len().is_multiple_of(2) {
+        return Err(format!("Seed ranges come in pairs, but there are {} numbers", seeds.len()));
     }
-    return AlmanacMap{in_name: names[0].to_string(), out_name: names[1].to_string(), out_nums: out_number, in_nums: in_number, numbers: range}
+    seeds.chunks(2).map(|pair| seed_interval(pair[0], pair[1])).collect()
 }
 
-fn find_lowest_location_number(seeds: Vec<usize>, almanacs: HashMap<String, AlmanacMap>) -> usize {
-    let mut start: String = "seed".to_string();
-    let mut next: usize;
-    let mut lowest: usize = usize::MAX;
-    for seed in seeds {
-        next = seed;
-        while almanacs.contains_key(&start){
-            next = almanacs[&start].map_to(next);
-            start = almanacs[&start].out_name.clone();
-        }
-        start = "seed".to_string();
-        lowest = lowest.min(next);
-    }
-    lowest
+fn get_single_seeds(seeds: &[usize]) -> Result<Vec<Interval>, String> {
+    seeds.iter().map(|seed| seed_interval(*seed, 1)).collect()
+}
+
+fn parse_numbers(string: &str) -> Result<Vec<usize>, String> {
+    string.split_whitespace()
+        .map(|number| number.parse::<usize>().map_err(|_| format!("Invalid number '{}'", number)))
+        .collect()
 }
 
-fn convert_almanacs_to_ranges(almanacs: HashMap<String, AlmanacMap>) ->  HashMap<String, AlmanacMap2>{
-    let mut new_almanacs: HashMap<String, AlmanacMap2> = HashMap::new();
-    for (k, v) in almanacs {
-            new_almanacs.insert(k, v.to_almanac2());
+// A "<from>-to-<to> map:" header, then a destination, source and length on each
+// line. Both ends of every range have to sit inside the domain.
+fn parse_block(block: Vec<&str>) -> Result<AlmanacMap, String>{
+    let header = block.first().ok_or("Empty map")?;
+    let names = header.strip_suffix(" map:").and_then(|names| names.split_once("-to-"));
+    let (in_name, out_name) = match names {
+        Some((in_name, out_name)) if !in_name.is_empty() && !out_name.is_empty() => (in_name, out_name),
+        _ => return Err(format!("Invalid map header '{}'", header)),
+    };
+    let mut in_number: Vec<usize> = Vec::new();
+    let mut out_number: Vec<usize> = Vec::new();
+    let mut range: Vec<usize> = Vec::new();
+    for line in block[1..].iter() {
+        let numbers = parse_numbers(line)?;
+        if numbers.len() != 3 {
+            return Err(format!("Expected a destination, source and length, found '{}'", line));
+        }
+        let fits = |start: usize| start.checked_add(numbers[2]).is_some_and(|end| end <= DOMAIN_END as usize);
+        if !fits(numbers[0]) || !fits(numbers[1]) {
+            return Err(format!("'{}' runs past the end of the domain", line));
+        }
+        out_number.push(numbers[0]);
+        in_number.push(numbers[1]);
+        range.push(numbers[2]);
     }
-    return new_almanacs;
+    Ok(AlmanacMap{in_name: in_name.to_string(), out_name: out_name.to_string(), out_nums: out_number, in_nums: in_number, numbers: range})
 }
 
-fn find_lowest_location_number2(seeds: Vec<usize>, almanacs: HashMap<String, AlmanacMap>) -> usize {
-    let almanacs2: HashMap<String, AlmanacMap2> = convert_almanacs_to_ranges(almanacs);
-    let mut start: String = "seed".to_string();
-    let seed_ranges: MultiRange = get_seed_ranges(seeds);
-    let mut ranges: MultiRange = seed_ranges;
-    while almanacs2.contains_key(&start){
-        ranges = almanacs2[&start].map_ranges_to_ranges(ranges);
-        start = almanacs2[&start].out_name.clone();
+fn print_lowest_location(part: usize, seed_to_location: &PiecewiseMap, seeds: Result<Vec<Interval>, String>) {
+    match seeds.map(|seeds| seed_to_location.lowest_image(&seeds)) {
+        Ok(Some(location)) => println!("Part{} Answer: {}", part, location),
+        Ok(None) => println!("Part{}: there are no seeds", part),
+        Err(error) => println!("Part{}: {}", part, error),
     }
-    ranges.get_lowest_value()
 }
 
 fn part1() {
-    let data: (Vec<usize>, HashMap<String, AlmanacMap>) = parse_input();
-    println!("Part1 Answer: {}", find_lowest_location_number(data.0, data.1));
+    let (seeds, almanac_maps) = parse_input();
+    let seed_to_location = Almanac::new(&almanac_maps).compose("seed", "location").unwrap();
+    print_lowest_location(1, &seed_to_location, get_single_seeds(&seeds));
 }
 
 fn part2() {
-    let data: (Vec<usize>, HashMap<String, AlmanacMap>) = parse_input();
-    println!("Part2 Answer: {}", find_lowest_location_number2(data.0, data.1));
+    let (seeds, almanac_maps) = parse_input();
+    let seed_to_location = Almanac::new(&almanac_maps).compose("seed", "location").unwrap();
+    print_lowest_location(2, &seed_to_location, get_seed_ranges(&seeds));
 }
 
-#[test]
-pub fn test_partial_middle() {
-    let a: Range2 = Range2 { start: 41, size: 3 };
-    let b: Range2 = Range2 { start: 40, size: 6 };
-    let out: Range2 = Range2 { start: 90, size: 3 };
-    let results: Vec<Range2>;
-    results = a.map_to_partial_middle(b, out);
-    assert_eq!(results.len(), 3);
-    assert_eq!(results[0].start, 40);
-    assert_eq!(results[0].size, 1);
-    assert_eq!(results[1].start, 44);
-    assert_eq!(results[1].size, 2);
-    assert_eq!(results[2].start, 90);
-    assert_eq!(results[2].size, 3);
+// The categories a seed passes through, and the seeds that end up at a location
+fn query(argument: &str, value: &str) -> Result<(), String> {
+    let value: i64 = value.parse().ok().filter(|value| (0..DOMAIN_END).contains(value)).ok_or(format!("Invalid number '{}'", value))?;
+    let (_, almanac_maps) = parse_input();
+    let almanac = Almanac::new(&almanac_maps);
+    match argument {
+        "--trace" => {
+            for (category, number) in almanac.trace("seed", value) {
+                println!("{} {}", category, number);
+            }
+        },
+        "--seeds-for" => {
+            let seed_to_location = almanac.compose("seed", "location").ok_or("No seed to location path")?;
+            println!("{:?}", seed_to_location.inverse().apply(value));
+        },
+        _ => return Err(format!("Unknown argument '{}'", argument)),
+    }
+    Ok(())
 }
 
 #[test]
-pub fn test_partial_right() {
-    let a: Range2 = Range2 { start: 41, size: 2 };
-    let b: Range2 = Range2 { start: 40, size: 3 };
-    let out: Range2 = Range2 { start: 90, size: 2 };
-    let results: Vec<Range2>;
-    results = a.map_to_partial_right(b, out);
-    assert_eq!(results.len(), 2);
-    assert_eq!(results[0].start, 40);
-    assert_eq!(results[0].size, 1);
-    assert_eq!(results[1].start, 90);
-    assert_eq!(results[1].size, 2);
+pub fn test_example_queries() {
+    let (seeds, almanac_maps) = parse_lines(include_str!("../example.txt").lines().collect()).unwrap();
+    let almanac = Almanac::new(&almanac_maps);
+    let seed_to_location = almanac.compose("seed", "location").unwrap();
+    assert_eq!(seed_to_location.lowest_image(&get_single_seeds(&seeds).unwrap()), Some(35));
+    assert_eq!(seed_to_location.lowest_image(&get_seed_ranges(&seeds).unwrap()), Some(46));
+    let locations: Vec<i64> = [79, 14, 55, 13].iter().map(|seed| seed_to_location.apply(*seed)).collect();
+    assert_eq!(locations, vec![82, 43, 86, 35]);
+    let location_to_seed = seed_to_location.inverse();
+    assert!(location_to_seed.apply(35).contains(&13));
+    for location in location_to_seed.apply(46) {
+        assert_eq!(seed_to_location.apply(location), 46);
+    }
+    let trace: Vec<i64> = almanac.trace("seed", 79).iter().map(|(_, value)| *value).collect();
+    assert_eq!(trace, vec![79, 81, 81, 81, 74, 78, 78, 82]);
+    assert_eq!(almanac.path("seed", "location").unwrap().len(), 8);
+    assert!(almanac.compose("location", "seed").is_none());
 }
 
 #[test]
-pub fn test_partial_right_zeros1() {
-    let a: Range2 = Range2 { start: 41, size: 2 };
-    let b: Range2 = Range2 { start: 40, size: 3 };
-    let out: Range2 = Range2 { start: 0, size: 2 };
-    let results: Vec<Range2>;
-    results = a.map_to_partial_right(b, out);
-    assert_eq!(results.len(), 2);
-    assert_eq!(results[0].start, 40);
-    assert_eq!(results[0].size, 1);
-    assert_eq!(results[1].start, 0);
-    assert_eq!(results[1].size, 2);
+pub fn test_composed_map_matches_chain() {
+    let (_, almanac_maps) = parse_lines(include_str!("../example.txt").lines().collect()).unwrap();
+    let almanac = Almanac::new(&almanac_maps);
+    let seed_to_location = almanac.compose("seed", "location").unwrap();
+    for seed in 0..200 {
+        let chained = almanac.trace("seed", seed).last().unwrap().1;
+        assert_eq!(seed_to_location.apply(seed), chained);
+    }
+    // Pieces stay sorted and cover the domain with no gaps
+    for pair in seed_to_location.pieces.windows(2) {
+        assert_eq!(pair[0].source.end, pair[1].source.start);
+        assert_ne!(pair[0].offset, pair[1].offset);
+    }
 }
 
 #[test]
-pub fn test_partial_left_one_value() {
-    let a: Range2 = Range2 { start: 3, size: 3 };
-    let b: Range2 = Range2 { start: 5, size: 3 };
-    let out: Range2 = Range2 { start: 0, size: 3 };
-    let results: Vec<Range2>;
-    results = a.map_to_partial_left(b, out);
-    assert_eq!(results.len(), 2);
-    assert_eq!(results[0].start, 6);
-    assert_eq!(results[0].size, 2);
-    assert_eq!(results[1].start, 2);
-    assert_eq!(results[1].size, 1);
-}
+pub fn test_branching_categories() {
+    let input = "seeds: 1 2
 
-#[test]
-pub fn test_partial_left() {
-    let a: Range2 = Range2 { start: 41, size: 5 };
-    let b: Range2 = Range2 { start: 42, size: 10 };
-    let out: Range2 = Range2 { start: 90, size: 5 };
-    let results: Vec<Range2>;
-    results = a.map_to_partial_left(b, out);
-    assert_eq!(results.len(), 2);
-    assert_eq!(results[0].start, 46);
-    assert_eq!(results[0].size, 6);
-    assert_eq!(results[1].start, 91);
-    assert_eq!(results[1].size, 4);
-}
+seed-to-soil map:
+10 0 5
 
-#[test]
-pub fn test_total2() {
-    let a: Range2 = Range2 { start: 0, size: 10 };
-    let b: Range2 = Range2 { start: 0, size: 3 };
-    let out: Range2 = Range2 { start: 90, size: 10 };
-    let results: Vec<Range2>;
-    results = a.map_to_total(b, out);
-    assert_eq!(results.len(), 1);
-    assert_eq!(results[0].start, 90);
-    assert_eq!(results[0].size, 3);
-}
+seed-to-colour map:
+100 3 2
 
-#[test]
-pub fn test_total3() {
-    let a: Range2 = Range2 { start: 0, size: 10 };
-    let b: Range2 = Range2 { start: 8, size: 3 };
-    let out: Range2 = Range2 { start: 90, size: 10 };
-    let results: Vec<Range2>;
-    results = a.map_to_total(b, out);
-    assert_eq!(results.len(), 1);
-    assert_eq!(results[0].start, 98);
-    assert_eq!(results[0].size, 3);
+colour-to-shade map:
+0 100 1";
+    let (_, almanac_maps) = parse_lines(input.lines().collect()).unwrap();
+    let almanac = Almanac::new(&almanac_maps);
+    assert_eq!(almanac.compose("seed", "soil").unwrap().apply(4), 14);
+    assert_eq!(almanac.path("seed", "shade").unwrap(), vec!["seed", "colour", "shade"]);
+    assert_eq!(almanac.compose("seed", "shade").unwrap().apply(3), 0);
+    let trace = almanac.trace("seed", 3);
+    assert_eq!(trace, vec![("seed".to_string(), 3), ("soil".to_string(), 13), ("colour".to_string(), 100), ("shade".to_string(), 0)]);
+    // Seed 3 is mapped to colour 100, which seed 100 already is by default
+    assert_eq!(almanac.compose("seed", "shade").unwrap().inverse().apply(0), vec![0, 3, 100]);
 }
 
 #[test]
-pub fn test_total() {
-    let a: Range2 = Range2 { start: 41, size: 5 };
-    let b: Range2 = Range2 { start: 42, size: 3 };
-    let out: Range2 = Range2 { start: 90, size: 5 };
-    let results: Vec<Range2>;
-    results = a.map_to_total(b, out);
-    assert_eq!(results.len(), 1);
-    assert_eq!(results[0].start, 91);
-    assert_eq!(results[0].size, 3);
+pub fn test_seed_errors() {
+    assert_eq!(get_seed_ranges(&[79, 14, 55]).unwrap_err(), "Seed ranges come in pairs, but there are 3 numbers");
+    assert!(get_seed_ranges(&[usize::MAX, 2]).is_err());
+    assert!(get_seed_ranges(&[DOMAIN_END as usize - 2, 2]).is_ok());
+    assert!(get_single_seeds(&[1, DOMAIN_END as usize]).is_err());
+    assert_eq!(get_single_seeds(&[]), Ok(Vec::new()));
 }
 
 #[test]
-pub fn test_overlap() {
-    let a = Range2 { start: 3, size: 3 }; // 3, 4, 5
-    let b = Range2 { start: 6, size: 3 }; // 6, 7, 8
-    let c = Range2 { start: 3, size: 6 }; // 3, 4, 5, 6, 7, 8
-    let d = Range2 { start: 4, size: 3 }; // 4, 5, 6
-    let e: Range2 = Range2 { start: 6, size: 3 }; // 6, 7, 8
-
-    // a has no overlap with b
-    assert_eq!(a.overlap(b.clone()), Overlap::None);
-    assert_eq!(b.overlap(a.clone()), Overlap::None);
-    // a is fully contained in c
-    assert_eq!(a.overlap(c.clone()), Overlap::PartialLeft);
-    // d and e overlap by a single value
-    assert_eq!(d.overlap(e.clone()), Overlap::PartialLeft);
-    // d and e overlap by a single value reversed
-    assert_eq!(e.overlap(d.clone()), Overlap::PartialRight);
-    // b is fully contained in c
-    assert_eq!(b.overlap(c.clone()), Overlap::PartialRight);
-    // c overlaps fully with a but has more to the right
-    assert_eq!(c.overlap(a.clone()), Overlap::Total);
-    // c overlaps fully with b but has more to the left
-    assert_eq!(c.overlap(b.clone()), Overlap::Total);
-    // d overlaps partially with a but has more to the right
-    assert_eq!(d.overlap(a.clone()), Overlap::PartialRight);
-    // a overlaps partially with d but has more to the left
-    assert_eq!(a.overlap(d.clone()), Overlap::PartialLeft);
-    // d overlaps with c and produces 3 ranges
-    assert_eq!(d.overlap(c.clone()), Overlap::PartialMiddle);
-    // d overlaps with c reverse
-    assert_eq!(c.overlap(d.clone()), Overlap::Total);
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day5_parse_block") {
+        assert!(parse_block(input.lines().collect()).is_err(), "{:?}", input);
+    }
 }
 
 fn main() {
-    part1();
-    part2();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => {
+            part1();
+            part2();
+        },
+        [argument, value] => {
+            if let Err(error) = query(argument, value) {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("Usage: day5 [--trace <seed> | --seeds-for <location>]");
+            std::process::exit(1);
+        },
+    }
 }
@@ -4,13 +4,65 @@ use std::time::Instant;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct Race {
-    time: usize,
-    distance: usize,
+    time: u128,
+    distance: u128,
 }
 
 impl Race {
-    fn will_time_win(&self, input_time: usize) -> bool {
-        self.distance < input_time * self.time - input_time * input_time
+    fn will_time_win(&self, input_time: u128) -> bool {
+        // A product too big for u128 is certainly further than any record
+        match input_time.checked_mul(self.time - input_time) {
+            Some(travelled) => self.distance < travelled,
+            None => true,
+        }
+    }
+    // Shortest winning hold. Holding h travels h * (time - h), so the winning holds
+    // lie strictly between the roots of h^2 - time * h + distance = 0.
+    fn first_winning_time(&self) -> Option<u128> {
+        let half = self.time / 2;
+        let mut hold: u128 = match self.time.checked_mul(self.time) {
+            // With time * time in range no hold travels more than a quarter of it,
+            // so a record too big to quadruple can't be beaten either
+            Some(square) => {
+                let discriminant = square.checked_sub(self.distance.checked_mul(4)?)?;
+                (self.time - discriminant.isqrt()) / 2
+            },
+            None => self.search_first_winning_time(half),
+        };
+        // The root is exact up to rounding, so this only ever moves by one
+        while hold > 0 && self.will_time_win(hold - 1) {
+            hold -= 1;
+        }
+        while hold <= half && !self.will_time_win(hold) {
+            hold += 1;
+        }
+        if hold > half {
+            None
+        }
+        else {
+            Some(hold)
+        }
+    }
+    // Binary search for when time * time is too big to take the square root of
+    fn search_first_winning_time(&self, half: u128) -> u128 {
+        let (mut low, mut high) = (0, half + 1);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.will_time_win(middle) {
+                high = middle;
+            }
+            else {
+                low = middle + 1;
+            }
+        }
+        low
+    }
+    // The winning holds are symmetric about time / 2
+    fn count_winning_times(&self) -> u128 {
+        match self.first_winning_time() {
+            Some(first) => self.time - 2 * first + 1,
+            None => 0,
+        }
     }
 }
 
@@ -24,57 +76,95 @@ fn get_input_lines() -> Vec<&'static str>{
     data
 }
 
-fn parse_input1() -> Vec<Race>{
+// The numbers after `label` on a line, as strings
+fn parse_column(line: Option<&&str>, label: &str) -> Result<Vec<String>, String> {
+    let line = line.ok_or(format!("Missing the {} line", label))?;
+    let numbers = line.strip_prefix(label).ok_or(format!("Expected '{}', found '{}'", label, line))?;
+    Ok(numbers.split_ascii_whitespace().map(|x| x.to_string()).collect())
+}
+
+fn parse_number(string: &str) -> Result<u128, String> {
+    string.parse::<u128>().map_err(|_| format!("Invalid number '{}'", string))
+}
+
+// Reads the races either as separate columns, or with `kerning` as one race
+// whose numbers are the digits of every column run together.
+fn parse_races(data: &[&str], kerning: bool) -> Result<Vec<Race>, String>{
     let mut races: Vec<Race> = Vec::new();
-    let data = get_input_lines();
-    let times_binding = data[0].replace("Time:", "");
-    let distance_binding = data[1].replace("Distance:", "");
-    let mut times_strings: Vec<&str> = times_binding.trim().split(' ').collect();
-    let mut distances_strings: Vec<&str> = distance_binding.trim().split(' ').collect();
-    times_strings.retain(|&x| !x.is_empty());
-    distances_strings.retain(|&x| !x.is_empty());
+    let mut times_strings = parse_column(data.first(), "Time:")?;
+    let mut distances_strings = parse_column(data.get(1), "Distance:")?;
+    if times_strings.len() != distances_strings.len() {
+        return Err(format!("There are {} times but {} distances", times_strings.len(), distances_strings.len()));
+    }
+    if kerning {
+        times_strings = vec![times_strings.concat()];
+        distances_strings = vec![distances_strings.concat()];
+    }
     for (time, distance) in izip!(times_strings, distances_strings) {
-        races.push(Race { time: time.parse::<usize>().unwrap(), distance: distance.parse::<usize>().unwrap() })
+        races.push(Race { time: parse_number(&time)?, distance: parse_number(&distance)? })
     }
-    races
+    Ok(races)
+}
 
+#[test]
+fn test_example() {
+    let data: Vec<&str> = include_str!("../example.txt").lines().collect();
+    let races = parse_races(&data, false).unwrap();
+    assert_eq!(races.iter().map(|race| race.count_winning_times()).collect::<Vec<u128>>(), vec![4, 8, 9]);
+    assert_eq!(parse_races(&data, true), Ok(vec![Race{time: 71530, distance: 940200}]));
+    assert_eq!(parse_races(&data, true).unwrap()[0].count_winning_times(), 71503);
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day6_parse_races") {
+        let data: Vec<&str> = input.lines().collect();
+        assert!(parse_races(&data, false).is_err() && parse_races(&data, true).is_err(), "{:?}", input);
+    }
 }
 
-fn parse_input2() -> Race{
-    let data = get_input_lines();
-    let times_binding = data[0].replace("Time:", "");
-    let distance_binding = data[1].replace("Distance:", "");
-    let time_string: String = times_binding.replace(' ', "");
-    let distance_string: String = distance_binding.replace(' ', "");
-    Race{ time: time_string.parse::<usize>().unwrap(), distance: distance_string.parse::<usize>().unwrap() }
+#[test]
+fn test_against_brute_force() {
+    for time in 0..60u128 {
+        for distance in 0..(time * time / 4 + 3) {
+            let race = Race{time, distance};
+            let expected = (0..=time).filter(|t| race.will_time_win(*t)).count() as u128;
+            assert_eq!(race.count_winning_times(), expected, "{:?}", race);
+        }
+    }
+}
 
+#[test]
+fn test_large_races() {
+    // Holding for 10 exactly ties the record, which doesn't count as a win
+    assert_eq!(Race{time: 30, distance: 200}.first_winning_time(), Some(11));
+    // time * time overflows u128 here, so the search is used instead of the root
+    let time: u128 = 1 << 100;
+    assert_eq!(Race{time, distance: 0}.count_winning_times(), time - 1);
+    // Holding for 1 ties a record of time - 1
+    assert_eq!(Race{time, distance: time - 1}.count_winning_times(), time - 3);
+    assert_eq!(Race{time, distance: time - 1}.first_winning_time(), Some(2));
+    assert_eq!(Race{time: u64::MAX as u128, distance: u128::MAX / 4}.count_winning_times(), 0);
+    let time: u128 = u64::MAX as u128;
+    assert_eq!(Race{time, distance: 2 * (time - 2)}.count_winning_times(), time - 5);
+    // Records too big to quadruple
+    let race = Race{time: 1 << 60, distance: u128::MAX / 2};
+    assert_eq!((race.first_winning_time(), race.count_winning_times()), (None, 0));
+    assert_eq!(Race{time: 1 << 60, distance: u128::MAX}.first_winning_time(), None);
 }
 
 fn part1() {
-    let races: Vec<Race> = parse_input1();
-    let mut counter: usize;
-    let mut total: usize = 1;
+    let races: Vec<Race> = parse_races(&get_input_lines(), false).unwrap();
+    let mut total: u128 = 1;
     for race in races {
-        counter = 0;
-        for t in 0..race.time {
-            if race.will_time_win(t) {
-                counter += 1;
-            }
-        }
-        total *= counter;
+        total *= race.count_winning_times();
     }
     println!("Part1 Answer: {}", total);
 }
 
 fn part2() {
-    let race: Race = parse_input2();
-    let mut counter: usize = 0;
-    for t in 0..race.time {
-        if race.will_time_win(t) {
-            counter += 1;
-        }
-    }
-    println!("Part2 Answer: {}", counter);
+    let race: Race = parse_races(&get_input_lines(), true).unwrap().remove(0);
+    println!("Part2 Answer: {}", race.count_winning_times());
 }
 
 
@@ -1,161 +1,267 @@
 mod aoc_parser;
-use std::collections::HashSet;
+use std::cmp::Ordering;
 
 use aoc_parser::{get_input_lines, InputType};
 
-#[derive(Clone, Debug)]
-struct Hand {
-    cards: String,
-    bid: usize,
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
 }
 
-impl Hand {
-    fn get_value(&self) -> String {
-        let suffix = self.get_suffix2();
-        let mut prefix: String;
-        let mut set_cards: HashSet<char> = self.cards.chars().collect();
-        set_cards.remove(&'J');
-        if set_cards.len() == 0 {
-            prefix = "7".to_string();
-        }
-        else {
-            match set_cards.len() {
-                // all same
-                1 => prefix = "7".to_string(),
-                // full house/4 of a kind
-                2 => prefix = self.fullhouse_or_4ofakind(),
-                // 3 of a kind/2 pair
-                3 => prefix = self.ofakind3_or_2pair(),
-                // one pair
-                4 => prefix = "2".to_string(),
-                // high card/nothing
-                5 => prefix = "1".to_string(),
-                // uh-oh
-                _ => unreachable!(),
-            }
+impl HandType {
+    const ALL: [HandType; 7] = [
+        HandType::HighCard, HandType::OnePair, HandType::TwoPair, HandType::ThreeOfAKind,
+        HandType::FullHouse, HandType::FourOfAKind, HandType::FiveOfAKind,
+    ];
+    // The group sizes a five card hand of this type needs, largest first.
+    // A full house is [3, 2], two pair is [2, 2] and a high card is [1].
+    fn groups(&self) -> &'static [usize] {
+        match self {
+            HandType::HighCard => &[1],
+            HandType::OnePair => &[2],
+            HandType::TwoPair => &[2, 2],
+            HandType::ThreeOfAKind => &[3],
+            HandType::FullHouse => &[3, 2],
+            HandType::FourOfAKind => &[4],
+            HandType::FiveOfAKind => &[5],
         }
-        prefix.push_str(&suffix);
-        prefix
     }
-    fn fullhouse_or_4ofakind(&self) -> String{
-        let mut set_cards: HashSet<char> = self.cards.chars().collect();
-        let elem = set_cards.iter().next().unwrap().clone();
-        let mut result: usize = 0;
-        if set_cards.contains(&'J') {
-            set_cards.remove(&'J');
-            for c in set_cards {
-                match self.cards.matches(c).count() {
-                    1 => result = result.max(6),
-                    2 => result = result.max(5),
-                    3 => result = result.max(6),
-                    _ => unreachable!(),
-                }
-            }
+}
 
+// How many wildcards it takes to turn these group counts (largest first) into
+// `groups`. Pairing the largest groups with the largest counts is optimal.
+fn wildcards_needed(groups: &[usize], counts: &[usize]) -> usize {
+    let mut needed: usize = 0;
+    for (k, group) in groups.iter().enumerate() {
+        needed += group.saturating_sub(counts.get(k).copied().unwrap_or(0));
+    }
+    needed
+}
+
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Rules {
+    // Weakest card first
+    card_order: Vec<char>,
+    // Cards that stand in for whichever card makes the best hand
+    wildcards: Vec<char>,
+    hand_size: usize,
+    // The group sizes, largest first, that each hand type needs under these
+    // rules, weakest type first. Types a game has no use for are left out.
+    categories: Vec<(HandType, Vec<usize>)>,
+}
+
+impl Rules {
+    fn new(card_order: &str, wildcards: &str, hand_size: usize, categories: &[(HandType, &[usize])]) -> Rules {
+        let mut categories: Vec<(HandType, Vec<usize>)> = categories.iter()
+            .map(|(hand_type, groups)| {
+                let mut groups = groups.to_vec();
+                groups.sort_unstable_by(|a, b| b.cmp(a));
+                (*hand_type, groups)
+            })
+            .collect();
+        categories.sort_unstable();
+        Rules{card_order: card_order.chars().collect(), wildcards: wildcards.chars().collect(), hand_size, categories}
+    }
+    fn five_card(card_order: &str, wildcards: &str) -> Rules {
+        let categories: Vec<(HandType, &[usize])> = HandType::ALL.iter().map(|hand_type| (*hand_type, hand_type.groups())).collect();
+        Rules::new(card_order, wildcards, 5, &categories)
+    }
+    // Cards rank 2 to A and J is a jack
+    fn standard() -> Rules {
+        Rules::five_card("23456789TJQKA", "")
+    }
+    // J is the weakest card but stands in for whichever card makes the best hand
+    fn jokers() -> Rules {
+        Rules::five_card("J23456789TQKA", "J")
+    }
+    fn card_rank(&self, card: char) -> Result<usize, String> {
+        match self.card_order.iter().position(|c| *c == card) {
+            Some(rank) => Ok(rank),
+            None => Err(format!("Unknown card '{}'", card)),
         }
-        else {
-            match self.cards.matches(elem).count() {
-                1 | 4 => result = result.max(6),
-                2 | 3 => result = result.max(5),
-                _ => unreachable!(),
-            }
-        }
-        result.to_string()
     }
-    fn ofakind3_or_2pair(&self) -> String{
-        let set_cards: HashSet<char> = self.cards.chars().collect();
-        let mut result: usize = 0;
-        if set_cards.contains(&'J') {
-            result = result.max(4);
+    // The best hand type these cards can make
+    fn classify(&self, cards: &[char]) -> Result<HandType, String> {
+        if cards.len() != self.hand_size {
+            return Err(format!("Hand has {} cards, expected {}", cards.len(), self.hand_size));
         }
-        else {
-            for c in set_cards{
-                match self.cards.matches(c).count() {
-                    1 => (),
-                    2 => result = result.max(3),
-                    3 => result = result.max(4),
-                    _ => unreachable!(),
-                }
+        let mut counts: Vec<usize> = vec![0; self.card_order.len()];
+        let mut wild: usize = 0;
+        for c in cards {
+            let rank = self.card_rank(*c)?;
+            if self.wildcards.contains(c) {
+                wild += 1;
+            }
+            else {
+                counts[rank] += 1;
             }
         }
-        result.to_string()
-    }
-    fn get_suffix(&self) -> String{
-        let mut suffix: String = "".to_owned();
-        for c in self.cards.chars(){
-            match c {
-                '2' => suffix.push_str("a"),
-                '3' => suffix.push_str("b"),
-                '4' => suffix.push_str("c"),
-                '5' => suffix.push_str("d"),
-                '6' => suffix.push_str("e"),
-                '7' => suffix.push_str("f"),
-                '8' => suffix.push_str("g"),
-                '9' => suffix.push_str("h"),
-                'T' => suffix.push_str("i"),
-                'J' => suffix.push_str("j"),
-                'Q' => suffix.push_str("k"),
-                'K' => suffix.push_str("l"),
-                'A' => suffix.push_str("m"),
-                _ => suffix.push_str("z"),
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        // Each group needs its own card, and wildcards can only be natural cards
+        let distinct_cards = self.card_order.iter().filter(|c| !self.wildcards.contains(c)).count();
+        for (hand_type, groups) in self.categories.iter().rev() {
+            if groups.len() <= distinct_cards && wildcards_needed(groups, &counts) <= wild {
+                return Ok(*hand_type);
             }
         }
-        suffix
+        Err(format!("Hand {} matches no hand type", cards.iter().collect::<String>()))
     }
-    fn get_suffix2(&self) -> String{
-        let mut suffix: String = "".to_owned();
-        for c in self.cards.chars(){
-            match c {
-                '2' => suffix.push_str("n"),
-                '3' => suffix.push_str("o"),
-                '4' => suffix.push_str("p"),
-                '5' => suffix.push_str("q"),
-                '6' => suffix.push_str("r"),
-                '7' => suffix.push_str("s"),
-                '8' => suffix.push_str("t"),
-                '9' => suffix.push_str("u"),
-                'T' => suffix.push_str("v"),
-                'J' => suffix.push_str("b"),
-                'Q' => suffix.push_str("x"),
-                'K' => suffix.push_str("y"),
-                'A' => suffix.push_str("z"),
-                _ => suffix.push_str("a"),
-            }
+    fn deal(&self, cards: &str, bid: usize) -> Result<Hand<'_>, String> {
+        let card_vec: Vec<char> = cards.chars().collect();
+        let hand_type = self.classify(&card_vec)?;
+        let mut card_ranks: Vec<usize> = Vec::new();
+        for c in card_vec {
+            card_ranks.push(self.card_rank(c)?);
         }
-        suffix
+        Ok(Hand{rules: self, bid, hand_type, card_ranks})
     }
+    fn dealt(&self, hand: &Hand) -> bool {
+        std::ptr::eq(self, hand.rules) || self == hand.rules
+    }
+    // Hands order by type first, then card by card from the left. Hands dealt
+    // under other rules have no order here.
+    fn compare(&self, a: &Hand, b: &Hand) -> Option<Ordering> {
+        if !self.dealt(a) || !self.dealt(b) {
+            return None;
+        }
+        Some(a.hand_type.cmp(&b.hand_type).then_with(|| a.card_ranks.cmp(&b.card_ranks)))
+    }
+}
+
+
+// A hand keeps the rules it was dealt under, as its type and card ranks only
+// mean anything under them. Only those rules can order it, via Rules::compare.
+#[derive(Clone, Debug)]
+struct Hand<'a> {
+    rules: &'a Rules,
+    bid: usize,
+    hand_type: HandType,
+    card_ranks: Vec<usize>,
 }
 
 
-fn parse_input() -> Vec<Hand>{
-    let data = get_input_lines(InputType::Input);
-    let mut hands: Vec<Hand> = Vec::new();
-    for row in data {
-        let binding: Vec<&str> = row.split_ascii_whitespace().collect();
-        hands.push(Hand { cards: binding[0].to_string(), bid: binding[1].parse::<usize>().unwrap() })
+// A line is the cards, then the bid
+fn parse_line<'a>(row: &str, rules: &'a Rules) -> Result<Hand<'a>, String> {
+    let binding: Vec<&str> = row.split_ascii_whitespace().collect();
+    if binding.len() != 2 {
+        return Err(format!("Expected cards and a bid, found '{}'", row));
     }
-    hands
+    let bid = binding[1].parse::<usize>().map_err(|_| format!("Invalid bid '{}'", binding[1]))?;
+    rules.deal(binding[0], bid)
 }
 
-fn part1() {
-    let mut hands: Vec<Hand> = parse_input();
+fn parse_lines<'a>(data: &[&str], rules: &'a Rules) -> Result<Vec<Hand<'a>>, String> {
+    data.iter().map(|row| parse_line(row, rules)).collect()
+}
+
+fn parse_input(input_type: InputType, rules: &Rules) -> Result<Vec<Hand<'_>>, String>{
+    parse_lines(&get_input_lines(input_type), rules)
+}
+
+// None if any hand was dealt under other rules
+fn get_winnings(rules: &Rules, mut hands: Vec<Hand>) -> Option<usize> {
+    if !hands.iter().all(|hand| rules.dealt(hand)) {
+        return None;
+    }
     let mut total: usize = 0;
-    hands.sort_by(|a, b| a.get_value().cmp(&b.get_value()));
+    hands.sort_by(|a, b| rules.compare(a, b).unwrap_or(Ordering::Equal));
     for (i, hand) in hands.iter().enumerate() {
         total += (i+1)*hand.bid;
     }
-    println!("Part 1 Answer: {}", total);
+    Some(total)
+}
+
+fn part1() {
+    let rules = Rules::standard();
+    let hands: Vec<Hand> = parse_input(InputType::Input, &rules).unwrap();
+    println!("Part 1 Answer: {}", get_winnings(&rules, hands).unwrap());
 }
 
 fn part2() {
-    let mut hands: Vec<Hand> = parse_input();
-    let mut total: usize = 0;
-    hands.sort_by(|a, b| a.get_value().cmp(&b.get_value()));
-    // dbg!(hands.clone());
-    for (i, hand) in hands.iter().enumerate() {
-        total += (i+1)*hand.bid;
+    let rules = Rules::jokers();
+    let hands: Vec<Hand> = parse_input(InputType::Input, &rules).unwrap();
+    println!("Part 2 Answer: {}", get_winnings(&rules, hands).unwrap());
+}
+
+#[test]
+pub fn test_example_winnings() {
+    let (standard, jokers) = (Rules::standard(), Rules::jokers());
+    assert_eq!(get_winnings(&standard, parse_input(InputType::Example, &standard).unwrap()), Some(6440));
+    assert_eq!(get_winnings(&jokers, parse_input(InputType::Example, &jokers).unwrap()), Some(5905));
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    let rules = Rules::jokers();
+    for input in aoc_gen::fuzz_regressions("day7_parse_lines") {
+        assert!(parse_lines(&input.lines().collect::<Vec<&str>>(), &rules).is_err(), "{:?}", input);
     }
-    println!("Part 2 Answer: {}", total);
+}
+
+#[test]
+pub fn test_hand_types() {
+    let hand_type = |cards: &str, rules: &Rules| rules.deal(cards, 0).unwrap().hand_type;
+    let order = |a: &str, b: &str, rules: &Rules| rules.compare(&rules.deal(a, 0).unwrap(), &rules.deal(b, 0).unwrap());
+    let (standard, jokers) = (Rules::standard(), Rules::jokers());
+    assert_eq!(hand_type("KTJJT", &standard), HandType::TwoPair);
+    assert_eq!(hand_type("KTJJT", &jokers), HandType::FourOfAKind);
+    assert_eq!(hand_type("JJJJJ", &jokers), HandType::FiveOfAKind);
+    assert_eq!(hand_type("2345J", &jokers), HandType::OnePair);
+    assert_eq!(hand_type("22J33", &jokers), HandType::FullHouse);
+    assert_eq!(hand_type("23456", &standard), HandType::HighCard);
+    assert_eq!(hand_type("T55J5", &standard), HandType::ThreeOfAKind);
+    // J is the weakest card under the joker rules
+    assert_eq!(order("JKKK2", "QQQQ2", &jokers), Some(Ordering::Less));
+    assert_eq!(order("JKKK2", "QQQQ2", &standard), Some(Ordering::Less));
+    assert_eq!(order("33332", "2AAAA", &standard), Some(Ordering::Greater));
+}
+
+#[test]
+pub fn test_cross_rules() {
+    // KTJJT is two pair under the standard rules and four of a kind with jokers,
+    // so no order between the two would mean anything
+    let (standard, jokers) = (Rules::standard(), Rules::jokers());
+    let (a, b) = (standard.deal("KTJJT", 1).unwrap(), jokers.deal("QQQ23", 2).unwrap());
+    assert_eq!(standard.compare(&a, &b), None);
+    assert_eq!(jokers.compare(&a, &b), None);
+    assert_eq!(standard.compare(&a, &standard.deal("QQQ23", 0).unwrap()), Some(Ordering::Less));
+    assert_eq!(get_winnings(&standard, vec![a, b]), None);
+    // Equal rules built separately still agree
+    assert_eq!(Rules::standard().compare(&standard.deal("KTJJT", 0).unwrap(), &standard.deal("KTJJT", 0).unwrap()), Some(Ordering::Equal));
+}
+
+#[test]
+pub fn test_custom_rules() {
+    // Seven card hands from a four card deck, with two wildcard symbols. The
+    // game decides which shape of hand counts as each type.
+    let rules = Rules::new("*?abcd", "*?", 7, &[
+        (HandType::HighCard, &[1]),
+        (HandType::TwoPair, &[3, 3]),
+        (HandType::FourOfAKind, &[4, 3]),
+        (HandType::ThreeOfAKind, &[4]),
+        (HandType::FiveOfAKind, &[7]),
+    ]);
+    let hand_type = |cards: &str| rules.classify(&cards.chars().collect::<Vec<char>>()).unwrap();
+    assert_eq!(hand_type("aabbcdd"), HandType::HighCard);
+    assert_eq!(hand_type("aaabbbc"), HandType::TwoPair);
+    assert_eq!(hand_type("aaaabbc"), HandType::ThreeOfAKind);
+    assert_eq!(hand_type("aa*bb?c"), HandType::ThreeOfAKind);
+    assert_eq!(hand_type("aaa*bb?"), HandType::FourOfAKind);
+    assert_eq!(hand_type("*?*?*?*"), HandType::FiveOfAKind);
+    // Within a category wildcards still rank as their own symbol
+    assert_eq!(rules.compare(&rules.deal("aaa*bb?", 0).unwrap(), &rules.deal("aaaaddd", 0).unwrap()), Some(Ordering::Less));
+    assert!(rules.classify(&['a', 'b']).is_err());
+    assert!(rules.classify(&"aaaabbe".chars().collect::<Vec<char>>()).is_err());
+    // Four distinct groups cannot be made from only two natural cards
+    let small = Rules::new("xy*", "*", 4, &[(HandType::HighCard, &[1, 1, 1, 1])]);
+    assert!(small.classify(&['x', 'y', '*', '*']).is_err());
 }
 
 #[test]
@@ -175,6 +281,6 @@ pub fn test_rust_char_count() {
 
 
 fn main() {
-    // part1();
+    part1();
     part2();
 }
@@ -1,7 +1,8 @@
 mod aoc_parser;
 use std::collections::HashMap;
 use aoc_parser::{get_input_lines, InputType};
-use num::integer::lcm;
+use num::integer::{Integer, lcm};
+use aoc_graph::{GraphEdge, GraphExport, GraphNode};
 
 #[derive(Clone, Debug)]
 enum Direction {
@@ -11,109 +12,521 @@ enum Direction {
 
 #[derive(Clone, Debug)]
 struct Commands {
-    current: usize,
     instructions: Vec<Direction>,
 }
 
 impl Commands {
-    fn get_next(&mut self) -> Direction {
-        let result: Direction;
-        if self.current == self.instructions.len() {
-            self.current = 0;
-        }
-        result = self.instructions[self.current].clone();
-        self.current += 1;
-        result
-    }
-    fn reset(&mut self) {
-        self.current = 0;
+    fn get(&self, step: u128) -> &Direction {
+        &self.instructions[(step % self.instructions.len() as u128) as usize]
     }
 }
 
-fn commands_from_str(line: &str) -> Commands {
+fn commands_from_str(line: &str) -> Result<Commands, String> {
     let mut instructions: Vec<Direction> = Vec::new();
-    for c in line.chars().into_iter() {
+    for c in line.trim().chars() {
         match c {
             'L' => instructions.push(Direction::Left),
             'R' => instructions.push(Direction::Right),
-            _ => (),
+            _ => return Err(format!("Invalid instruction '{}'", c)),
         }
     }
-    Commands { current: 0, instructions: instructions }
+    if instructions.is_empty() {
+        return Err("There are no instructions".to_string());
+    }
+    Ok(Commands { instructions })
 }
 
 
-fn parse_input() -> (Commands, HashMap<String, (String, String)>){
-    let data = get_input_lines(InputType::Input);
-    let commands: Commands = commands_from_str(data[0]); 
-    let mut network: HashMap<String, (String, String)> = HashMap::new();
-    for row in data[2..].into_iter() {
+// The node labels interned to dense ids, with each node's successors stored by id.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Network {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
+
+impl Network {
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.left.push(id);
+        self.right.push(id);
+        id
+    }
+    fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+    fn next(&self, node: usize, direction: &Direction) -> usize {
+        match direction {
+            Direction::Left => self.left[node],
+            Direction::Right => self.right[node],
+        }
+    }
+    // For every node id, whether its label satisfies the condition
+    fn mark(&self, condition: &dyn Fn(&str) -> bool) -> Vec<bool> {
+        self.names.iter().map(|name| condition(name)).collect()
+    }
+    fn len(&self) -> usize {
+        self.names.len()
+    }
+}
+
+impl GraphExport for Network {
+    fn graph_nodes(&self) -> Vec<GraphNode> {
+        self.names.iter().map(|name| GraphNode::new(name, name)).collect()
+    }
+    fn graph_edges(&self) -> Vec<GraphEdge> {
+        let mut edges: Vec<GraphEdge> = Vec::new();
+        for (node, name) in self.names.iter().enumerate() {
+            edges.push(GraphEdge::new(name, &self.names[self.left[node]]).with_label("L"));
+            edges.push(GraphEdge::new(name, &self.names[self.right[node]]).with_label("R"));
+        }
+        edges
+    }
+    fn graph_name(&self) -> String {
+        "network".to_string()
+    }
+}
+
+
+fn parse_input() -> (Commands, Network){
+    parse_lines(get_input_lines(InputType::Input)).unwrap()
+}
+
+fn parse_lines(data: Vec<&str>) -> Result<(Commands, Network), String>{
+    let commands: Commands = commands_from_str(data.first().ok_or("The map is empty")?)?;
+    let mut network = Network{names: Vec::new(), ids: HashMap::new(), left: Vec::new(), right: Vec::new()};
+    let mut defined: Vec<bool> = Vec::new();
+    for row in data.iter().skip(2) {
         let s = row.replace(&['(', ')', ',', '='][..], "");
         let binding: Vec<&str> = s.trim().split_ascii_whitespace().collect();
-        network.insert(binding[0].to_string(),  (binding[1].to_string(), binding[2].to_string() ));
+        if binding.len() != 3 {
+            return Err(format!("Expected 'NODE = (LEFT, RIGHT)', found '{}'", row));
+        }
+        let node = network.intern(binding[0]);
+        let left = network.intern(binding[1]);
+        let right = network.intern(binding[2]);
+        network.left[node] = left;
+        network.right[node] = right;
+        defined.resize(network.len(), false);
+        defined[node] = true;
+    }
+    if network.len() == 0 {
+        return Err("The map has no nodes".to_string());
+    }
+    if let Some(missing) = defined.iter().position(|is_defined| !is_defined) {
+        return Err(format!("Node {} is used but never defined", network.names[missing]));
     }
-    (commands, network)
+    Ok((commands, network))
 }
 
-fn find_steps_to_end(start: &str, condition: &dyn Fn(String) -> bool, mut maps: (Commands, HashMap<String, (String, String)>)) -> u128{
-    let mut location: &str = start;
+fn find_steps_to_end(start: usize, is_end: &[bool], commands: &Commands, network: &Network) -> u128{
+    let mut location: usize = start;
     let mut counter: u128 = 0;
-    while condition(location.to_string().clone()) {
-        let direction: Direction = maps.0.get_next();
+    while !is_end[location] {
+        location = network.next(location, commands.get(counter));
         counter += 1;
-        match direction {
-            Direction::Left => location = &maps.1[location].0,
-            Direction::Right => location = &maps.1[location].1,
-        }
     }
-    maps.0.reset();
     counter
 }
 
 fn part1(){
-    let maps: (Commands, HashMap<String, (String, String)>) = parse_input();
-    println!("Part 1: {}", find_steps_to_end("AAA", &is_not_end_node1, maps.clone()))
+    let (commands, network) = parse_input();
+    let is_end = network.mark(&|name| name == "ZZZ");
+    println!("Part 1: {}", find_steps_to_end(network.id("AAA").unwrap(), &is_end, &commands, &network))
 }
 
-fn get_start_nodes(map_: HashMap<String, (String, String)>) -> Vec<String>{
-    let mut starts: Vec<String> = Vec::new();
-    for key in map_.clone().keys() {
-        if key.ends_with('A') {
-            starts.push(key.to_string());
+fn get_start_nodes(network: &Network) -> Vec<usize>{
+    let mut starts: Vec<usize> = Vec::new();
+    for (id, name) in network.names.iter().enumerate() {
+        if name.ends_with('A') {
+            starts.push(id);
         }
     }
     starts
 }
 
-fn is_not_end_node1(location: String) -> bool {
-    location != "ZZZ"
+
+// Node reached after 2^k full passes through the instructions, for each k,
+// so that walks of any length take one lookup per bit plus a partial pass.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct JumpTable {
+    passes: Vec<Vec<usize>>,
+}
+
+impl JumpTable {
+    fn new(commands: &Commands, network: &Network, max_steps: u128) -> JumpTable {
+        let mut one_pass: Vec<usize> = Vec::with_capacity(network.len());
+        for node in 0..network.len() {
+            let mut location = node;
+            for direction in &commands.instructions {
+                location = network.next(location, direction);
+            }
+            one_pass.push(location);
+        }
+        let max_passes = max_steps / commands.instructions.len() as u128;
+        let mut passes: Vec<Vec<usize>> = vec![one_pass];
+        while (1u128 << passes.len()) <= max_passes {
+            let last = &passes[passes.len() - 1];
+            let doubled: Vec<usize> = last.iter().map(|node| last[*node]).collect();
+            passes.push(doubled);
+        }
+        JumpTable{passes}
+    }
+    // Where a walk starting on the first instruction is after `steps` steps, or
+    // None if the table wasn't built for walks that long
+    fn position_after(&self, start: usize, steps: u128, commands: &Commands, network: &Network) -> Option<usize> {
+        let full_passes = steps / commands.instructions.len() as u128;
+        if self.passes.len() < 128 && full_passes >= (1u128 << self.passes.len()) {
+            return None;
+        }
+        let mut location = start;
+        for (k, pass) in self.passes.iter().enumerate() {
+            if full_passes & (1 << k) != 0 {
+                location = pass[location];
+            }
+        }
+        for step in 0..(steps % commands.instructions.len() as u128) {
+            location = network.next(location, commands.get(step));
+        }
+        Some(location)
+    }
 }
 
-fn is_not_end_node2(location: String) -> bool {
-    !location.ends_with('Z')
+// Every time a ghost stands on an end node, as seen from its cycle over
+// (node, instruction index) states.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct GhostCycle {
+    // Step at which the repeating part begins, and how many steps it lasts
+    cycle_start: u128,
+    cycle_length: u128,
+    // End node arrivals before the cycle begins
+    hits_before: Vec<u128>,
+    // End node arrivals within the first pass of the cycle
+    hits_in_cycle: Vec<u128>,
 }
 
-fn get_lcm_of_vector(vec: Vec<u128>) -> u128 {
-    let mut lowest_multiple: u128 = lcm(vec[0], vec[1]);
-    for number in vec[2..].into_iter() {
-        lowest_multiple = lcm(lowest_multiple, *number);
+impl GhostCycle {
+    fn arrivals(&self) -> Vec<Arrivals> {
+        let mut arrivals: Vec<Arrivals> = Vec::new();
+        for hit in &self.hits_before {
+            arrivals.push(Arrivals{first: *hit, period: 0});
+        }
+        for hit in &self.hits_in_cycle {
+            arrivals.push(Arrivals{first: *hit, period: self.cycle_length});
+        }
+        arrivals
     }
-    lowest_multiple
+}
+
+// The steps first, first + period, first + 2 * period, ... or just `first` if the period is 0
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+struct Arrivals {
+    first: u128,
+    period: u128,
+}
+
+impl Arrivals {
+    fn contains(&self, step: u128) -> bool {
+        if self.period == 0 {
+            step == self.first
+        }
+        else {
+            step >= self.first && (step - self.first).is_multiple_of(self.period)
+        }
+    }
+    // Steps in both sets, found with the generalised Chinese remainder theorem
+    fn intersect(&self, other: &Arrivals) -> Option<Arrivals> {
+        if self.period == 0 {
+            return if other.contains(self.first) { Some(*self) } else { None };
+        }
+        if other.period == 0 {
+            return if self.contains(other.first) { Some(*other) } else { None };
+        }
+        let (m1, m2) = (self.period as i128, other.period as i128);
+        let difference = other.first as i128 - self.first as i128;
+        let gcd = m1.extended_gcd(&m2);
+        if difference % gcd.gcd != 0 {
+            return None;
+        }
+        // first + m1 * k hits the other set when m1 * k = difference (mod m2)
+        let modulus = m2 / gcd.gcd;
+        let k = (difference / gcd.gcd % modulus * (gcd.x % modulus)).rem_euclid(modulus);
+        let period = lcm(self.period, other.period);
+        let mut first = self.first + self.period * k as u128;
+        let lowest = self.first.max(other.first);
+        if first < lowest {
+            first += (lowest - first).div_ceil(period) * period;
+        }
+        Some(Arrivals{first, period})
+    }
+}
+
+fn find_ghost_cycle(start: usize, is_end: &[bool], commands: &Commands, network: &Network) -> GhostCycle {
+    let length = commands.instructions.len();
+    // Step at which each (node, instruction index) state was first seen
+    let mut seen: Vec<u128> = vec![u128::MAX; network.len() * length];
+    let mut hits: Vec<u128> = Vec::new();
+    let mut location: usize = start;
+    let mut step: u128 = 0;
+    loop {
+        let state = location * length + (step % length as u128) as usize;
+        if seen[state] != u128::MAX {
+            let cycle_start = seen[state];
+            return GhostCycle{
+                cycle_start,
+                cycle_length: step - cycle_start,
+                hits_before: hits.iter().copied().filter(|hit| *hit < cycle_start).collect(),
+                hits_in_cycle: hits.iter().copied().filter(|hit| *hit >= cycle_start).collect(),
+            };
+        }
+        seen[state] = step;
+        if is_end[location] {
+            hits.push(step);
+        }
+        location = network.next(location, commands.get(step));
+        step += 1;
+    }
+}
+
+// Walks every ghost in lockstep until they all stand on an end node at once.
+// With no ghosts there is nothing to arrive.
+fn simulate_ghosts(starts: &[usize], is_end: &[bool], commands: &Commands, network: &Network, max_steps: u128) -> Option<u128> {
+    if starts.is_empty() {
+        return None;
+    }
+    let mut locations: Vec<usize> = starts.to_vec();
+    for step in 0..=max_steps {
+        if locations.iter().all(|location| is_end[*location]) {
+            return Some(step);
+        }
+        let direction = commands.get(step);
+        for location in locations.iter_mut() {
+            *location = network.next(*location, direction);
+        }
+    }
+    None
+}
+
+// Combines the arrival times of every ghost. Returns None when they can never
+// all stand on an end node together.
+fn combine_ghost_cycles(cycles: &[GhostCycle]) -> Option<u128> {
+    let mut combined: Vec<Arrivals> = match cycles.first() {
+        Some(cycle) => cycle.arrivals(),
+        None => return None,
+    };
+    for cycle in &cycles[1..] {
+        let mut next: Vec<Arrivals> = Vec::new();
+        for arrivals in &combined {
+            for other in cycle.arrivals() {
+                if let Some(both) = arrivals.intersect(&other) {
+                    next.push(both);
+                }
+            }
+        }
+        next.sort();
+        next.dedup();
+        combined = next;
+    }
+    combined.iter().map(|arrivals| arrivals.first).min()
+}
+
+const SIMULATION_STEPS: u128 = 10_000;
+
+// Tries a short direct walk first, then falls back to combining each ghost's
+// cycle. None if the ghosts never all stand on end nodes together.
+fn find_ghost_steps_to_end(starts: &[usize], is_end: &[bool], commands: &Commands, network: &Network) -> Result<Option<u128>, String> {
+    if let Some(steps) = simulate_ghosts(starts, is_end, commands, network, SIMULATION_STEPS) {
+        return Ok(Some(steps));
+    }
+    let cycles: Vec<GhostCycle> = starts.iter().map(|start| find_ghost_cycle(*start, is_end, commands, network)).collect();
+    let Some(steps) = combine_ghost_cycles(&cycles) else {
+        return Ok(None);
+    };
+    // The combined answer comes from arithmetic alone, so jump every ghost straight there to check it
+    let table = JumpTable::new(commands, network, steps);
+    for start in starts {
+        match table.position_after(*start, steps, commands, network) {
+            Some(location) if is_end[location] => (),
+            _ => return Err(format!("The ghosts are not all on end nodes after {} steps", steps)),
+        }
+    }
+    Ok(Some(steps))
+}
+
+fn is_end_node2(location: &str) -> bool {
+    location.ends_with('Z')
 }
 
 fn part2(){
-    let maps: (Commands, HashMap<String, (String, String)>) = parse_input();
-    let locations: Vec<String> = get_start_nodes(maps.1.clone());
-    let answer: u128;
-    let mut steps_for_each_route: Vec<u128> = Vec::new();
-    for location in locations {
-        steps_for_each_route.push(find_steps_to_end(&location, &is_not_end_node2, maps.clone()))
+    let (commands, network) = parse_input();
+    let locations: Vec<usize> = get_start_nodes(&network);
+    let is_end = network.mark(&is_end_node2);
+    match find_ghost_steps_to_end(&locations, &is_end, &commands, &network) {
+        Ok(Some(answer)) => println!("Part 2: {}", answer),
+        Ok(None) => println!("Part 2: the ghosts never reach the end nodes together"),
+        Err(error) => eprintln!("Part 2: {}", error),
+    }
+}
+
+#[cfg(test)]
+const GHOST_NETWORK: &str = "L
+
+11A = (11B, 11B)
+11B = (11Z, 11Z)
+11Z = (11C, 11C)
+11C = (11B, 11B)
+22A = (22Z, 22Z)
+22Z = (22A, 22A)
+33A = (33B, 33B)
+33B = (33Z, 33Z)
+33Z = (33B, 33B)";
+
+#[cfg(test)]
+fn get_ids(network: &Network, names: &[&str]) -> Vec<usize> {
+    names.iter().map(|name| network.id(name).unwrap()).collect()
+}
+
+#[test]
+fn test_example_walks() {
+    let (commands, network) = parse_lines(get_input_lines(InputType::Example)).unwrap();
+    let is_end = network.mark(&|name| name == "ZZZ");
+    assert_eq!(find_steps_to_end(network.id("AAA").unwrap(), &is_end, &commands, &network), 2);
+    assert_eq!(network.names[network.left[network.id("AAA").unwrap()]], "BBB");
+}
+
+#[test]
+fn test_graph_export() {
+    let (_, network) = parse_lines(get_input_lines(InputType::Example)).unwrap();
+    assert_eq!(network.graph_nodes().len(), 7);
+    assert_eq!(network.graph_edges().len(), 14);
+    let dot = network.to_dot();
+    assert!(dot.contains("    \"AAA\" -> \"BBB\" [label=\"L\"];\n"));
+    assert!(dot.contains("    \"AAA\" -> \"CCC\" [label=\"R\"];\n"));
+    // Dead ends loop back on themselves both ways
+    assert!(network.to_node_link_json().contains("\"multigraph\": true"));
+}
+
+#[test]
+fn test_example2_ghosts() {
+    let (commands, network) = parse_lines(get_input_lines(InputType::Example2)).unwrap();
+    let is_end = network.mark(&is_end_node2);
+    let starts = get_ids(&network, &["11A", "22A"]);
+    assert_eq!(get_start_nodes(&network), starts);
+    assert_eq!(find_ghost_steps_to_end(&starts, &is_end, &commands, &network), Ok(Some(6)));
+    let cycles: Vec<GhostCycle> = starts.iter().map(|start| find_ghost_cycle(*start, &is_end, &commands, &network)).collect();
+    assert_eq!(combine_ghost_cycles(&cycles), Some(6));
+}
+
+#[test]
+fn test_ghost_cycles_with_offsets() {
+    let (commands, network) = parse_lines(GHOST_NETWORK.lines().collect()).unwrap();
+    let is_end = network.mark(&is_end_node2);
+    let cycle = find_ghost_cycle(network.id("11A").unwrap(), &is_end, &commands, &network);
+    assert_eq!(cycle, GhostCycle{cycle_start: 1, cycle_length: 3, hits_before: vec![], hits_in_cycle: vec![2]});
+    // 11A first arrives at step 2 and 22A at step 1, so the LCM would say 2
+    let starts = get_ids(&network, &["11A", "22A"]);
+    let cycles: Vec<GhostCycle> = starts.iter().map(|start| find_ghost_cycle(*start, &is_end, &commands, &network)).collect();
+    assert_eq!(combine_ghost_cycles(&cycles), Some(5));
+    assert_eq!(simulate_ghosts(&starts, &is_end, &commands, &network, 100), Some(5));
+    // 22A only arrives on odd steps and 33A only on even ones
+    let starts = get_ids(&network, &["22A", "33A"]);
+    assert_eq!(find_ghost_steps_to_end(&starts, &is_end, &commands, &network), Ok(None));
+    // A single ghost needs nothing to combine with
+    let starts = get_ids(&network, &["33A"]);
+    assert_eq!(find_ghost_steps_to_end(&starts, &is_end, &commands, &network), Ok(Some(2)));
+    // And no ghosts never arrive
+    assert_eq!(simulate_ghosts(&[], &is_end, &commands, &network, 100), None);
+    assert_eq!(find_ghost_steps_to_end(&[], &is_end, &commands, &network), Ok(None));
+}
+
+#[test]
+fn test_jump_table() {
+    let (commands, network) = parse_lines(get_input_lines(InputType::Example2)).unwrap();
+    let table = JumpTable::new(&commands, &network, 2_000_000_000_000);
+    let start = network.id("22A").unwrap();
+    for steps in 0..50 {
+        let mut location = start;
+        for step in 0..steps {
+            location = network.next(location, commands.get(step));
+        }
+        assert_eq!(table.position_after(start, steps, &commands, &network), Some(location));
+    }
+    // 22A cycles through 22B, 22C, 22Z from step 1, so step 10^12 + 2 lands on 22Z
+    let location = table.position_after(start, 1_000_000_000_002, &commands, &network).unwrap();
+    assert_eq!(network.names[location], "22Z");
+    // Walks past what the table was built for aren't guessed at
+    assert_eq!(table.position_after(start, 1 << 60, &commands, &network), None);
+    assert_eq!(JumpTable::new(&commands, &network, 10).position_after(start, 100, &commands, &network), None);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse_lines(vec!["LR", "", "AAA = (BBB, AAA)"]).unwrap_err(), "Node BBB is used but never defined");
+    assert!(parse_lines(vec!["LR", "", "AAA = (BBB)"]).is_err());
+    assert!(parse_lines(vec![]).is_err());
+    assert!(commands_from_str("").is_err());
+    assert!(commands_from_str("LRX").is_err());
+}
+
+#[test]
+fn test_arrivals_intersect() {
+    let a = Arrivals{first: 10, period: 12};
+    let b = Arrivals{first: 6, period: 8};
+    assert_eq!(a.intersect(&b), Some(Arrivals{first: 22, period: 24}));
+    assert_eq!(b.intersect(&a), Some(Arrivals{first: 22, period: 24}));
+    assert_eq!(a.intersect(&Arrivals{first: 3, period: 8}), None);
+    assert_eq!(a.intersect(&Arrivals{first: 1, period: 4}), None);
+    assert_eq!(a.intersect(&Arrivals{first: 46, period: 0}), Some(Arrivals{first: 46, period: 0}));
+    assert_eq!(a.intersect(&Arrivals{first: 45, period: 0}), None);
+    // The first shared step must come after both sets have started
+    assert_eq!(Arrivals{first: 100, period: 5}.intersect(&Arrivals{first: 0, period: 1}), Some(Arrivals{first: 100, period: 5}));
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day8_commands_from_str") {
+        assert!(commands_from_str(&input).is_err(), "{:?}", input);
+    }
+    for input in aoc_gen::fuzz_regressions("day8_parse_lines") {
+        assert!(parse_lines(input.lines().collect()).is_err(), "{:?}", input);
+    }
+}
+
+#[test]
+fn test_export_args() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+    assert_eq!(export(&args(&["--export", "dot"])), Ok(parse_input().1.to_dot()));
+    assert!(export(&args(&["--export", "json"])).unwrap().starts_with("{\"directed\": true"));
+    assert!(export(&args(&["--export", "png"])).is_err());
+    assert!(export(&args(&["--dot"])).is_err());
+}
+
+// With `--export <dot|json>`, prints the network as a graph instead of solving
+fn export(args: &[String]) -> Result<String, String> {
+    match args {
+        [flag, format] if flag == "--export" => parse_lines(get_input_lines(InputType::Input))?.1.export(format),
+        _ => Err("Usage: day8 [--export <dot|json>]".to_string()),
     }
-    answer = get_lcm_of_vector(steps_for_each_route);
-    println!("Part 2: {}", answer)
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match export(&args) {
+            Ok(graph) => print!("{}", graph),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
     part1();
     part2();
 }
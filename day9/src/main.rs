@@ -1,66 +1,217 @@
 mod aoc_parser;
 use aoc_parser::get_input_as_lines;
+use num::{BigInt, BigRational, One, Zero};
 
-fn arithmetic_sequence_next_step(sequence: &Vec<i64>) -> i64  {
-    let mut reduced_sequence: Vec<i64> = Vec::new();
-    let n: usize = sequence.len();
-    for i in 0..(n-1) {
-        reduced_sequence.push(sequence[i+1] - sequence[i]);
+// A sequence together with its table of differences, built down to the first
+// row that is all zeroes. Values are big integers so long sequences can't overflow.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Sequence {
+    differences: Vec<Vec<BigInt>>,
+}
+
+impl Sequence {
+    fn new(values: Vec<BigInt>) -> Result<Sequence, String> {
+        if values.is_empty() {
+            return Err("Cannot analyse an empty sequence".to_string());
+        }
+        let mut differences: Vec<Vec<BigInt>> = vec![values];
+        loop {
+            let last = &differences[differences.len() - 1];
+            if last.iter().all(|x| x.is_zero()) {
+                differences.pop();
+                return Ok(Sequence{differences});
+            }
+            if last.len() == 1 {
+                return Err(format!("Differences never reach zero within {} values", differences[0].len()));
+            }
+            let reduced: Vec<BigInt> = last.windows(2).map(|pair| &pair[1] - &pair[0]).collect();
+            differences.push(reduced);
+        }
+    }
+    fn len(&self) -> usize {
+        self.differences.first().map_or(0, |row| row.len())
+    }
+    // Degree of the polynomial through the values. An all-zero sequence counts as degree 0.
+    fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+    // Newton's forward difference formula: the value at x is the sum over k of
+    // the k-th difference at 0 times (x choose k), which also works for negative x.
+    fn value_at(&self, x: i64) -> BigInt {
+        let mut total = BigInt::zero();
+        let mut choose = BigInt::one();
+        for (k, row) in self.differences.iter().enumerate() {
+            if k > 0 {
+                choose = choose * (x - k as i64 + 1) / k as i64;
+            }
+            total += &row[0] * &choose;
+        }
+        total
     }
-    if reduced_sequence.iter().all(|x| *x == 0) {
-        reduced_sequence.last().unwrap() + sequence.last().unwrap()
+    // The value `k` places after the last one
+    fn extrapolate_forward(&self, k: u32) -> BigInt {
+        self.value_at(self.len() as i64 - 1 + k as i64)
     }
-    else {
-        arithmetic_sequence_next_step(&reduced_sequence) + sequence.last().unwrap()
+    // The value `k` places before the first one
+    fn extrapolate_backward(&self, k: u32) -> BigInt {
+        self.value_at(-(k as i64))
+    }
+    fn next_step(&self) -> BigInt {
+        self.extrapolate_forward(1)
+    }
+    fn previous_step(&self) -> BigInt {
+        self.extrapolate_backward(1)
+    }
+    // Coefficients c such that the value at x is sum(c[i] * x^i), lowest power first
+    fn coefficients(&self) -> Vec<BigRational> {
+        let mut coefficients: Vec<BigRational> = vec![BigRational::zero(); self.degree() + 1];
+        // x(x-1)...(x-k+1) / k!, expanded into powers of x
+        let mut falling: Vec<BigRational> = vec![BigRational::one()];
+        for (k, row) in self.differences.iter().enumerate() {
+            if k > 0 {
+                let shift = BigRational::from_integer(BigInt::from(k - 1));
+                let divisor = BigRational::from_integer(BigInt::from(k));
+                let mut next: Vec<BigRational> = vec![BigRational::zero(); falling.len() + 1];
+                for (power, coefficient) in falling.iter().enumerate() {
+                    next[power + 1] += coefficient / &divisor;
+                    next[power] -= coefficient * &shift / &divisor;
+                }
+                falling = next;
+            }
+            let difference = BigRational::from_integer(row[0].clone());
+            for (power, coefficient) in falling.iter().enumerate() {
+                coefficients[power] += coefficient * &difference;
+            }
+        }
+        coefficients
     }
 }
 
-fn arithmetic_sequence_previous_step(sequence: &Vec<i64>) -> i64  {
-    let mut reduced_sequence: Vec<i64> = Vec::new();
-    let n: usize = sequence.len();
-    for i in 0..(n-1) {
-        reduced_sequence.push(sequence[i+1] - sequence[i]);
-    }
-    if reduced_sequence.iter().all(|x| *x == 0) {
-        sequence[0] - reduced_sequence[0]
+fn parse_sequence(row: &str) -> Result<Vec<BigInt>, String> {
+    row.split_ascii_whitespace()
+        .map(|x| x.parse::<BigInt>().map_err(|_| format!("Invalid number '{}'", x)))
+        .collect()
+}
+
+fn parse_lines(data: &[&str]) -> Result<Vec<Sequence>, String> {
+    let mut sequences: Vec<Sequence> = Vec::new();
+    for (i, row) in data.iter().enumerate() {
+        let sequence = parse_sequence(row).and_then(Sequence::new).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+        sequences.push(sequence);
     }
-    else {
-        sequence[0] - arithmetic_sequence_previous_step(&reduced_sequence)
+    Ok(sequences)
+}
+
+fn parse_input(string: &'static str) -> Result<Vec<Sequence>, String> {
+    parse_lines(&get_input_as_lines(string))
+}
+
+#[cfg(test)]
+fn sequence_from(values: &[i64]) -> Result<Sequence, String> {
+    Sequence::new(values.iter().map(|x| BigInt::from(*x)).collect())
+}
+
+#[test]
+fn test_example() {
+    let sequences = parse_input(include_str!("../example.txt")).unwrap();
+    let next: BigInt = sequences.iter().map(|s| s.next_step()).sum();
+    let previous: BigInt = sequences.iter().map(|s| s.previous_step()).sum();
+    assert_eq!(next, BigInt::from(114));
+    assert_eq!(previous, BigInt::from(2));
+    assert_eq!(sequences.iter().map(|s| s.degree()).collect::<Vec<usize>>(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_extrapolate_and_coefficients() {
+    // 2x^3 - x + 5
+    let values: Vec<i64> = (0..6).map(|x| 2 * x * x * x - x + 5).collect();
+    let sequence = sequence_from(&values).unwrap();
+    assert_eq!(sequence.degree(), 3);
+    assert_eq!(sequence.extrapolate_forward(5), BigInt::from(2 * 1000 - 10 + 5));
+    assert_eq!(sequence.extrapolate_backward(3), BigInt::from(-54 + 3 + 5));
+    let expected: Vec<BigRational> = [5, -1, 0, 2].iter().map(|c| BigRational::from_integer(BigInt::from(*c))).collect();
+    assert_eq!(sequence.coefficients(), expected);
+    // Triangular numbers have half-integer coefficients: x^2 / 2 + x / 2
+    let triangular = sequence_from(&[0, 1, 3, 6, 10]).unwrap();
+    let half = BigRational::new(BigInt::from(1), BigInt::from(2));
+    assert_eq!(triangular.coefficients(), vec![BigRational::zero(), half.clone(), half]);
+    assert_eq!(sequence_from(&[7, 7, 7]).unwrap().coefficients(), vec![BigRational::from_integer(BigInt::from(7))]);
+}
+
+#[test]
+fn test_no_overflow_and_errors() {
+    // Consecutive values near the top of i64 would overflow on the first extrapolation
+    let sequence = sequence_from(&[i64::MAX - 2, i64::MAX - 1, i64::MAX]).unwrap();
+    assert_eq!(sequence.extrapolate_forward(10), BigInt::from(i64::MAX) + 10);
+    assert!(sequence_from(&[1, 2, 4, 8]).is_err());
+    assert!(sequence_from(&[]).is_err());
+    assert!(parse_sequence("1 two 3").is_err());
+}
+
+#[test]
+fn test_fuzz_regressions() {
+    for input in aoc_gen::fuzz_regressions("day9_parse_lines") {
+        assert!(parse_lines(&input.lines().collect::<Vec<&str>>()).is_err(), "{:?}", input);
     }
 }
 
-fn part1(){
-    let data = get_input_as_lines(include_str!("../input.txt"));
-    let mut sequences: Vec<Vec<i64>> = Vec::new();
-    let mut total: i64 = 0;
-    for row in data {
-        let num_string: Vec<&str> = row.split_ascii_whitespace().collect();
-        sequences.push(num_string.into_iter().map(|x: &str| x.parse::<i64>().unwrap()).collect::<Vec<i64>>())
+const USAGE: &str = "Usage: day9 [--analyse]";
+
+fn parse_args(args: &[String]) -> Result<bool, String> {
+    let mut analyse = false;
+    for arg in args {
+        match arg.as_str() {
+            "--analyse" => analyse = true,
+            _ => return Err(USAGE.to_string()),
+        }
     }
-    for sequence in sequences{
-        let result: i64 = arithmetic_sequence_next_step(&sequence);
-        total += result;
+    Ok(analyse)
+}
+
+// One line per history: its degree and its coefficients, lowest power first
+fn analyse(sequences: &[Sequence]) -> String {
+    let mut text = String::new();
+    for (i, sequence) in sequences.iter().enumerate() {
+        let coefficients: Vec<String> = sequence.coefficients().iter().map(|c| c.to_string()).collect();
+        text += &format!("Line {}: degree {}, coefficients [{}]\n", i + 1, sequence.degree(), coefficients.join(", "));
     }
+    text
+}
+
+#[test]
+fn test_analyse() {
+    let sequences = parse_input(include_str!("../example.txt")).unwrap();
+    let text = analyse(&sequences);
+    assert_eq!(text.lines().next(), Some("Line 1: degree 1, coefficients [0, 3]"));
+    assert_eq!(text.lines().count(), 3);
+    assert_eq!(parse_args(&["--analyse".to_string()]), Ok(true));
+    assert_eq!(parse_args(&[]), Ok(false));
+    assert!(parse_args(&["--analyze".to_string()]).is_err());
+}
+
+fn part1(){
+    let sequences = parse_input(include_str!("../input.txt")).unwrap();
+    let total: BigInt = sequences.iter().map(|s| s.next_step()).sum();
     println!("Part 1 Answer: {}", total);
 
 }
 
 fn part2(){
-    let data = get_input_as_lines(include_str!("../input.txt"));
-    let mut sequences: Vec<Vec<i64>> = Vec::new();
-    let mut total: i64 = 0;
-    for row in data {
-        let num_string: Vec<&str> = row.split_ascii_whitespace().collect();
-        sequences.push(num_string.into_iter().map(|x: &str| x.parse::<i64>().unwrap()).collect::<Vec<i64>>())
-    }
-    for sequence in sequences{
-        let result: i64 = arithmetic_sequence_previous_step(&sequence);
-        total += result;
-    }
+    let sequences = parse_input(include_str!("../input.txt")).unwrap();
+    let total: BigInt = sequences.iter().map(|s| s.previous_step()).sum();
     println!("Part 2 Answer: {}", total);
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match parse_args(&args) {
+        Ok(true) => print!("{}", analyse(&parse_input(include_str!("../input.txt")).unwrap())),
+        Ok(false) => (),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
     part1();
     part2();
 }
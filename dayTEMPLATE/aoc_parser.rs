@@ -1,3 +1,5 @@
+use aoc_coords::Vec2;
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
 #[allow(dead_code)]
 pub enum InputType {
@@ -22,6 +24,18 @@ pub struct Coord32 {
     pub y: i32,
 }
 
+impl From<Coord32> for Vec2<i32> {
+    fn from(coord: Coord32) -> Vec2<i32> {
+        Vec2::new(coord.x, coord.y)
+    }
+}
+
+impl From<Vec2<i32>> for Coord32 {
+    fn from(vector: Vec2<i32>) -> Coord32 {
+        Coord32{x: vector.x, y: vector.y}
+    }
+}
+
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
 #[allow(dead_code)]
@@ -30,6 +44,18 @@ pub struct Coord64 {
     pub y: i64,
 }
 
+impl From<Coord64> for Vec2<i64> {
+    fn from(coord: Coord64) -> Vec2<i64> {
+        Vec2::new(coord.x, coord.y)
+    }
+}
+
+impl From<Vec2<i64>> for Coord64 {
+    fn from(vector: Vec2<i64>) -> Coord64 {
+        Coord64{x: vector.x, y: vector.y}
+    }
+}
+
 // Uses a rule I found by accident: get the total area using the shoelace algorithm (the trapezoid formula in this case)
 // Then add that to half the total perimeter and add 1.
 // Taken from 2023 day 18 - only works for polygon with either horizontal or vertical edges (i.e. squares)
@@ -1,17 +1,49 @@
 mod aoc_parser;
 use std::time::Instant;
+use aoc_parser::get_input_as_lines;
 
 
-fn parse_input() {
+fn parse_input(mut string: &'static str) -> Vec<&'static str> {
+    if string.is_empty(){
+        string = include_str!("../input.txt");
+    }
+    get_input_as_lines(string)
+}
+
+fn solve_part1(_data: &[&str]) -> usize {
+    0
+}
+
+// The answer to the example in the puzzle text. The placeholder never matches,
+// so the example test fails until the real answer is filled in.
+#[cfg(test)]
+const EXAMPLE_PART1: usize = usize::MAX;
+
+#[test]
+fn test_example() {
+    let data = parse_input(include_str!("../example.txt"));
+    assert!(!data.is_empty(), "example.txt is empty");
+    assert_eq!(solve_part1(&data), EXAMPLE_PART1);
+}
 
+#[test]
+fn test_vec2_conversion() {
+    // The parser's coordinates and aoc_coords' vectors convert both ways unchanged
+    let coord = aoc_parser::Coord32{x: -1, y: 2};
+    assert_eq!(aoc_coords::Vec2::from(coord), aoc_coords::Vec2::new(-1, 2));
+    assert_eq!(aoc_parser::Coord32::from(aoc_coords::Vec2::from(coord)), coord);
+    let coord = aoc_parser::Coord64{x: 1 << 40, y: -3};
+    assert_eq!(aoc_parser::Coord64::from(aoc_coords::Vec2::from(coord)), coord);
 }
 
 fn part1(){
-    println!("Part 1 Answer: {}", 0);
+    let data = parse_input("");
+    println!("Part 1 Answer: {}", solve_part1(&data));
 }
 
 
 fn part2(){
+    let _data = parse_input("");
     println!("Part 2 Answer: {}", 0);
 }
 
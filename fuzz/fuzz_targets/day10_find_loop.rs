@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day10/src"]
+mod day10 {
+    include!("../../day10/src/main.rs");
+
+    pub fn fuzz(tiles: &str) {
+        if let Ok((nodes, start)) = parse_input(tiles) {
+            let _ = find_loop(start, nodes);
+        }
+    }
+}
+
+fuzz_target!(|tiles: &str| day10::fuzz(tiles));
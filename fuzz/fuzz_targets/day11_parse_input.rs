@@ -0,0 +1,23 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day11/src"]
+mod day11 {
+    include!("../../day11/src/main.rs");
+
+    pub fn fuzz(image: &str) {
+        if let Ok(data) = parse_input(image) {
+            for factor in [2, 1000000] {
+                let galaxies = expand_the_universe2(&data, factor);
+                sum_of_distances(&galaxies);
+                closest_pair(&galaxies);
+                farthest_pair(&galaxies);
+            }
+        }
+    }
+}
+
+fuzz_target!(|image: &str| day11::fuzz(image));
@@ -0,0 +1,21 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day12/src"]
+mod day12 {
+    include!("../../day12/src/main.rs");
+
+    pub fn fuzz(records: &str) {
+        if let Ok(lines) = parse_input(records) {
+            // Every blank doubles the arrangements to check
+            for line in lines.iter().filter(|line| line.get_num_blanks() <= 12) {
+                line.get_num_valid_arrangements();
+            }
+        }
+    }
+}
+
+fuzz_target!(|records: &str| day12::fuzz(records));
@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day13/src"]
+mod day13 {
+    include!("../../day13/src/main.rs");
+
+    pub fn fuzz(notes: &str) {
+        if let Ok(patterns) = parse_input(notes) {
+            assert!(patterns.iter().all(|pattern| pattern.iter().all(|row| row.len() == pattern[0].len())));
+        }
+    }
+}
+
+fuzz_target!(|notes: &str| day13::fuzz(notes));
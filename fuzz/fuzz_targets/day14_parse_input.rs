@@ -0,0 +1,20 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day14/src"]
+mod day14 {
+    include!("../../day14/src/main.rs");
+
+    pub fn fuzz(platform: &str) {
+        let program = parse_program(SPIN_CYCLE).unwrap();
+        if let Ok(mut platform) = parse_input(platform) {
+            platform.get_load_after(&program, Direction::North, 1000000000);
+            platform.display();
+        }
+    }
+}
+
+fuzz_target!(|platform: &str| day14::fuzz(platform));
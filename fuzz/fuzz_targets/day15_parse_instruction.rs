@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day15/src"]
+mod day15 {
+    include!("../../day15/src/main.rs");
+
+    pub fn fuzz(step: &str) {
+        let _ = parse_instruction(step);
+    }
+}
+
+fuzz_target!(|step: &str| day15::fuzz(step));
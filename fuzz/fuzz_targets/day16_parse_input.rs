@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day16/src"]
+mod day16 {
+    include!("../../day16/src/main.rs");
+
+    pub fn fuzz(contraption: &str) {
+        if let Ok(tiles) = parse_input(contraption) {
+            find_energised_tiles_from_start(&tiles, Coord{i: 0, j: 0}, Direction::East, &mut Recorder::off());
+        }
+    }
+}
+
+fuzz_target!(|contraption: &str| day16::fuzz(contraption));
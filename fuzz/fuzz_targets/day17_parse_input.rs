@@ -0,0 +1,23 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day17/src"]
+mod day17 {
+    include!("../../day17/src/main.rs");
+
+    pub fn fuzz(map: &str) {
+        if let Ok(city) = parse_input(map) {
+            let goal = Coord{i: city.height - 1, j: city.width - 1};
+            for crucible in [CRUCIBLE, ULTRA_CRUCIBLE] {
+                if let Some(route) = city.find_route(crucible, Coord{i: 0, j: 0}, goal) {
+                    city.render_route(&route);
+                }
+            }
+        }
+    }
+}
+
+fuzz_target!(|map: &str| day17::fuzz(map));
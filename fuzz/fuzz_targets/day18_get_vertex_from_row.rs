@@ -0,0 +1,23 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day18/src"]
+mod day18 {
+    include!("../../day18/src/main.rs");
+
+    pub fn fuzz(plan: &str) {
+        // Rows are chained the way parse_input does, so a plan can walk off the edge
+        let mut coord = Coord64{x: 0, y: 0};
+        for row in plan.lines() {
+            match get_vertex_from_row(row, coord) {
+                Ok((_, next)) => coord = next,
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+fuzz_target!(|plan: &str| day18::fuzz(plan));
@@ -0,0 +1,20 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day1/src"]
+mod day1 {
+    include!("../../day1/src/main.rs");
+
+    pub fn fuzz(document: &str) {
+        let matcher = Matcher::new(&[Vocabulary::english(), Vocabulary::digits(), Vocabulary::roman()]);
+        let _ = matcher.calibration_total(&parse_input(document));
+        let matcher = matcher.with_longest(true);
+        let _ = matcher.calibration_total(&parse_input(document));
+        let _ = matcher.digits(document);
+    }
+}
+
+fuzz_target!(|document: &str| day1::fuzz(document));
@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day20/src"]
+mod day20 {
+    include!("../../day20/src/main.rs");
+
+    pub fn fuzz(line: &str) {
+        let _ = parse_module(line);
+    }
+}
+
+fuzz_target!(|line: &str| day20::fuzz(line));
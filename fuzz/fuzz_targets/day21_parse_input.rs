@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day21/src"]
+mod day21 {
+    include!("../../day21/src/main.rs");
+
+    pub fn fuzz(garden: &str) {
+        if let Ok(garden) = parse_input(garden) {
+            garden.record_n_steps(20, &mut Recorder::new().with_max_frames(2));
+        }
+    }
+}
+
+fuzz_target!(|garden: &str| day21::fuzz(garden));
@@ -0,0 +1,20 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day23/src"]
+mod day23 {
+    include!("../../day23/src/main.rs");
+
+    pub fn fuzz(map: &str) {
+        if let Ok(trail) = parse_input(map) {
+            for slopes in [true, false] {
+                trail.get_junctions(slopes).to_dot();
+            }
+        }
+    }
+}
+
+fuzz_target!(|map: &str| day23::fuzz(map));
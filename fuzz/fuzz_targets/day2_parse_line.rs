@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day2/src"]
+mod day2 {
+    include!("../../day2/src/main.rs");
+
+    pub fn fuzz(line: &str) {
+        if let Ok(game) = parse_line(line, &COLOURS) {
+            let _ = game.get_minimum_hand().get_hand_power();
+        }
+    }
+}
+
+fuzz_target!(|line: &str| day2::fuzz(line));
@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day3/src"]
+mod day3 {
+    include!("../../day3/src/main.rs");
+
+    pub fn fuzz(schematic: &str) {
+        if let Ok(schematic) = parse_input(schematic) {
+            schematic.sum_of_products('*', 2);
+            schematic.highlight();
+        }
+    }
+}
+
+fuzz_target!(|schematic: &str| day3::fuzz(schematic));
@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day4/src"]
+mod day4 {
+    include!("../../day4/src/main.rs");
+
+    pub fn fuzz(line: &str) {
+        let _ = parse_line(line);
+    }
+}
+
+fuzz_target!(|line: &str| day4::fuzz(line));
@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day5/src"]
+mod day5 {
+    include!("../../day5/src/main.rs");
+
+    pub fn fuzz(block: &str) {
+        let _ = parse_block(block.lines().collect());
+    }
+}
+
+fuzz_target!(|block: &str| day5::fuzz(block));
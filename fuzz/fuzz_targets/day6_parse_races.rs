@@ -0,0 +1,23 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day6/src"]
+mod day6 {
+    include!("../../day6/src/main.rs");
+
+    pub fn fuzz(sheet: &str) {
+        let data: Vec<&str> = sheet.lines().collect();
+        for kerning in [false, true] {
+            if let Ok(races) = parse_races(&data, kerning) {
+                for race in races {
+                    let _ = race.count_winning_times();
+                }
+            }
+        }
+    }
+}
+
+fuzz_target!(|sheet: &str| day6::fuzz(sheet));
@@ -0,0 +1,21 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day7/src"]
+mod day7 {
+    include!("../../day7/src/main.rs");
+
+    pub fn fuzz(hands: &str) {
+        let data: Vec<&str> = hands.lines().collect();
+        for rules in [Rules::standard(), Rules::jokers()] {
+            if let Ok(hands) = parse_lines(&data, &rules) {
+                assert!(get_winnings(&rules, hands).is_some());
+            }
+        }
+    }
+}
+
+fuzz_target!(|hands: &str| day7::fuzz(hands));
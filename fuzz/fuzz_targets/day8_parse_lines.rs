@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day8/src"]
+mod day8 {
+    include!("../../day8/src/main.rs");
+
+    pub fn fuzz(map: &str) {
+        let _ = parse_lines(map.lines().collect());
+    }
+}
+
+fuzz_target!(|map: &str| day8::fuzz(map));
@@ -0,0 +1,22 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The day is a binary, so its main.rs is pulled in as a module, and the harness
+// lives inside that module where the parser and its types are visible
+#[allow(dead_code)]
+#[path = "../../day9/src"]
+mod day9 {
+    include!("../../day9/src/main.rs");
+
+    pub fn fuzz(sequences: &str) {
+        let data: Vec<&str> = sequences.lines().collect();
+        if let Ok(sequences) = parse_lines(&data) {
+            for sequence in sequences {
+                sequence.next_step();
+                sequence.previous_step();
+            }
+        }
+    }
+}
+
+fuzz_target!(|sequences: &str| day9::fuzz(sequences));